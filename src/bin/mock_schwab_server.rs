@@ -0,0 +1,48 @@
+use clap::Parser;
+use schwab_auto_trader::{
+    Error,
+    mock::{
+        schwab_api::{Fixtures, MockSchwabServer},
+        streamer::MockStreamer,
+    },
+};
+use tokio::signal::ctrl_c;
+
+// Serves the Schwab API from fixtures so the trader can be run against it by setting
+// api_base_url in the config to the printed address.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "127.0.0.1:8081")]
+    bind_address: String,
+
+    #[arg(short, long, default_value = "tests/fixtures/schwab")]
+    fixtures_path: String,
+
+    #[arg(short, long, default_value = "127.0.0.1:8082")]
+    streamer_address: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    env_logger::init();
+
+    let args = Args::parse();
+    let server = MockSchwabServer::start(
+        args.bind_address.parse()?,
+        Fixtures::load(&args.fixtures_path)?,
+    )
+    .await?;
+    log::info!("Serving mock Schwab API on: {}", server.base_url());
+    let streamer = MockStreamer::start(
+        args.streamer_address.parse()?,
+        std::time::Duration::from_secs(10),
+    )
+    .await?;
+    server.set_streamer_url(&streamer.url());
+    log::info!("Serving mock Schwab streamer on: {}", streamer.url());
+
+    ctrl_c().await?;
+
+    Ok(())
+}
@@ -1,4 +1,12 @@
-use crate::Error;
+use crate::{
+    Error,
+    schwab::{
+        endpoints::DEFAULT_BASE_URL, market_overview::MarketOverviewConfig,
+        rate_limiter::RateLimitConfig, retry::RetryPolicy,
+        schemas::market_data::instruments::Instrument, streamer::StreamerConfig,
+    },
+};
+use chrono::NaiveTime;
 use serde::Deserialize;
 use serde_json::Deserializer as jsonDe;
 use std::{collections::HashMap, fs};
@@ -13,6 +21,29 @@ pub struct Config {
     pub cert_path: String,
     pub key_path: String,
     pub trading_config: TradingConfig,
+
+    // Root of the Schwab API, used for both the trader/marketdata and oauth endpoints.
+    #[serde(default = "default_api_base_url")]
+    pub api_base_url: String,
+
+    #[serde(default)]
+    pub rate_limits: RateLimitConfig,
+
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
+
+    // Streams account activity from Schwab when set, e.g. `"streamer": {}` for the defaults.
+    #[serde(default)]
+    pub streamer: Option<StreamerConfig>,
+
+    // Shows the top movers of a few indices on the dashboard when set, e.g.
+    // `"market_overview": {}` for the Dow, Nasdaq and S&P 500.
+    #[serde(default)]
+    pub market_overview: Option<MarketOverviewConfig>,
+}
+
+fn default_api_base_url() -> String {
+    DEFAULT_BASE_URL.to_string()
 }
 
 impl Config {
@@ -30,6 +61,26 @@ pub struct TradingConfig {
 
     // will be -infinity to infinity
     pub target_cash_balance: f64,
+
+    // Place orders against a simulated broker instead of the real account.
+    #[serde(default)]
+    pub paper_trading: bool,
+    // Where the simulated account is persisted between runs; kept in memory only when unset.
+    #[serde(default)]
+    pub paper_trading_state_path: Option<String>,
+
+    // Directory where daily candles of every traded symbol are kept; no history is synced when
+    // unset.
+    #[serde(default)]
+    pub candle_store_path: Option<String>,
+
+    // File where the instruments of the traded symbols are cached; they are fetched from Schwab on
+    // every start when unset.
+    #[serde(default)]
+    pub instrument_cache_path: Option<String>,
+
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
 }
 
 impl TradingConfig {
@@ -88,8 +139,91 @@ impl TradingConfig {
             return Err(format!("allocations don't added up to '{}%' not 100%", sum).into());
         }
 
+        for window in self.schedule.trading_windows.iter() {
+            let (start, end) = window.times()?;
+            if start >= end {
+                return Err(format!(
+                    "Trading window '{}' to '{}' ends before it starts",
+                    window.start, window.end
+                )
+                .into());
+            }
+        }
+
         Ok(())
     }
+
+    // Checks the collections against the instruments Schwab returned for their symbols. This
+    // needs the API, so it runs once the account manager is authorized rather than with validate.
+    pub fn validate_instruments(
+        &self,
+        instruments: &HashMap<String, Instrument>,
+    ) -> Result<(), Error> {
+        for collection in self.trading_collections.iter() {
+            for symbol in collection.collection.iter() {
+                match instruments.get(symbol) {
+                    None => {
+                        return Err(format!(
+                            "'{}' in collection '{}' is not a known symbol",
+                            symbol, collection.id
+                        )
+                        .into());
+                    }
+                    Some(instrument) if !instrument.is_equity_or_etf() => {
+                        return Err(format!(
+                            "'{}' in collection '{}' is a {}, not an equity or ETF",
+                            symbol, collection.id, instrument.asset_type
+                        )
+                        .into());
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// How often the trader wakes up in each phase of the market day, and when it may place orders.
+// The regular session is polled at the rate the account manager is started with.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    pub pre_market_poll_seconds: u64,
+    pub after_hours_poll_seconds: u64,
+    pub closed_poll_seconds: u64,
+
+    // Orders are only placed during the regular session, and only inside these windows when any
+    // are given.
+    pub trading_windows: Vec<TradingWindow>,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            pre_market_poll_seconds: 60,
+            after_hours_poll_seconds: 300,
+            closed_poll_seconds: 1800,
+            trading_windows: Vec::new(),
+        }
+    }
+}
+
+// Times of day in New York time, formatted as HH:MM.
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct TradingWindow {
+    pub start: String,
+    pub end: String,
+}
+
+impl TradingWindow {
+    pub fn times(&self) -> Result<(NaiveTime, NaiveTime), Error> {
+        let parse = |time: &str| {
+            NaiveTime::parse_from_str(time, "%H:%M")
+                .map_err(|e| format!("Invalid trading window time '{}': {}", time, e))
+        };
+        Ok((parse(&self.start)?, parse(&self.end)?))
+    }
 }
 
 // A collection of stocks under an ID, suports multiple stocks for Tax Loss Harvesting in the
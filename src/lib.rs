@@ -1,4 +1,5 @@
 pub mod config;
+pub mod mock;
 pub mod oauth;
 pub mod schwab;
 pub mod server;
@@ -3,7 +3,7 @@ use schwab_auto_trader::{
     Error,
     config::Config,
     oauth::{token, token_storage, utils},
-    schwab::account_manager::AccountManager,
+    schwab::{account_manager::AccountManager, cassette::Cassette, endpoints::Endpoints},
     server::server,
 };
 use tokio::signal::{
@@ -16,6 +16,20 @@ use tokio::signal::{
 struct Args {
     #[arg(short, long)]
     config_file_path: String,
+
+    // Overrides the config to trade against the simulated broker.
+    #[arg(short, long)]
+    paper_trading: bool,
+
+    // Records every Schwab request and response to this file, with tokens and account numbers
+    // redacted, so the session can be replayed offline.
+    #[arg(long)]
+    record_cassette: Option<String>,
+
+    // Answers Schwab requests from a recorded cassette instead of Schwab, to reproduce a session
+    // offline.
+    #[arg(long, conflicts_with = "record_cassette")]
+    replay_cassette: Option<String>,
 }
 
 // Next steps; since we can't access the basis of the stocks lots, we can only do Tax Loss
@@ -32,7 +46,8 @@ async fn main() -> Result<(), Error> {
     env_logger::init();
 
     let args = Args::parse();
-    let config = Config::load(&args.config_file_path)?;
+    let mut config = Config::load(&args.config_file_path)?;
+    config.trading_config.paper_trading |= args.paper_trading;
     log::info!("Validating config");
     config.validate()?;
     log::info!("Config validated");
@@ -44,6 +59,7 @@ async fn main() -> Result<(), Error> {
             config.client_id,
             config.client_secret,
             config.redirect_address,
+            &config.api_base_url,
         )?,
         token_storage::TokenStorage::load(config.token_file_path)?,
     )));
@@ -52,10 +68,30 @@ async fn main() -> Result<(), Error> {
         .await;
     token::OauthManager::spawn_token_refresher(om.clone(), core::time::Duration::from_secs(60))
         .await;
-    let am = std::sync::Arc::new(tokio::sync::Mutex::new(AccountManager::new(
+    let mut account_manager = AccountManager::new(
         config.trading_config.clone(),
+        Endpoints::new(&config.api_base_url),
         om.clone(),
-    )));
+    )?
+    .with_rate_limits(&config.rate_limits)
+    .with_retry_policy(&config.retry_policy);
+    if let Some(streamer_config) = config.streamer.clone() {
+        log::info!("Streaming account activity from Schwab");
+        account_manager = account_manager.with_streamer(streamer_config);
+    }
+    if let Some(market_overview_config) = config.market_overview.clone() {
+        log::info!("Showing a market overview on the dashboard");
+        account_manager = account_manager.with_market_overview(market_overview_config);
+    }
+    if let Some(path) = args.record_cassette {
+        log::info!("Recording Schwab interactions to '{}'", path);
+        account_manager = account_manager.with_cassette(Cassette::record(&path));
+    }
+    if let Some(path) = args.replay_cassette {
+        log::info!("Replaying Schwab interactions from '{}'", path);
+        account_manager = account_manager.with_cassette(Cassette::replay(&path)?);
+    }
+    let am = std::sync::Arc::new(tokio::sync::Mutex::new(account_manager));
 
     let jh = tokio::spawn(server::run_server(
         config.bind_address.parse()?,
@@ -0,0 +1,2 @@
+pub mod schwab_api;
+pub mod streamer;
@@ -0,0 +1,983 @@
+use crate::Error;
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    Method, Request, Response, StatusCode,
+    body::{Bytes, Incoming},
+    header,
+};
+use hyper_util::rt::TokioIo;
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    fs,
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+// Canned responses served by the mock Schwab API, loaded from one json file per endpoint.
+#[derive(Clone, Debug, Default)]
+pub struct Fixtures {
+    pub account_numbers: Value,
+    pub accounts: Value,
+    pub orders: Value,
+    pub transactions: Value,
+    pub quotes: Value,
+    // Daily candles keyed by symbol.
+    pub price_history: Value,
+    // Option contracts keyed by underlying, with expirations given in days from today.
+    pub option_chains: Value,
+    pub user_preference: Value,
+    // Instruments with their fundamentals, which are left out unless asked for.
+    pub instruments: Value,
+    // Movers keyed by index, e.g. "$DJI", in no particular order.
+    pub movers: Value,
+    pub token: Value,
+}
+
+impl Fixtures {
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let read = |name: &str| -> Result<Value, Error> {
+            let file = Path::new(path).join(name);
+            Ok(serde_json::from_reader(fs::File::open(&file).map_err(
+                |e| format!("failed to open fixture {}: {}", file.display(), e),
+            )?)?)
+        };
+
+        Ok(Self {
+            account_numbers: read("account_numbers.json")?,
+            accounts: read("accounts.json")?,
+            orders: read("orders.json")?,
+            transactions: read("transactions.json")?,
+            quotes: read("quotes.json")?,
+            price_history: read("price_history.json")?,
+            option_chains: read("option_chains.json")?,
+            user_preference: read("user_preference.json")?,
+            instruments: read("instruments.json")?,
+            movers: read("movers.json")?,
+            token: read("token.json")?,
+        })
+    }
+
+    fn account_number_for_hash(&self, account_hash: &str) -> Option<&str> {
+        self.account_numbers
+            .as_array()?
+            .iter()
+            .find(|an| an["hashValue"] == account_hash)?["accountNumber"]
+            .as_str()
+    }
+
+    fn account(&self, account_hash: &str) -> Option<Value> {
+        let account_number = self.account_number_for_hash(account_hash)?;
+        self.accounts
+            .as_array()?
+            .iter()
+            .find(|a| a["securitiesAccount"]["accountNumber"] == account_number)
+            .cloned()
+    }
+}
+
+// Everything the mock has seen, so tests can assert on what the client sent.
+#[derive(Debug, Default)]
+pub struct MockState {
+    pub requests: Vec<String>,
+    pub placed_orders: Vec<Value>,
+    // The placed orders as they are listed by the orders endpoint.
+    pub orders: Vec<Value>,
+    // Leaves placed orders working instead of filling them instantly, so they can be canceled or
+    // replaced.
+    pub hold_orders: bool,
+    // Order previews for these symbols come back with a reject.
+    pub rejected_symbols: Vec<String>,
+    // The market is reported open for the whole day around now unless this is set.
+    pub market_closed: bool,
+    pub failures: Vec<InjectedFailure>,
+    // Points the user preference's streamer info at a local streamer.
+    pub streamer_url: Option<String>,
+}
+
+// Fails the next `times` requests to a path with the given status. With `after_handling` set the
+// request is still handled and only the response is lost, like a timeout after Schwab placed an
+// order.
+#[derive(Debug, Clone)]
+pub struct InjectedFailure {
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    pub times: usize,
+    pub after_handling: bool,
+}
+
+impl MockState {
+    fn place_order(&mut self, account_number: &str, request: Value) -> i64 {
+        let order_id = 2000000001 + self.placed_orders.len() as i64;
+        self.orders.push(listed_order(
+            order_id,
+            account_number,
+            &request,
+            !self.hold_orders,
+        ));
+        self.placed_orders.push(request);
+        order_id
+    }
+
+    fn take_failure(&mut self, method: &Method, path: &str) -> Option<InjectedFailure> {
+        let failure = self
+            .failures
+            .iter_mut()
+            .find(|f| f.times > 0 && f.method == method.as_str() && f.path == path)?;
+        failure.times -= 1;
+        Some(failure.clone())
+    }
+
+    fn order_mut(&mut self, order_id: i64) -> Option<&mut Value> {
+        self.orders
+            .iter_mut()
+            .find(|o| o["orderId"].as_i64() == Some(order_id))
+    }
+}
+
+// Turns an order request into the order Schwab would list, either filled or still working.
+fn listed_order(order_id: i64, account_number: &str, request: &Value, filled: bool) -> Value {
+    let mut order = request.clone();
+    let quantity = request["orderLegCollection"]
+        .as_array()
+        .and_then(|legs| legs.first())
+        .and_then(|leg| leg["quantity"].as_f64())
+        .unwrap_or(0.0);
+    let now = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%z").to_string();
+
+    if let Some(o) = order.as_object_mut() {
+        o.insert("orderId".to_string(), json!(order_id));
+        o.insert(
+            "status".to_string(),
+            json!(if filled { "FILLED" } else { "WORKING" }),
+        );
+        o.insert("quantity".to_string(), json!(quantity));
+        o.insert(
+            "filledQuantity".to_string(),
+            json!(if filled { quantity } else { 0.0 }),
+        );
+        o.insert(
+            "remainingQuantity".to_string(),
+            json!(if filled { 0.0 } else { quantity }),
+        );
+        o.insert("complexOrderStrategyType".to_string(), json!("NONE"));
+        o.insert("requestedDestination".to_string(), json!("AUTO"));
+        o.insert("destinationLinkName".to_string(), json!("MOCK"));
+        o.insert("cancelable".to_string(), json!(!filled));
+        o.insert("editable".to_string(), json!(!filled));
+        o.insert("enteredTime".to_string(), json!(now));
+        if filled {
+            o.insert("closeTime".to_string(), json!(now));
+        }
+        o.insert(
+            "accountNumber".to_string(),
+            json!(account_number.parse::<i64>().unwrap_or(0)),
+        );
+        o.insert("orderActivityCollection".to_string(), json!([]));
+    }
+    order
+}
+
+// A local stand-in for the Schwab trader, marketdata and oauth APIs, served over plain http.
+pub struct MockSchwabServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    cancel_token: CancellationToken,
+}
+
+impl MockSchwabServer {
+    pub async fn start(addr: SocketAddr, fixtures: Fixtures) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let cancel_token = CancellationToken::new();
+
+        tokio::spawn({
+            let state = state.clone();
+            let cancel_token = cancel_token.clone();
+            async move {
+                if let Err(e) = run(listener, fixtures, state, cancel_token).await {
+                    log::error!("Mock Schwab server failed: {}", e);
+                }
+            }
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            cancel_token,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    pub fn placed_orders(&self) -> Vec<Value> {
+        self.state.lock().unwrap().placed_orders.clone()
+    }
+
+    pub fn orders(&self) -> Vec<Value> {
+        self.state.lock().unwrap().orders.clone()
+    }
+
+    pub fn set_hold_orders(&self, hold_orders: bool) {
+        self.state.lock().unwrap().hold_orders = hold_orders;
+    }
+
+    pub fn set_market_closed(&self, market_closed: bool) {
+        self.state.lock().unwrap().market_closed = market_closed;
+    }
+
+    pub fn set_streamer_url(&self, url: &str) {
+        self.state.lock().unwrap().streamer_url = Some(url.to_string());
+    }
+
+    pub fn fail_requests(&self, method: &str, path: &str, status: u16, times: usize) {
+        self.inject_failure(method, path, status, times, false);
+    }
+
+    pub fn lose_responses(&self, method: &str, path: &str, status: u16, times: usize) {
+        self.inject_failure(method, path, status, times, true);
+    }
+
+    fn inject_failure(
+        &self,
+        method: &str,
+        path: &str,
+        status: u16,
+        times: usize,
+        after_handling: bool,
+    ) {
+        self.state.lock().unwrap().failures.push(InjectedFailure {
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            times,
+            after_handling,
+        });
+    }
+
+    pub fn set_rejected_symbols(&self, symbols: &[&str]) {
+        self.state.lock().unwrap().rejected_symbols =
+            symbols.iter().map(|s| s.to_string()).collect();
+    }
+}
+
+impl Drop for MockSchwabServer {
+    fn drop(&mut self) {
+        self.cancel_token.cancel();
+    }
+}
+
+pub async fn run(
+    listener: TcpListener,
+    fixtures: Fixtures,
+    state: Arc<Mutex<MockState>>,
+    cancel_token: CancellationToken,
+) -> Result<(), Error> {
+    let fixtures = Arc::new(fixtures);
+    loop {
+        tokio::select! {
+            _ = cancel_token.cancelled() => return Ok(()),
+            connection = listener.accept() => {
+                let (stream, _) = connection?;
+                let fixtures = fixtures.clone();
+                let state = state.clone();
+                tokio::task::spawn(async move {
+                    if let Err(err) = hyper::server::conn::http1::Builder::new()
+                        .serve_connection(
+                            TokioIo::new(stream),
+                            hyper::service::service_fn(move |req| {
+                                handle(req, fixtures.clone(), state.clone())
+                            }),
+                        )
+                        .await
+                    {
+                        log::warn!("Error serving mock connection: {}", err);
+                    }
+                });
+            },
+        }
+    }
+}
+
+fn json_response(status: StatusCode, body: &Value) -> Response<Full<Bytes>> {
+    let mut r = Response::new(Full::from(body.to_string()));
+    *r.status_mut() = status;
+    r.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    r
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Full<Bytes>> {
+    json_response(status, &json!({ "message": message, "errors": [message] }))
+}
+
+fn injected_failure_response(failure: &InjectedFailure) -> Response<Full<Bytes>> {
+    let status = StatusCode::from_u16(failure.status).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut r = error_response(status, "Injected failure");
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        r.headers_mut()
+            .insert(header::RETRY_AFTER, header::HeaderValue::from_static("1"));
+    }
+    r
+}
+
+// Orders are placed and replaced with an empty body and a Location header pointing at the order.
+fn created_order_response(
+    host: &str,
+    account_hash: &str,
+    order_id: i64,
+) -> Result<Response<Full<Bytes>>, Error> {
+    let mut r = Response::new(Full::default());
+    *r.status_mut() = StatusCode::CREATED;
+    r.headers_mut().insert(
+        header::LOCATION,
+        header::HeaderValue::from_str(&format!(
+            "http://{}/trader/v1/accounts/{}/orders/{}",
+            host, account_hash, order_id
+        ))?,
+    );
+    Ok(r)
+}
+
+// Prices the order at the ask of the quote fixtures, rejecting it when a symbol has no quote or
+// was set up to be rejected.
+fn preview_order(fixtures: &Fixtures, state: &MockState, order: &Value) -> Value {
+    let mut order_value = 0.0;
+    let mut rejects = Vec::new();
+    for leg in order["orderLegCollection"].as_array().into_iter().flatten() {
+        let symbol = leg["instrument"]["symbol"].as_str().unwrap_or("");
+        match fixtures.quotes.get(symbol) {
+            _ if state.rejected_symbols.iter().any(|s| s == symbol) => rejects.push(json!({
+                "validationRuleName": "MockReject",
+                "message": format!("Orders for {} are rejected", symbol),
+                "originalSeverity": "REJECT",
+            })),
+            Some(quote) => {
+                order_value += leg["quantity"].as_f64().unwrap_or(0.0)
+                    * quote["quote"]["askPrice"].as_f64().unwrap_or(0.0)
+            }
+            None => rejects.push(json!({
+                "validationRuleName": "InvalidSymbol",
+                "message": format!("Symbol {} not found", symbol),
+                "originalSeverity": "REJECT",
+            })),
+        }
+    }
+
+    json!({
+        "orderStrategy": {
+            "orderBalance": {
+                "orderValue": order_value,
+                "projectedCommission": 0.0,
+            },
+            "orderValue": order_value,
+        },
+        "orderValidationResult": { "rejects": rejects },
+        "commissionAndFee": {
+            "commission": { "commissionLegs": [{ "commissionValues": [] }] },
+            "fee": { "feeLegs": [{ "feeValues": [{ "value": 0.01, "type": "SEC_FEE" }] }] },
+        },
+    })
+}
+
+// Builds a chain from the contract fixtures, dated relative to today and limited to the requested
+// contract type and expiration dates.
+fn option_chain(fixtures: &Fixtures, query: &HashMap<String, String>) -> Option<Value> {
+    let symbol = query.get("symbol")?;
+    let fixture = fixtures.option_chains.get(symbol)?;
+    let today = chrono::Utc::now().date_naive();
+    let date = |name: &str| {
+        query
+            .get(name)
+            .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    };
+    let (from_date, to_date) = (date("fromDate"), date("toDate"));
+    let contract_type = query
+        .get("contractType")
+        .map(|s| s.as_str())
+        .unwrap_or("ALL");
+
+    let mut maps = (serde_json::Map::new(), serde_json::Map::new());
+    for contract in fixture["contracts"].as_array().into_iter().flatten() {
+        let put_call = contract["putCall"].as_str().unwrap_or("");
+        let days = contract["daysToExpiration"].as_u64().unwrap_or(0);
+        let expiration_date = today + chrono::Days::new(days);
+        if (contract_type != "ALL" && contract_type != put_call)
+            || from_date.is_some_and(|d| expiration_date < d)
+            || to_date.is_some_and(|d| expiration_date > d)
+        {
+            continue;
+        }
+
+        let strike_price = contract["strikePrice"].as_f64().unwrap_or(0.0);
+        let mut contract = contract.clone();
+        contract["symbol"] = json!(format!(
+            "{:<6}{}{}{:08}",
+            symbol,
+            expiration_date.format("%y%m%d"),
+            &put_call[..1],
+            (strike_price * 1000.0) as i64
+        ));
+        contract["expirationDate"] = json!(format!("{}T20:00:00.000+00:00", expiration_date));
+        contract["expirationType"] = json!("S");
+        let map = match put_call {
+            "CALL" => &mut maps.0,
+            _ => &mut maps.1,
+        };
+        map.entry(format!("{}:{}", expiration_date, days))
+            .or_insert(json!({}))
+            .as_object_mut()?
+            .entry(format!("{:.1}", strike_price))
+            .or_insert(json!([]))
+            .as_array_mut()?
+            .push(contract);
+    }
+
+    Some(json!({
+        "symbol": symbol,
+        "status": "SUCCESS",
+        "strategy": "SINGLE",
+        "isDelayed": false,
+        "isIndex": false,
+        "underlyingPrice": fixture["underlyingPrice"],
+        "volatility": fixture["volatility"],
+        "callExpDateMap": maps.0,
+        "putExpDateMap": maps.1,
+    }))
+}
+
+// The instruments matching a search like Schwab's: exact symbols for the symbol and fundamental
+// searches, a case insensitive substring of the description for desc-search, and regular
+// expressions matched against the whole symbol or anywhere in the description.
+fn search_instruments(
+    fixtures: &Fixtures,
+    symbol: &str,
+    projection: &str,
+) -> Result<Value, String> {
+    let regex = |pattern: &str| regex::Regex::new(pattern).map_err(|e| e.to_string());
+    let matches: Box<dyn Fn(&Value) -> bool> = match projection {
+        "symbol-search" | "fundamental" => {
+            let symbols: Vec<String> = symbol.split(',').map(|s| s.trim().to_uppercase()).collect();
+            Box::new(move |i| {
+                i["symbol"]
+                    .as_str()
+                    .is_some_and(|s| symbols.iter().any(|symbol| symbol == s))
+            })
+        }
+        "symbol-regex" => {
+            let regex = regex(&format!("^(?:{})$", symbol))?;
+            Box::new(move |i| i["symbol"].as_str().is_some_and(|s| regex.is_match(s)))
+        }
+        "desc-search" | "search" => {
+            let search = symbol.to_lowercase();
+            Box::new(move |i| {
+                i["description"]
+                    .as_str()
+                    .is_some_and(|d| d.to_lowercase().contains(&search))
+                    || (projection == "search"
+                        && i["symbol"]
+                            .as_str()
+                            .is_some_and(|s| s.eq_ignore_ascii_case(&search)))
+            })
+        }
+        "desc-regex" => {
+            let regex = regex(symbol)?;
+            Box::new(move |i| i["description"].as_str().is_some_and(|d| regex.is_match(d)))
+        }
+        _ => return Err(format!("Unknown projection '{}'", projection)),
+    };
+
+    Ok(json!({
+        "instruments": fixtures
+            .instruments
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|i| matches(i))
+            .map(|i| instrument(i, projection == "fundamental"))
+            .collect::<Vec<Value>>()
+    }))
+}
+
+fn instrument(fixture: &Value, fundamental: bool) -> Value {
+    let mut instrument = fixture.clone();
+    if !fundamental && let Some(i) = instrument.as_object_mut() {
+        i.remove("fundamental");
+    }
+    instrument
+}
+
+// The movers of an index ordered like Schwab orders them, with only gainers or losers when sorting
+// by percent change.
+fn movers(fixtures: &Fixtures, index: &str, sort: &str) -> Option<Value> {
+    let mut movers: Vec<Value> = fixtures.movers.get(index)?.as_array()?.clone();
+    let field = |m: &Value, name: &str| m[name].as_f64().unwrap_or(0.0);
+    match sort {
+        "VOLUME" => movers.sort_by(|a, b| field(b, "volume").total_cmp(&field(a, "volume"))),
+        "TRADES" => movers.sort_by(|a, b| field(b, "trades").total_cmp(&field(a, "trades"))),
+        "PERCENT_CHANGE_UP" => {
+            movers.retain(|m| field(m, "netPercentChange") > 0.0);
+            movers.sort_by(|a, b| {
+                field(b, "netPercentChange").total_cmp(&field(a, "netPercentChange"))
+            });
+        }
+        "PERCENT_CHANGE_DOWN" => {
+            movers.retain(|m| field(m, "netPercentChange") < 0.0);
+            movers.sort_by(|a, b| {
+                field(a, "netPercentChange").total_cmp(&field(b, "netPercentChange"))
+            });
+        }
+        _ => (),
+    }
+    Some(json!({ "screeners": movers }))
+}
+
+// The distinct expirations of the contract fixtures.
+fn expiration_chain(fixtures: &Fixtures, symbol: &str) -> Option<Value> {
+    let today = chrono::Utc::now().date_naive();
+    let mut days: Vec<u64> = fixtures.option_chains.get(symbol)?["contracts"]
+        .as_array()?
+        .iter()
+        .filter_map(|c| c["daysToExpiration"].as_u64())
+        .collect();
+    days.sort();
+    days.dedup();
+
+    Some(json!({
+        "status": "SUCCESS",
+        "expirationList": days
+            .iter()
+            .map(|d| json!({
+                "expirationDate": (today + chrono::Days::new(*d)).to_string(),
+                "daysToExpiration": d,
+                "expirationType": "S",
+                "standard": true,
+                "settlementType": "P",
+                "optionRoots": symbol,
+            }))
+            .collect::<Vec<Value>>(),
+    }))
+}
+
+// Equity hours with a regular session around the current time, so tests can trade whenever they
+// run.
+fn market_hours(state: &MockState, date: &str) -> Value {
+    let now = chrono::Utc::now();
+    let time = |t: chrono::DateTime<chrono::Utc>| t.format("%Y-%m-%dT%H:%M:%S%:z").to_string();
+    let hours = match state.market_closed {
+        true => json!({
+            "date": date,
+            "marketType": "EQUITY",
+            "product": "equity",
+            "isOpen": false,
+        }),
+        false => json!({
+            "date": date,
+            "marketType": "EQUITY",
+            "product": "EQ",
+            "productName": "equity",
+            "isOpen": true,
+            "sessionHours": {
+                "regularMarket": [{
+                    "start": time(now - chrono::Duration::hours(12)),
+                    "end": time(now + chrono::Duration::hours(12)),
+                }],
+            },
+        }),
+    };
+    json!({ "equity": { hours["product"].as_str().unwrap_or("EQ"): hours } })
+}
+
+// The fixture orders followed by everything placed through the mock.
+fn all_orders(fixtures: &Fixtures, state: &Mutex<MockState>) -> Vec<Value> {
+    fixtures
+        .orders
+        .as_array()
+        .into_iter()
+        .flatten()
+        .chain(state.lock().unwrap().orders.iter())
+        .cloned()
+        .collect()
+}
+
+fn parse_time(time: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S%.f%z")
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(time))
+        .ok()
+        .map(|t| t.to_utc())
+}
+
+// The items whose time falls inside the range given by two query parameters, up to maxResults of
+// them. Like Schwab, a range longer than `max_days` is rejected.
+fn in_query_range(
+    items: Vec<Value>,
+    time_key: &str,
+    query: &HashMap<String, String>,
+    (from_key, to_key): (&str, &str),
+    max_days: i64,
+) -> Response<Full<Bytes>> {
+    let from = query.get(from_key).and_then(|t| parse_time(t));
+    let to = query.get(to_key).and_then(|t| parse_time(t));
+    if let (Some(from), Some(to)) = (from, to)
+        && to - from > chrono::Duration::days(max_days)
+    {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            &format!("The date range can't be longer than {} days", max_days),
+        );
+    }
+
+    json_response(
+        StatusCode::OK,
+        &Value::Array(
+            items
+                .into_iter()
+                .filter(|item| match item[time_key].as_str().and_then(parse_time) {
+                    Some(time) => from.is_none_or(|f| time >= f) && to.is_none_or(|t| time <= t),
+                    None => true,
+                })
+                .take(
+                    query
+                        .get("maxResults")
+                        .and_then(|m| m.parse().ok())
+                        .unwrap_or(usize::MAX),
+                )
+                .collect(),
+        ),
+    )
+}
+
+fn query_pairs(req: &Request<Incoming>) -> HashMap<String, String> {
+    url::form_urlencoded::parse(req.uri().query().unwrap_or("").as_bytes())
+        .into_owned()
+        .collect()
+}
+
+async fn handle(
+    req: Request<Incoming>,
+    fixtures: Arc<Fixtures>,
+    state: Arc<Mutex<MockState>>,
+) -> Result<Response<Full<Bytes>>, Error> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    state
+        .lock()
+        .unwrap()
+        .requests
+        .push(format!("{} {}", method, req.uri()));
+
+    if path == "/v1/oauth/token" {
+        return Ok(match method {
+            Method::POST => json_response(StatusCode::OK, &fixtures.token),
+            _ => error_response(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed"),
+        });
+    }
+
+    if !req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("Bearer "))
+    {
+        return Ok(error_response(
+            StatusCode::UNAUTHORIZED,
+            "Client not authorized",
+        ));
+    }
+
+    let failure = state.lock().unwrap().take_failure(&method, &path);
+    match failure {
+        Some(failure) if !failure.after_handling => Ok(injected_failure_response(&failure)),
+        Some(failure) => {
+            route(req, method, path, fixtures, state).await?;
+            Ok(injected_failure_response(&failure))
+        }
+        None => route(req, method, path, fixtures, state).await,
+    }
+}
+
+async fn route(
+    req: Request<Incoming>,
+    method: Method,
+    path: String,
+    fixtures: Arc<Fixtures>,
+    state: Arc<Mutex<MockState>>,
+) -> Result<Response<Full<Bytes>>, Error> {
+    let query = query_pairs(&req);
+    let host = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("localhost")
+        .to_string();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    Ok(match (&method, segments.as_slice()) {
+        (&Method::GET, ["trader", "v1", "accounts"]) => {
+            json_response(StatusCode::OK, &fixtures.accounts)
+        }
+        (&Method::GET, ["trader", "v1", "accounts", "accountNumbers"]) => {
+            json_response(StatusCode::OK, &fixtures.account_numbers)
+        }
+        (&Method::GET, ["trader", "v1", "userPreference"]) => {
+            let mut preference = fixtures.user_preference.clone();
+            if let Some(url) = state.lock().unwrap().streamer_url.clone()
+                && let Some(info) = preference["streamerInfo"].as_array_mut()
+            {
+                info.iter_mut()
+                    .for_each(|i| i["streamerSocketUrl"] = Value::String(url.clone()));
+            }
+            json_response(StatusCode::OK, &preference)
+        }
+        (&Method::GET, ["trader", "v1", "accounts", account_hash]) => {
+            match fixtures.account(account_hash) {
+                Some(account) => json_response(StatusCode::OK, &account),
+                None => error_response(StatusCode::NOT_FOUND, "Account not found"),
+            }
+        }
+        (_, ["trader", "v1", "accounts", account_hash, ..])
+            if fixtures.account_number_for_hash(account_hash).is_none() =>
+        {
+            error_response(StatusCode::NOT_FOUND, "Account not found")
+        }
+        (&Method::GET, ["trader", "v1", "orders"] | ["trader", "v1", "accounts", _, "orders"]) => {
+            in_query_range(
+                all_orders(&fixtures, &state)
+                    .into_iter()
+                    .filter(|o| query.get("status").is_none_or(|s| o["status"] == *s))
+                    .collect(),
+                "enteredTime",
+                &query,
+                ("fromEnteredTime", "toEnteredTime"),
+                60,
+            )
+        }
+        (&Method::POST, ["trader", "v1", "accounts", account_hash, "orders"]) => {
+            let body = req.into_body().collect().await?.to_bytes();
+            match serde_json::from_slice::<Value>(&body) {
+                Ok(order) => {
+                    let account_number =
+                        fixtures.account_number_for_hash(account_hash).unwrap_or("");
+                    let order_id = state.lock().unwrap().place_order(account_number, order);
+                    created_order_response(&host, account_hash, order_id)?
+                }
+                Err(e) => error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+            }
+        }
+        (&Method::POST, ["trader", "v1", "accounts", _, "previewOrder"]) => {
+            let body = req.into_body().collect().await?.to_bytes();
+            match serde_json::from_slice::<Value>(&body) {
+                Ok(order) => json_response(
+                    StatusCode::OK,
+                    &preview_order(&fixtures, &state.lock().unwrap(), &order),
+                ),
+                Err(e) => error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+            }
+        }
+        (&Method::GET, ["trader", "v1", "accounts", _, "orders", order_id]) => {
+            match all_orders(&fixtures, &state)
+                .into_iter()
+                .find(|o| o["orderId"].as_i64() == order_id.parse().ok())
+            {
+                Some(order) => json_response(StatusCode::OK, &order),
+                None => error_response(StatusCode::NOT_FOUND, "Order not found"),
+            }
+        }
+        (&Method::DELETE, ["trader", "v1", "accounts", _, "orders", order_id]) => {
+            let mut state = state.lock().unwrap();
+            match order_id.parse().ok().and_then(|id| state.order_mut(id)) {
+                Some(order) if order["cancelable"] == true => {
+                    order["status"] = json!("CANCELED");
+                    order["cancelable"] = json!(false);
+                    order["editable"] = json!(false);
+                    Response::new(Full::default())
+                }
+                Some(_) => error_response(StatusCode::BAD_REQUEST, "Order is not cancelable"),
+                None => error_response(StatusCode::NOT_FOUND, "Order not found"),
+            }
+        }
+        (&Method::PUT, ["trader", "v1", "accounts", account_hash, "orders", order_id]) => {
+            let body = req.into_body().collect().await?.to_bytes();
+            match serde_json::from_slice::<Value>(&body) {
+                Ok(replacement) => {
+                    let mut state = state.lock().unwrap();
+                    match order_id.parse().ok().and_then(|id| state.order_mut(id)) {
+                        Some(order) if order["editable"] == true => {
+                            order["status"] = json!("REPLACED");
+                            order["cancelable"] = json!(false);
+                            order["editable"] = json!(false);
+                            let account_number =
+                                fixtures.account_number_for_hash(account_hash).unwrap_or("");
+                            let order_id = state.place_order(account_number, replacement);
+                            created_order_response(&host, account_hash, order_id)?
+                        }
+                        Some(_) => error_response(StatusCode::BAD_REQUEST, "Order is not editable"),
+                        None => error_response(StatusCode::NOT_FOUND, "Order not found"),
+                    }
+                }
+                Err(e) => error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+            }
+        }
+        (&Method::GET, ["trader", "v1", "accounts", _, "transactions"]) => in_query_range(
+            fixtures
+                .transactions
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|t| {
+                    query.get("types").is_none_or(|types| {
+                        types.split(',').any(|ty| t["type"].as_str() == Some(ty))
+                    })
+                })
+                .filter(|t| {
+                    query.get("symbol").is_none_or(|symbol| {
+                        t["transferItems"].as_array().is_some_and(|items| {
+                            items
+                                .iter()
+                                .any(|i| i["instrument"]["symbol"].as_str() == Some(symbol))
+                        })
+                    })
+                })
+                .collect(),
+            "time",
+            &query,
+            ("startDate", "endDate"),
+            365,
+        ),
+        (
+            &Method::GET,
+            [
+                "trader",
+                "v1",
+                "accounts",
+                _,
+                "transactions",
+                transaction_id,
+            ],
+        ) => {
+            match fixtures.transactions.as_array().and_then(|transactions| {
+                transactions
+                    .iter()
+                    .find(|t| t["activityId"].as_i64() == transaction_id.parse().ok())
+            }) {
+                Some(transaction) => json_response(StatusCode::OK, transaction),
+                None => error_response(StatusCode::NOT_FOUND, "Transaction not found"),
+            }
+        }
+        (&Method::GET, ["marketdata", "v1", "quotes"]) => {
+            let (known, invalid): (Vec<&str>, Vec<&str>) = query
+                .get("symbols")
+                .map(|s| s.as_str())
+                .unwrap_or("")
+                .split(',')
+                .map(|symbol| symbol.trim())
+                .filter(|symbol| !symbol.is_empty())
+                .partition(|symbol| fixtures.quotes.get(*symbol).is_some());
+
+            let mut quotes: serde_json::Map<String, Value> = known
+                .iter()
+                .map(|symbol| (symbol.to_string(), fixtures.quotes[*symbol].clone()))
+                .collect();
+            if !invalid.is_empty() {
+                quotes.insert("errors".to_string(), json!({ "invalidSymbols": invalid }));
+            }
+            json_response(StatusCode::OK, &Value::Object(quotes))
+        }
+        (&Method::GET, ["marketdata", "v1", "instruments"]) => match search_instruments(
+            &fixtures,
+            query.get("symbol").map(|s| s.as_str()).unwrap_or(""),
+            query.get("projection").map(|p| p.as_str()).unwrap_or(""),
+        ) {
+            Ok(instruments) => json_response(StatusCode::OK, &instruments),
+            Err(e) => error_response(StatusCode::BAD_REQUEST, &e),
+        },
+        (&Method::GET, ["marketdata", "v1", "instruments", cusip]) => {
+            match fixtures.instruments.as_array().and_then(|instruments| {
+                instruments
+                    .iter()
+                    .find(|i| i["cusip"].as_str() == Some(*cusip))
+            }) {
+                Some(fixture) => json_response(
+                    StatusCode::OK,
+                    &json!({ "instruments": [instrument(fixture, false)] }),
+                ),
+                None => error_response(StatusCode::NOT_FOUND, "Instrument not found"),
+            }
+        }
+        (&Method::GET, ["marketdata", "v1", "movers", index]) => match movers(
+            &fixtures,
+            &urlencoding::decode(index).unwrap_or_default(),
+            query.get("sort").map(|s| s.as_str()).unwrap_or(""),
+        ) {
+            Some(movers) => json_response(StatusCode::OK, &movers),
+            None => error_response(StatusCode::BAD_REQUEST, "Unknown index"),
+        },
+        (&Method::GET, ["marketdata", "v1", "markets"]) => json_response(
+            StatusCode::OK,
+            &market_hours(
+                &state.lock().unwrap(),
+                query.get("date").map(|d| d.as_str()).unwrap_or(""),
+            ),
+        ),
+        (&Method::GET, ["marketdata", "v1", "chains"]) => match option_chain(&fixtures, &query) {
+            Some(chain) => json_response(StatusCode::OK, &chain),
+            None => error_response(StatusCode::NOT_FOUND, "No option chain for symbol"),
+        },
+        (&Method::GET, ["marketdata", "v1", "expirationchain"]) => {
+            match query
+                .get("symbol")
+                .and_then(|symbol| expiration_chain(&fixtures, symbol))
+            {
+                Some(chain) => json_response(StatusCode::OK, &chain),
+                None => error_response(StatusCode::NOT_FOUND, "No option chain for symbol"),
+            }
+        }
+        (&Method::GET, ["marketdata", "v1", "pricehistory"]) => {
+            let symbol = query.get("symbol").map(|s| s.as_str()).unwrap_or("");
+            let date = |name: &str| query.get(name).and_then(|d| d.parse::<i64>().ok());
+            let (start_date, end_date) = (date("startDate"), date("endDate"));
+            let candles: Vec<Value> = fixtures
+                .price_history
+                .get(symbol)
+                .and_then(|c| c.as_array())
+                .into_iter()
+                .flatten()
+                .filter(|c| {
+                    let datetime = c["datetime"].as_i64().unwrap_or(0);
+                    start_date.is_none_or(|d| datetime >= d)
+                        && end_date.is_none_or(|d| datetime <= d)
+                })
+                .cloned()
+                .collect();
+            json_response(
+                StatusCode::OK,
+                &json!({ "symbol": symbol, "empty": candles.is_empty(), "candles": candles }),
+            )
+        }
+        (&Method::GET, ["marketdata", "v1", symbol, "quotes"]) => {
+            match fixtures.quotes.get(*symbol) {
+                Some(quote) => json_response(StatusCode::OK, &json!({ *symbol: quote })),
+                None => error_response(StatusCode::NOT_FOUND, "Symbol not found"),
+            }
+        }
+        _ => error_response(StatusCode::NOT_FOUND, "Not found"),
+    })
+}
@@ -0,0 +1,246 @@
+use crate::{
+    Error,
+    schwab::schemas::streamer::{Command, Service, StreamerRequest, StreamerRequests},
+};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use std::{
+    collections::{BTreeSet, HashMap},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{net::TcpListener, sync::broadcast};
+use tokio_tungstenite::{accept_async, tungstenite::protocol::Message};
+use tokio_util::sync::CancellationToken;
+
+// Everything the mock streamer has seen, so tests can assert on what the client sent.
+#[derive(Debug, Default)]
+pub struct MockStreamerState {
+    pub requests: Vec<StreamerRequest>,
+    pub logins: usize,
+    // The keys subscribed to on the latest connection.
+    pub subscriptions: HashMap<Service, BTreeSet<String>>,
+    // Stops sending heartbeats, so the client should give up on the connection.
+    pub heartbeats_paused: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Push {
+    Data(Service, Value),
+    Close,
+}
+
+// A local stand-in for the Schwab streamer, served over plain websockets. It accepts any
+// non-empty access token, acknowledges every request, sends heartbeats and pushes the data tests
+// give it to connections subscribed to the key.
+pub struct MockStreamer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockStreamerState>>,
+    pushes: broadcast::Sender<Push>,
+    cancel_token: CancellationToken,
+}
+
+impl MockStreamer {
+    pub async fn start(addr: SocketAddr, heartbeat_interval: Duration) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockStreamerState::default()));
+        let (pushes, _) = broadcast::channel(1024);
+        let cancel_token = CancellationToken::new();
+
+        tokio::spawn({
+            let state = state.clone();
+            let pushes = pushes.clone();
+            let cancel_token = cancel_token.clone();
+            async move {
+                loop {
+                    tokio::select! {
+                        _ = cancel_token.cancelled() => return,
+                        connection = listener.accept() => {
+                            let Ok((stream, _)) = connection else { continue };
+                            let state = state.clone();
+                            let pushes = pushes.subscribe();
+                            let cancel_token = cancel_token.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = serve(stream, state, pushes, heartbeat_interval, cancel_token).await {
+                                    log::warn!("Error serving mock streamer connection: {}", e);
+                                }
+                            });
+                        },
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            pushes,
+            cancel_token,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("ws://{}/ws", self.addr)
+    }
+
+    pub fn requests(&self) -> Vec<StreamerRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    pub fn logins(&self) -> usize {
+        self.state.lock().unwrap().logins
+    }
+
+    pub fn subscriptions(&self, service: Service) -> Vec<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .subscriptions
+            .get(&service)
+            .map(|keys| keys.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn pause_heartbeats(&self, paused: bool) {
+        self.state.lock().unwrap().heartbeats_paused = paused;
+    }
+
+    // Sends the content, e.g. {"key": "VTI", "3": 230.0}, to connections subscribed to its key.
+    pub fn push(&self, service: Service, content: Value) {
+        let _ = self.pushes.send(Push::Data(service, content));
+    }
+
+    // Closes every open connection, like the streamer going away.
+    pub fn drop_connections(&self) {
+        let _ = self.pushes.send(Push::Close);
+    }
+}
+
+impl Drop for MockStreamer {
+    fn drop(&mut self) {
+        self.cancel_token.cancel();
+    }
+}
+
+fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+fn response(request: &StreamerRequest, code: i64, msg: &str) -> Message {
+    Message::text(
+        json!({"response": [{
+            "service": request.service,
+            "command": request.command,
+            "requestid": request.requestid,
+            "SchwabClientCorrelId": request.correl_id,
+            "timestamp": now_millis(),
+            "content": {"code": code, "msg": msg}
+        }]})
+        .to_string(),
+    )
+}
+
+async fn serve(
+    stream: tokio::net::TcpStream,
+    state: Arc<Mutex<MockStreamerState>>,
+    mut pushes: broadcast::Receiver<Push>,
+    heartbeat_interval: Duration,
+    cancel_token: CancellationToken,
+) -> Result<(), Error> {
+    let mut socket = accept_async(stream).await?;
+    let mut logged_in = false;
+    let mut subscriptions: HashMap<Service, BTreeSet<String>> = HashMap::new();
+    let mut heartbeat = tokio::time::interval(heartbeat_interval);
+
+    loop {
+        tokio::select! {
+            _ = cancel_token.cancelled() => return Ok(()),
+            _ = heartbeat.tick() => {
+                if logged_in && !state.lock().unwrap().heartbeats_paused {
+                    socket
+                        .send(Message::text(
+                            json!({"notify": [{"heartbeat": now_millis().to_string()}]}).to_string(),
+                        ))
+                        .await?;
+                }
+            }
+            push = pushes.recv() => match push {
+                Ok(Push::Data(service, content)) => {
+                    let subscribed = content["key"]
+                        .as_str()
+                        .is_some_and(|key| subscriptions.get(&service).is_some_and(|s| s.contains(key)));
+                    if logged_in && subscribed {
+                        socket
+                            .send(Message::text(
+                                json!({"data": [{
+                                    "service": service,
+                                    "timestamp": now_millis(),
+                                    "command": "SUBS",
+                                    "content": [content]
+                                }]})
+                                .to_string(),
+                            ))
+                            .await?;
+                    }
+                }
+                Ok(Push::Close) | Err(broadcast::error::RecvError::Closed) => {
+                    let _ = socket.close(None).await;
+                    return Ok(());
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => (),
+            },
+            message = socket.next() => {
+                let text = match message {
+                    None | Some(Ok(Message::Close(_))) => return Ok(()),
+                    Some(Err(e)) => return Err(e.into()),
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(_)) => continue,
+                };
+                for request in serde_json::from_str::<StreamerRequests>(&text)?.requests {
+                    state.lock().unwrap().requests.push(request.clone());
+                    let reply = match (request.command, logged_in) {
+                        (Command::Login, _) => {
+                            match request.parameters.get("Authorization").is_some_and(|t| !t.is_empty()) {
+                                true => {
+                                    logged_in = true;
+                                    let mut state = state.lock().unwrap();
+                                    state.logins += 1;
+                                    state.subscriptions.clear();
+                                    response(&request, 0, "server=mock;status=PN")
+                                }
+                                false => response(&request, 3, "Login denied"),
+                            }
+                        }
+                        (_, false) => response(&request, 3, "Not logged in"),
+                        (Command::Logout, true) => {
+                            socket.send(response(&request, 0, "SUCCESS")).await?;
+                            let _ = socket.close(None).await;
+                            return Ok(());
+                        }
+                        (command @ (Command::Subs | Command::Add | Command::Unsubs), true) => {
+                            let keys = request
+                                .parameters
+                                .get("keys")
+                                .map(|k| k.split(',').map(|k| k.to_string()).collect::<Vec<String>>())
+                                .unwrap_or_default();
+                            let subscribed = subscriptions.entry(request.service).or_default();
+                            match command {
+                                Command::Subs => *subscribed = keys.into_iter().collect(),
+                                Command::Add => subscribed.extend(keys),
+                                _ => keys.iter().for_each(|k| {
+                                    subscribed.remove(k);
+                                }),
+                            }
+                            state.lock().unwrap().subscriptions = subscriptions.clone();
+                            response(&request, 0, &format!("{:?} command succeeded", command))
+                        }
+                        (Command::Unknown, true) => response(&request, 22, "Unknown command"),
+                    };
+                    socket.send(reply).await?;
+                }
+            }
+        }
+    }
+}
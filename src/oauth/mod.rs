@@ -1,3 +1,4 @@
 pub mod token;
+pub mod token_provider;
 pub mod token_storage;
 pub mod utils;
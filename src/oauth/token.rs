@@ -124,6 +124,29 @@ impl OauthManager {
         .to_utc()
     }
 
+    // Exchanges the stored refresh token for a new access token and stores it.
+    pub async fn refresh_token(&mut self) -> Result<(), Error> {
+        let refresh_token = match self.token_storage.get_token() {
+            Some(Ok(token)) => token
+                .refresh_token()
+                .cloned()
+                .ok_or("Token has no refresh token")?,
+            Some(Err(e)) => return Err(e),
+            None => return Err("No token to refresh".into()),
+        };
+
+        let token = self
+            .client
+            .exchange_refresh_token(&refresh_token)
+            .request_async(&reqwest::Client::new())
+            .await?;
+        log::info!("token refreshed, storing...");
+        self.token_storage
+            .set_token(&token, Self::calculate_expiration(token.expires_in()))?;
+        log::info!("token refresh complete");
+        Ok(())
+    }
+
     pub async fn spawn_token_refresher(
         s: sSync::Arc<tSync::Mutex<Self>>,
         period: core::time::Duration,
@@ -147,23 +170,8 @@ impl OauthManager {
                                     > (expir - std::time::Duration::from_secs(180))
                                 {
                                     log::info!("Token is expired, refreshing...");
-                                    if let Some(refresh_token) = token.refresh_token() {
-                                        if let Err(e) = async {
-                                            let token = s_handle
-                                                .client
-                                                .exchange_refresh_token(refresh_token)
-                                                .request_async(&reqwest::Client::new())
-                                                .await?;
-                                            log::info!("token refreshed, storing...");
-                                            s_handle.token_storage.set_token(
-                                                &token,
-                                                Self::calculate_expiration(token.expires_in()),
-                                            )?;
-                                            log::info!("token refresh complete");
-                                            Ok::<(), Error>(())
-                                        }
-                                        .await
-                                        {
+                                    if token.refresh_token().is_some() {
+                                        if let Err(e) = s_handle.refresh_token().await {
                                             log::error!(
                                                 "Couldn't refresh oauth token: '{}'; resetting...",
                                                 e
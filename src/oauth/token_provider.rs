@@ -0,0 +1,66 @@
+use crate::{
+    Error,
+    oauth::token::{OauthManager, OauthTokenResponse},
+};
+use oauth2::TokenResponse;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+// Hands API clients the current access token. Clones share the same source, so a token refreshed
+// for one client is used by all of them.
+#[derive(Clone)]
+pub enum TokenProvider {
+    Oauth(Arc<Mutex<OauthManager>>),
+    // A fixed token, for tools and tests that don't go through the oauth flow.
+    Static(OauthTokenResponse),
+}
+
+impl TokenProvider {
+    pub async fn access_token(&self) -> Result<String, Error> {
+        match self {
+            Self::Oauth(om) => {
+                let token = om.lock().await.get_unexpired_token();
+                match token {
+                    Some(token) => Ok(token?.access_token().secret().clone()),
+                    None => Err("Couldn't get valid oauth token".into()),
+                }
+            }
+            Self::Static(token) => Ok(token.access_token().secret().clone()),
+        }
+    }
+
+    pub async fn has_token(&self) -> bool {
+        self.access_token().await.is_ok()
+    }
+
+    // Refreshes the token after Schwab rejected the given access token. When another client
+    // already refreshed it in the meantime, the newer token is kept as is.
+    pub async fn refresh(&self, rejected_access_token: &str) -> Result<(), Error> {
+        match self {
+            Self::Oauth(om) => {
+                let mut om = om.lock().await;
+                let current = om
+                    .get_unexpired_token()
+                    .and_then(|t| t.ok())
+                    .map(|t| t.access_token().secret().clone());
+                if current.is_some_and(|t| t != rejected_access_token) {
+                    return Ok(());
+                }
+                om.refresh_token().await
+            }
+            Self::Static(_) => Err("A static token can't be refreshed".into()),
+        }
+    }
+}
+
+impl From<Arc<Mutex<OauthManager>>> for TokenProvider {
+    fn from(om: Arc<Mutex<OauthManager>>) -> Self {
+        Self::Oauth(om)
+    }
+}
+
+impl From<OauthTokenResponse> for TokenProvider {
+    fn from(token: OauthTokenResponse) -> Self {
+        Self::Static(token)
+    }
+}
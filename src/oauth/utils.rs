@@ -11,18 +11,21 @@ pub mod oauth_utils {
 
     use oauth2::{AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenUrl};
 
-    const AUTHORIZE_ENDPOINT: &str = "https://api.schwabapi.com/v1/oauth/authorize";
-    const TOKEN_ENDPOINT: &str = "https://api.schwabapi.com/v1/oauth/token";
+    const AUTHORIZE_PATH: &str = "/v1/oauth/authorize";
+    const TOKEN_PATH: &str = "/v1/oauth/token";
 
+    // base_url is the root of the Schwab API, e.g. https://api.schwabapi.com
     pub fn new_oauth_basic_client(
         client_id: String,
         client_secret: String,
         redirect_address: String,
+        base_url: &str,
     ) -> Result<Client, Box<dyn error::Error + Send + Sync>> {
+        let base_url = base_url.trim_end_matches('/');
         Ok(oauth2::basic::BasicClient::new(ClientId::new(client_id))
             .set_client_secret(ClientSecret::new(client_secret))
-            .set_auth_uri(AuthUrl::new(AUTHORIZE_ENDPOINT.to_string())?)
-            .set_token_uri(TokenUrl::new(TOKEN_ENDPOINT.to_string())?)
+            .set_auth_uri(AuthUrl::new(format!("{}{}", base_url, AUTHORIZE_PATH))?)
+            .set_token_uri(TokenUrl::new(format!("{}{}", base_url, TOKEN_PATH))?)
             .set_redirect_uri(RedirectUrl::new(redirect_address)?))
     }
 }
@@ -1,12 +1,35 @@
 use crate::{
     Error,
-    config::TradingConfig,
+    config::{ScheduleConfig, TradingConfig},
     oauth::token::OauthManager,
-    schwab::{client::SchwabClient, math::two_decimals},
-    server::web_resources::files::html::InvestmentCollectionPercent,
+    schwab::{
+        candle_store::{CANDLE_SYNC_HOURS, CandleStore},
+        cassette::Cassette,
+        client::{SchwabClient, is_order_not_created},
+        endpoints::Endpoints,
+        error::SchwabApiError,
+        instrument_cache::InstrumentCache,
+        market_overview::{MarketOverviewConfig, market_overview},
+        math::{calculate_investment_amount, two_decimals},
+        order_tracker::{CLOCK_SKEW_SECONDS, OrderTracker, TrackedOrder},
+        paper_broker::{PaperBroker, PaperPosition},
+        rate_limiter::{RateLimitConfig, RateLimiter},
+        retry::RetryPolicy,
+        scheduler::Scheduler,
+        schemas::{
+            accounts_and_trading::accounts::{OrderRequest, Position},
+            market_data::quotes::QuoteResponseObject,
+            streamer::{ACCOUNT_ACTIVITY_KEY, Service},
+        },
+        streamer::{StreamEvent, Streamer, StreamerConfig},
+    },
+    server::web_resources::files::html::{IndexMovers, InvestmentCollectionPercent, SecurityName},
 };
 use std::collections::HashMap;
-use tokio::{sync::watch, task::JoinSet};
+use tokio::{
+    sync::{Notify, broadcast, watch},
+    task::JoinSet,
+};
 
 // In this Manager, we will want to represent a state we want to achieve/maintain.
 
@@ -32,9 +55,20 @@ struct Investment {
 pub struct AccountManager {
     account_number: String,
     investments: AccountInvestments,
-    om: std::sync::Arc<tokio::sync::Mutex<OauthManager>>,
+    client: SchwabClient,
     account_data: watch::Sender<AccountData>,
     internal_account_data: std::sync::Arc<tokio::sync::RwLock<InternalAccountData>>,
+    paper_broker: Option<std::sync::Arc<tokio::sync::Mutex<PaperBroker>>>,
+    order_tracker: std::sync::Arc<tokio::sync::Mutex<OrderTracker>>,
+    candle_store: Option<std::sync::Arc<CandleStore>>,
+    instrument_cache: Option<std::sync::Arc<InstrumentCache>>,
+    // Kept to check the collections against Schwab's instruments once authorized.
+    trading_config: TradingConfig,
+    schedule: ScheduleConfig,
+    streamer_config: Option<StreamerConfig>,
+    market_overview_config: Option<MarketOverviewConfig>,
+    // Wakes the update/trade loop early, e.g. when the streamer reports a fill.
+    refresh: std::sync::Arc<Notify>,
     js: JoinSet<Result<(), Error>>,
 }
 
@@ -54,6 +88,9 @@ pub struct AccountData {
     pub total_day_change: f64,
     pub total_profit_loss: f64,
     pub investment_account_state_percent: Vec<InvestmentCollectionPercent>,
+    pub paper_trading: bool,
+    pub account_nickname: Option<String>,
+    pub market_overview: Vec<IndexMovers>,
 }
 
 #[derive(Default, Clone)]
@@ -61,17 +98,21 @@ struct InternalAccountData {
     account_data: AccountData,
     account_hash: String,
     securities: HashMap<String, Security>,
+    // Names of the traded symbols, e.g. "VTI" -> "Vanguard Total Stock Market ETF".
+    instrument_names: HashMap<String, String>,
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl AccountManager {
     pub fn new(
         trading_config: TradingConfig,
+        endpoints: Endpoints,
         om: std::sync::Arc<tokio::sync::Mutex<OauthManager>>,
-    ) -> Self {
-        Self {
+    ) -> Result<Self, Error> {
+        Ok(Self {
             account_number: trading_config.account_number.clone(),
             investments: Self::account_config_from_trading_config(&trading_config),
-            om,
+            client: SchwabClient::new(om, endpoints),
             account_data: {
                 let (s, _) = watch::channel(AccountData::default());
                 s
@@ -79,8 +120,34 @@ impl AccountManager {
             internal_account_data: std::sync::Arc::new(tokio::sync::RwLock::new(
                 InternalAccountData::default(),
             )),
+            paper_broker: match trading_config.paper_trading {
+                true => {
+                    log::info!("Paper trading enabled; orders will not be sent to Schwab");
+                    Some(std::sync::Arc::new(tokio::sync::Mutex::new(
+                        match trading_config.paper_trading_state_path.clone() {
+                            Some(path) => PaperBroker::load(path)?,
+                            None => PaperBroker::new(),
+                        },
+                    )))
+                }
+                false => None,
+            },
+            order_tracker: std::sync::Arc::new(tokio::sync::Mutex::new(OrderTracker::new())),
+            candle_store: match trading_config.candle_store_path.as_ref() {
+                Some(path) => Some(std::sync::Arc::new(CandleStore::new(path)?)),
+                None => None,
+            },
+            instrument_cache: match trading_config.instrument_cache_path.as_ref() {
+                Some(path) => Some(std::sync::Arc::new(InstrumentCache::new(path)?)),
+                None => None,
+            },
+            trading_config: trading_config.clone(),
+            schedule: trading_config.schedule.clone(),
+            streamer_config: None,
+            market_overview_config: None,
+            refresh: std::sync::Arc::new(Notify::new()),
             js: JoinSet::new(),
-        }
+        })
     }
 
     fn account_config_from_trading_config(trading_config: &TradingConfig) -> AccountInvestments {
@@ -117,14 +184,50 @@ impl AccountManager {
         self.account_data.subscribe()
     }
 
+    pub fn with_rate_limits(mut self, config: &RateLimitConfig) -> Self {
+        self.client = self.client.with_rate_limits(config);
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: &RetryPolicy) -> Self {
+        self.client = self.client.with_retry_policy(retry_policy);
+        self
+    }
+
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.client = self.client.with_cassette(cassette);
+        self
+    }
+
+    // Follows account activity on the Schwab streamer, so fills and cancels are acted on as soon as
+    // they happen instead of on the next poll.
+    pub fn with_streamer(mut self, config: StreamerConfig) -> Self {
+        self.streamer_config = Some(config);
+        self
+    }
+
+    // Keeps the top movers of a few indices on the dashboard, next to the account.
+    pub fn with_market_overview(mut self, config: MarketOverviewConfig) -> Self {
+        self.market_overview_config = Some(config);
+        self
+    }
+
+    pub async fn tracked_orders(&self) -> Vec<TrackedOrder> {
+        self.order_tracker.lock().await.orders().to_vec()
+    }
+
+    pub fn rate_limiter(&self) -> std::sync::Arc<RateLimiter> {
+        self.client.rate_limiter()
+    }
+
     async fn initialize_account_hash(
-        om: &std::sync::Arc<tokio::sync::Mutex<OauthManager>>,
+        client: &SchwabClient,
         internal_account_data: &std::sync::Arc<tokio::sync::RwLock<InternalAccountData>>,
         account_number: &String,
     ) -> Result<(), Error> {
         internal_account_data.write().await.account_hash = 'outer: loop {
-            if let Some(Ok(token)) = om.lock().await.get_unexpired_token() {
-                for an in SchwabClient::new(token).get_account_numbers().await?.iter() {
+            if client.is_authorized().await {
+                for an in client.get_account_numbers().await?.iter() {
                     if an.account_number == *account_number {
                         log::info!("Retrieved the account hash.");
                         break 'outer an.hash_value.clone();
@@ -135,29 +238,129 @@ impl AccountManager {
             tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
         };
 
+        // The nickname is only for the dashboard, so trading goes ahead without it.
+        match client.get_user_preference().await {
+            Ok(preference) => {
+                internal_account_data
+                    .write()
+                    .await
+                    .account_data
+                    .account_nickname = preference.nickname(account_number)
+            }
+            Err(e) => log::warn!("Couldn't get the account nickname: '{}'", e),
+        }
+
+        Ok(())
+    }
+
+    // Looks up the symbols of every collection, refusing to trade any that Schwab doesn't know or
+    // that aren't equities or ETFs, and keeps their names for the dashboard. The lookup itself
+    // failing only costs the names.
+    async fn check_instruments(
+        client: &SchwabClient,
+        instrument_cache: &Option<std::sync::Arc<InstrumentCache>>,
+        trading_config: &TradingConfig,
+        internal_account_data: &std::sync::Arc<tokio::sync::RwLock<InternalAccountData>>,
+    ) -> Result<(), Error> {
+        let symbols = trading_config
+            .trading_collections
+            .iter()
+            .flat_map(|collection| collection.collection.iter())
+            .fold(Vec::new(), |mut symbols, symbol| {
+                if !symbols.contains(symbol) {
+                    symbols.push(symbol.clone());
+                }
+                symbols
+            });
+        let instruments = match instrument_cache {
+            Some(instrument_cache) => instrument_cache.instruments(client, &symbols).await,
+            None => client.get_instruments(&symbols).await.map(|instruments| {
+                instruments
+                    .into_iter()
+                    .map(|instrument| (instrument.symbol.clone(), instrument))
+                    .collect()
+            }),
+        };
+        let instruments: HashMap<_, _> = match instruments {
+            Ok(instruments) => instruments,
+            Err(e) => {
+                log::warn!("Couldn't look up the traded symbols: '{}'", e);
+                return Ok(());
+            }
+        };
+
+        trading_config.validate_instruments(&instruments)?;
+        log::info!("Every traded symbol is a known equity or ETF");
+        internal_account_data.write().await.instrument_names = instruments
+            .into_iter()
+            .map(|(symbol, instrument)| (symbol, instrument.name().to_string()))
+            .collect();
         Ok(())
     }
 
     async fn update_account_data(
-        om: &std::sync::Arc<tokio::sync::Mutex<OauthManager>>,
+        client: &SchwabClient,
         account_data: &watch::Sender<AccountData>,
         internal_account_data: &mut std::sync::Arc<tokio::sync::RwLock<InternalAccountData>>,
         target_investments: &AccountInvestments,
+        paper_broker: &Option<std::sync::Arc<tokio::sync::Mutex<PaperBroker>>>,
+        account_number: &String,
     ) -> Result<(), Error> {
-        if let Some(Ok(token)) = om.lock().await.get_unexpired_token() {
+        if client.is_authorized().await {
             let account_hash = internal_account_data.read().await.account_hash.clone();
-            let client = SchwabClient::new(token);
-            let account = client.get_account(account_hash.as_str()).await?;
-            let now = chrono::Utc::now();
-
-            println!(
-                "ORDERS: {:#?}",
-                client
-                    .get_orders(account_hash.as_str(), now - chrono::Days::new(365), now)
-                    .await?
-            );
+            let account = match client.get_account(account_hash.as_str()).await {
+                Ok(account) => account,
+                Err(e) => {
+                    // Only a 404 for the account itself means the hash is stale; quote lookups can
+                    // 404 too.
+                    if let Some(SchwabApiError::NotFound(_)) = e.downcast_ref::<SchwabApiError>() {
+                        log::warn!("Account hash is no longer valid, looking it up again");
+                        if let Err(e) = Self::initialize_account_hash(
+                            client,
+                            internal_account_data,
+                            account_number,
+                        )
+                        .await
+                        {
+                            log::error!("Error when looking up the account hash: '{}'", e);
+                        }
+                    }
+                    return Err(e);
+                }
+            };
 
             if let Some(securities_account) = account.securities_account {
+                let paper_account_state = match paper_broker {
+                    Some(paper_broker) => {
+                        {
+                            let mut pb = paper_broker.lock().await;
+                            if !pb.is_seeded() {
+                                pb.seed(
+                                    securities_account.initial_balances.cash(),
+                                    securities_account.positions.iter().fold(
+                                        HashMap::new(),
+                                        |mut positions, position| {
+                                            if let Some(symbol) = position.instrument.symbol() {
+                                                positions.insert(
+                                                    symbol,
+                                                    PaperPosition {
+                                                        quantity: position.long_quantity,
+                                                        cost: position.average_price
+                                                            * position.long_quantity,
+                                                    },
+                                                );
+                                            }
+                                            positions
+                                        },
+                                    ),
+                                )?;
+                            }
+                        }
+                        Some(Self::paper_account_state(client, paper_broker).await?)
+                    }
+                    None => None,
+                };
+
                 // When the algorithm decides to make a purchase, it must hold all other potential
                 // purches until after that set of orders are fulfilled and it has reflected in the
                 // account balance.
@@ -186,11 +389,7 @@ impl AccountManager {
                 // Update the account data
                 iad.account_data.total_account_value =
                     securities_account.initial_balances.account_value;
-                iad.account_data.total_cash_balance =
-                    match securities_account.initial_balances.total_cash > 0.0 {
-                        true => securities_account.initial_balances.total_cash,
-                        false => securities_account.initial_balances.margin_balance,
-                    };
+                iad.account_data.total_cash_balance = securities_account.initial_balances.cash();
                 iad.account_data.target_cash_balance = target_investments.target_cash_balance;
 
                 (
@@ -198,36 +397,21 @@ impl AccountManager {
                     iad.account_data.total_day_change,
                     iad.account_data.total_profit_loss,
                     iad.securities,
-                ) = securities_account.positions.iter().fold(
-                    (0.0, 0.0, 0.0, HashMap::new()),
-                    |(total_market_value, total_day_change, total_profit_loss, mut securities),
-                     position| {
-                        // skip if symbol is unknown
-                        match position.instrument.symbol() {
-                            None => (
-                                total_market_value,
-                                total_day_change,
-                                total_profit_loss,
-                                securities,
-                            ),
-                            Some(symbol) => (
-                                two_decimals(total_market_value + position.market_value),
-                                two_decimals(total_day_change + position.current_day_profit_loss),
-                                two_decimals(total_profit_loss + position.long_open_profit_loss),
-                                {
-                                    securities.insert(
-                                        symbol,
-                                        Security {
-                                            amount: position.long_quantity,
-                                            total_value: position.market_value,
-                                        },
-                                    );
-                                    securities
-                                },
-                            ),
-                        }
-                    },
-                );
+                ) = Self::position_totals(&securities_account.positions);
+
+                // When paper trading, the dashboard and trading decisions are driven by the
+                // simulated account rather than the real one.
+                if let Some((cash, market_value, day_change, profit_loss, securities)) =
+                    paper_account_state
+                {
+                    iad.account_data.total_cash_balance = cash;
+                    iad.account_data.total_market_value = market_value;
+                    iad.account_data.total_account_value = two_decimals(cash + market_value);
+                    iad.account_data.total_day_change = day_change;
+                    iad.account_data.total_profit_loss = profit_loss;
+                    iad.securities = securities;
+                }
+                iad.account_data.paper_trading = paper_broker.is_some();
 
                 iad.account_data.investment_account_state_percent =
                     target_investments.priority_queue_investments.iter().fold(
@@ -242,6 +426,13 @@ impl AccountManager {
                                 Amount::PercentageValue(p) => {
                                     v.push(InvestmentCollectionPercent {
                                         name: group_name.clone(),
+                                        securities: equities
+                                            .iter()
+                                            .map(|symbol| SecurityName {
+                                                symbol: symbol.clone(),
+                                                name: iad.instrument_names.get(symbol).cloned(),
+                                            })
+                                            .collect(),
                                         target_investment: *p,
                                         actual_investment: two_decimals(
                                             (equities.iter().fold(0.0, |t, e| {
@@ -266,70 +457,652 @@ impl AccountManager {
                         },
                     );
 
+                iad.updated_at = Some(chrono::Utc::now());
+
                 account_data.send_modify(|ad: &mut AccountData| ad.clone_from(&iad.account_data));
             }
         }
         Ok(())
     }
 
+    // The market value, day change and profit/loss of every position, and the positions keyed by
+    // symbol so they can be matched to the collections. A position whose asset type isn't known
+    // still counts towards the totals.
+    fn position_totals(positions: &[Position]) -> (f64, f64, f64, HashMap<String, Security>) {
+        positions.iter().fold(
+            (0.0, 0.0, 0.0, HashMap::new()),
+            |(total_market_value, total_day_change, total_profit_loss, mut securities),
+             position| {
+                if let Some(symbol) = position.instrument.symbol() {
+                    securities.insert(
+                        symbol,
+                        Security {
+                            amount: position.long_quantity,
+                            total_value: position.market_value,
+                        },
+                    );
+                }
+                (
+                    two_decimals(total_market_value + position.market_value),
+                    two_decimals(total_day_change + position.current_day_profit_loss),
+                    two_decimals(total_profit_loss + position.long_open_profit_loss),
+                    securities,
+                )
+            },
+        )
+    }
+
+    // Polls the orders placed in earlier rounds and reports whether any of them are still working
+    // or not yet reflected in the account positions.
     async fn has_unsettled_trades(
-        om: &std::sync::Arc<tokio::sync::Mutex<OauthManager>>,
+        client: &SchwabClient,
+        internal_account_data: &std::sync::Arc<tokio::sync::RwLock<InternalAccountData>>,
+        order_tracker: &tokio::sync::Mutex<OrderTracker>,
     ) -> Result<bool, Error> {
-        match om.lock().await.get_unexpired_token() {
-            Some(Ok(_token)) => Ok(true),
-            _ => Err("Couldn't get valid oauth token".into()),
+        if !client.is_authorized().await {
+            return Err("Couldn't get valid oauth token".into());
+        }
+
+        let mut tracker = order_tracker.lock().await;
+        if tracker.is_empty() {
+            return Ok(false);
+        }
+
+        if tracker.has_pending_orders() {
+            if let Some(earliest_submission) = tracker.earliest_submission() {
+                let account_hash = internal_account_data.read().await.account_hash.clone();
+                let orders = client
+                    .get_orders(
+                        &account_hash,
+                        earliest_submission - chrono::Duration::seconds(CLOCK_SKEW_SECONDS),
+                        chrono::Utc::now(),
+                    )
+                    .await?;
+                tracker.update(&orders);
+            }
+        }
+
+        let iad = internal_account_data.read().await;
+        let settled = tracker.settle(&iad.securities, iad.updated_at);
+        if !settled {
+            log::info!(
+                "Waiting on {} order(s) to settle before trading",
+                tracker.orders().len()
+            );
+        }
+        Ok(!settled)
+    }
+
+    // Total value currently held across every equity in a collection.
+    fn collection_value(equities: &[String], securities: &HashMap<String, Security>) -> f64 {
+        equities.iter().fold(0.0, |t, e| {
+            t + securities.get(e).map(|s| s.total_value).unwrap_or(0.0)
+        })
+    }
+
+    // Splits the investable cash across the investments, returning one amount per entry in
+    // priority_queue_investments. Fixed amount collections are topped up to their target value
+    // first, in priority order; whatever is left is spread across the percentage collections by
+    // math::calculate_investment_amount.
+    fn allocate_investable_cash(
+        target_investments: &AccountInvestments,
+        securities: &HashMap<String, Security>,
+        mut investable: f64,
+    ) -> Result<Vec<f64>, Error> {
+        let investments = &target_investments.priority_queue_investments;
+        let mut allocations = vec![0.0; investments.len()];
+
+        for (i, investment) in investments.iter().enumerate() {
+            if let Amount::AmountValue(a) = investment.amount {
+                let deficit = two_decimals(
+                    a as f64 - Self::collection_value(&investment.equities, securities),
+                );
+                if deficit > 0.0 && investable > 0.0 {
+                    allocations[i] = deficit.min(investable);
+                    investable = two_decimals(investable - allocations[i]);
+                }
+            }
+        }
+
+        let percentage_investments: Vec<(usize, f64, f64)> = investments
+            .iter()
+            .enumerate()
+            .filter_map(|(i, investment)| match investment.amount {
+                Amount::PercentageValue(p) => Some((
+                    i,
+                    p / 100.0,
+                    Self::collection_value(&investment.equities, securities),
+                )),
+                Amount::AmountValue(_) => None,
+            })
+            .collect();
+
+        if investable <= 0.0 || percentage_investments.is_empty() {
+            return Ok(allocations);
+        }
+
+        let total = percentage_investments
+            .iter()
+            .fold(0.0, |t, (_, _, value)| t + value);
+
+        let amounts = calculate_investment_amount(
+            investable,
+            total,
+            percentage_investments
+                .iter()
+                .map(|(_, target, value)| {
+                    // With nothing invested yet the current level doesn't matter, so use the target
+                    // to keep the levels summing to 1.
+                    (*target, if total > 0.0 { value / total } else { *target })
+                })
+                .collect(),
+        )?;
+
+        for ((i, _, _), amount) in percentage_investments.iter().zip(amounts) {
+            allocations[*i] = amount;
+        }
+
+        Ok(allocations)
+    }
+
+    // Picks the first equity in the collection that the allocation can buy at least one whole share
+    // of, falling back to the alternates when the primary is too expensive.
+    fn order_for_allocation(
+        equities: &[String],
+        amount: f64,
+        prices: &HashMap<String, f64>,
+    ) -> Option<OrderRequest> {
+        equities.iter().find_map(|symbol| {
+            let price = *prices.get(symbol)?;
+            if price <= 0.0 {
+                return None;
+            }
+            let quantity = (amount / price).floor();
+            if quantity >= 1.0 {
+                Some(OrderRequest::new_fill_or_kill_etf(quantity, symbol.clone()))
+            } else {
+                None
+            }
+        })
+    }
+
+    async fn get_quotes(
+        client: &SchwabClient,
+        symbols: &[String],
+    ) -> Result<HashMap<String, QuoteResponseObject>, Error> {
+        if symbols.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let response = client.get_quotes(symbols).await?;
+        if let Some(errors) = response.errors {
+            log::warn!(
+                "Invalid symbols in quote request: {:?}",
+                errors.invalid_symbols
+            );
+        }
+        for symbol in symbols.iter().filter(|s| !response.quotes.contains_key(*s)) {
+            log::warn!("No quote returned for '{}'", symbol);
+        }
+        Ok(response.quotes)
+    }
+
+    async fn get_prices(
+        client: &SchwabClient,
+        symbols: &[String],
+    ) -> Result<HashMap<String, f64>, Error> {
+        Ok(Self::get_quotes(client, symbols)
+            .await?
+            .iter()
+            .filter_map(|(symbol, quote)| {
+                // Market buys fill at the ask, fall back to the last trade if there is no ask.
+                quote
+                    .ask_price()
+                    .or(quote.last_price())
+                    .map(|price| (symbol.clone(), price))
+            })
+            .collect())
+    }
+
+    // Values the simulated positions at the latest quotes, returning the same totals that are
+    // otherwise read from the real account.
+    async fn paper_account_state(
+        client: &SchwabClient,
+        paper_broker: &tokio::sync::Mutex<PaperBroker>,
+    ) -> Result<(f64, f64, f64, f64, HashMap<String, Security>), Error> {
+        let (cash, positions) = {
+            let pb = paper_broker.lock().await;
+            (pb.cash(), pb.positions().clone())
+        };
+        let quotes =
+            Self::get_quotes(client, &positions.keys().cloned().collect::<Vec<_>>()).await?;
+
+        Ok(positions.iter().fold(
+            (cash, 0.0, 0.0, 0.0, HashMap::new()),
+            |(cash, total_market_value, total_day_change, total_profit_loss, mut securities),
+             (symbol, position)| {
+                let quote = quotes.get(symbol);
+                let price = quote
+                    .and_then(|q| q.last_price())
+                    .unwrap_or(position.cost / position.quantity);
+                let market_value = two_decimals(position.quantity * price);
+                let day_change = quote
+                    .and_then(|q| q.net_change())
+                    .map(|c| c * position.quantity)
+                    .unwrap_or(0.0);
+                securities.insert(
+                    symbol.clone(),
+                    Security {
+                        amount: position.quantity,
+                        total_value: market_value,
+                    },
+                );
+                (
+                    cash,
+                    two_decimals(total_market_value + market_value),
+                    two_decimals(total_day_change + day_change),
+                    two_decimals(total_profit_loss + market_value - position.cost),
+                    securities,
+                )
+            },
+        ))
+    }
+
+    // Asks Schwab to validate the order, failing if it would be rejected so it never reaches the
+    // market.
+    async fn preview_order(
+        client: &SchwabClient,
+        account_hash: &str,
+        order: &OrderRequest,
+    ) -> Result<(), Error> {
+        let preview = client.preview_order(account_hash, order).await?;
+        let validation = &preview.order_validation_result;
+        for warning in validation.warns.iter().chain(validation.alerts.iter()) {
+            log::warn!(
+                "Order preview warning: {}",
+                warning.message.as_deref().unwrap_or("no message")
+            );
         }
+        if preview.is_rejected() {
+            return Err(format!(
+                "Schwab rejected the order: {}",
+                validation
+                    .rejects
+                    .iter()
+                    .filter_map(|r| r.message.clone())
+                    .collect::<Vec<String>>()
+                    .join("; ")
+            )
+            .into());
+        }
+
+        log::info!(
+            "Order preview accepted, commission {} fees {} projected buying power {:?}",
+            preview.commission(),
+            preview.fees(),
+            preview
+                .order_strategy
+                .as_ref()
+                .and_then(|s| s.order_balance.as_ref())
+                .and_then(|b| b.projected_buying_power)
+        );
+        Ok(())
     }
 
     async fn perform_trades(
-        om: &std::sync::Arc<tokio::sync::Mutex<OauthManager>>,
-        _internal_account_data: &mut std::sync::Arc<tokio::sync::RwLock<InternalAccountData>>,
-        _target_investments: &AccountInvestments,
+        client: &SchwabClient,
+        internal_account_data: &mut std::sync::Arc<tokio::sync::RwLock<InternalAccountData>>,
+        target_investments: &AccountInvestments,
+        paper_broker: &Option<std::sync::Arc<tokio::sync::Mutex<PaperBroker>>>,
+        order_tracker: &tokio::sync::Mutex<OrderTracker>,
     ) -> Result<(), Error> {
-        if let Some(Ok(_token)) = om.lock().await.get_unexpired_token() {
-            // Calculate allocations and perform trades sequentially. If the amount allocated is
-            // not enough to buy that position, buy the alternate.
-            // For each trade, it should determine if a position for that stock had been sold
-            // recently so it can buy the alternate.
+        if !client.is_authorized().await {
+            return Ok(());
         }
+
+        let (account_hash, total_cash_balance, securities) = {
+            let iad = internal_account_data.read().await;
+            (
+                iad.account_hash.clone(),
+                iad.account_data.total_cash_balance,
+                iad.securities.clone(),
+            )
+        };
+
+        let investable = two_decimals(total_cash_balance - target_investments.target_cash_balance);
+        if investable <= 0.0 {
+            log::debug!(
+                "Cash balance {} is not above the target {}; nothing to invest",
+                total_cash_balance,
+                target_investments.target_cash_balance
+            );
+            return Ok(());
+        }
+
+        let allocations =
+            Self::allocate_investable_cash(target_investments, &securities, investable)?;
+
+        let symbols: Vec<String> = target_investments
+            .priority_queue_investments
+            .iter()
+            .zip(allocations.iter())
+            .filter(|(_, amount)| **amount > 0.0)
+            .flat_map(|(investment, _)| investment.equities.clone())
+            .collect();
+        if symbols.is_empty() {
+            return Ok(());
+        }
+
+        let prices = Self::get_prices(client, &symbols).await?;
+
+        for (investment, amount) in target_investments
+            .priority_queue_investments
+            .iter()
+            .zip(allocations)
+            .filter(|(_, amount)| *amount > 0.0)
+        {
+            match Self::order_for_allocation(&investment.equities, amount, &prices) {
+                Some(order) => {
+                    log::info!(
+                        "Placing order for collection '{}' with allocation {}: {:?}",
+                        investment.group_name,
+                        amount,
+                        order.order_leg_collection
+                    );
+                    // A failed order doesn't stop the rest of the round; the next round works
+                    // from whatever was actually placed.
+                    match paper_broker {
+                        Some(paper_broker) => {
+                            match paper_broker.lock().await.place_order(&order, &prices) {
+                                Ok(order_id) => {
+                                    log::info!("Paper order {} filled", order_id);
+                                    order_tracker.lock().await.track_filled(
+                                        &order,
+                                        &securities,
+                                        order_id,
+                                    );
+                                }
+                                Err(e) => log::error!("Paper order failed: '{}'", e),
+                            }
+                        }
+                        None => {
+                            if let Err(e) = Self::preview_order(client, &account_hash, &order).await
+                            {
+                                log::error!("Order failed preview: '{}'", e);
+                                continue;
+                            }
+                            let known_order_ids = order_tracker.lock().await.order_ids();
+                            match client
+                                .post_order(&account_hash, &order, &known_order_ids)
+                                .await
+                            {
+                                Ok(placed) => {
+                                    log::info!("Placed order {}", placed.order_id);
+                                    order_tracker.lock().await.track(
+                                        &order,
+                                        &securities,
+                                        Some(placed.order_id),
+                                    );
+                                }
+                                Err(e) if is_order_not_created(&e) => {
+                                    log::error!("Order was not placed: '{}'", e);
+                                }
+                                Err(e) => {
+                                    // The order may have been created without us seeing the
+                                    // response, so it's matched against the orders list instead.
+                                    log::error!("Error when placing order: '{}'", e);
+                                    order_tracker.lock().await.track(&order, &securities, None);
+                                }
+                            }
+                        }
+                    }
+                }
+                None => log::info!(
+                    "Allocation {} for collection '{}' can't buy a whole share of {:?}",
+                    amount,
+                    investment.group_name,
+                    investment.equities
+                ),
+            }
+        }
+
         Ok(())
     }
 
+    // Every symbol in the trading collections, in order and without duplicates.
+    fn symbols(target_investments: &AccountInvestments) -> Vec<String> {
+        target_investments
+            .priority_queue_investments
+            .iter()
+            .flat_map(|investment| investment.equities.iter())
+            .fold(Vec::new(), |mut symbols, symbol| {
+                if !symbols.contains(symbol) {
+                    symbols.push(symbol.clone());
+                }
+                symbols
+            })
+    }
+
+    // Applies the activity streamed for our account to the tracked orders, and refreshes the
+    // account straight away when one of them changed.
+    async fn follow_account_activity(
+        streamer: Streamer,
+        account_number: String,
+        order_tracker: std::sync::Arc<tokio::sync::Mutex<OrderTracker>>,
+        refresh: std::sync::Arc<Notify>,
+    ) -> Result<(), Error> {
+        let mut events = streamer.events();
+        streamer.subscribe(Service::AcctActivity, &[ACCOUNT_ACTIVITY_KEY.to_string()])?;
+        loop {
+            match events.recv().await {
+                Ok(StreamEvent::AccountActivity(activity))
+                    if activity.account_number == account_number =>
+                {
+                    log::debug!("Account activity: {:?}", activity);
+                    if order_tracker.lock().await.apply(&activity) {
+                        refresh.notify_one();
+                    }
+                }
+                Ok(_) => (),
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    // The next poll catches up on whatever was missed.
+                    log::warn!("Missed {} streamer events", missed);
+                    refresh.notify_one();
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+
+    // Refreshes the market overview on the dashboard until the account manager is dropped.
+    async fn follow_market_overview(
+        client: SchwabClient,
+        config: MarketOverviewConfig,
+        account_data: watch::Sender<AccountData>,
+        internal_account_data: std::sync::Arc<tokio::sync::RwLock<InternalAccountData>>,
+    ) -> Result<(), Error> {
+        loop {
+            if !client.is_authorized().await {
+                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                continue;
+            }
+
+            let overview = market_overview(&client, &config).await;
+            {
+                // Stored with the rest of the account data so the next account update keeps it.
+                let iad = &mut internal_account_data.write().await;
+                iad.account_data.market_overview = overview;
+                account_data.send_modify(|ad| {
+                    ad.market_overview
+                        .clone_from(&iad.account_data.market_overview)
+                });
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(config.refresh_seconds)).await;
+        }
+    }
+
     pub async fn init(&mut self, timeout: tokio::time::Duration) -> Result<(), Error> {
+        // With a token at hand, a collection that can't be traded fails startup. Otherwise the
+        // symbols are checked by the trading task once the user has authorized.
+        let instruments_checked = self.client.is_authorized().await;
+        if instruments_checked
+            && let Err(e) = Self::check_instruments(
+                &self.client,
+                &self.instrument_cache,
+                &self.trading_config,
+                &self.internal_account_data,
+            )
+            .await
+        {
+            log::error!("Refusing to trade: {}", e);
+            return Err(e);
+        }
+
+        if let Some(config) = self.market_overview_config.clone() {
+            self.js.spawn(Self::follow_market_overview(
+                self.client.clone(),
+                config,
+                self.account_data.clone(),
+                self.internal_account_data.clone(),
+            ));
+        }
+
+        if let Some(config) = self.streamer_config.clone() {
+            self.js.spawn(Self::follow_account_activity(
+                Streamer::start(self.client.clone(), config),
+                self.account_number.clone(),
+                self.order_tracker.clone(),
+                self.refresh.clone(),
+            ));
+        }
+
+        if let Some(candle_store) = self.candle_store.clone() {
+            self.js.spawn({
+                let client = self.client.clone();
+                let symbols = Self::symbols(&self.investments);
+                async move {
+                    loop {
+                        match client.is_authorized().await {
+                            true => {
+                                candle_store.sync(&client, &symbols).await;
+                                tokio::time::sleep(tokio::time::Duration::from_secs(
+                                    CANDLE_SYNC_HOURS * 60 * 60,
+                                ))
+                                .await;
+                            }
+                            false => tokio::time::sleep(tokio::time::Duration::from_secs(1)).await,
+                        }
+                    }
+                }
+            });
+        }
+
         self.js.spawn({
-            let om = self.om.clone();
+            let client = self.client.clone();
             let account_data = self.account_data.clone();
             let mut internal_account_data = self.internal_account_data.clone();
             let account_number = self.account_number.clone();
             let investments = self.investments.clone();
+            let paper_broker = self.paper_broker.clone();
+            let order_tracker = self.order_tracker.clone();
+            let schedule = self.schedule.clone();
+            let refresh = self.refresh.clone();
+            let instrument_cache = self.instrument_cache.clone();
+            let trading_config = self.trading_config.clone();
             async move {
                 log::info!("Initializing trading system");
-                Self::initialize_account_hash(&om, &mut internal_account_data, &account_number)
-                    .await?;
+                Self::initialize_account_hash(
+                    &client,
+                    &mut internal_account_data,
+                    &account_number,
+                )
+                .await?;
+                if !instruments_checked
+                    && let Err(e) = Self::check_instruments(
+                        &client,
+                        &instrument_cache,
+                        &trading_config,
+                        &internal_account_data,
+                    )
+                    .await
+                {
+                    log::error!("Refusing to trade: {}", e);
+                    return Err(e);
+                }
 
                 log::info!("Begining update/trade loop");
+                let mut scheduler = Scheduler::new(schedule, timeout);
+                let mut last_phase = None;
                 loop {
+                    let now = chrono::Utc::now();
+                    let sessions = scheduler
+                        .sessions(client.is_authorized().await.then_some(&client), now)
+                        .await;
+                    let phase = Scheduler::phase(&sessions, now);
+                    if last_phase != Some(phase) {
+                        log::info!("Market phase is now {:?}", phase);
+                        last_phase = Some(phase);
+                    }
+                    let sleep_duration = scheduler.sleep_duration(&sessions, now);
+
                     if let Err(e) = Self::update_account_data(
-                        &om,
+                        &client,
                         &account_data,
                         &mut internal_account_data,
                         &investments,
+                        &paper_broker,
+                        &account_number,
                     )
                     .await
                     {
                         log::error!("Error when updating account data: '{}'", e);
+                        match e.downcast_ref::<SchwabApiError>() {
+                            Some(SchwabApiError::Unauthorized(_)) => {
+                                log::warn!(
+                                    "Schwab rejected the access token; trading is paused until it is refreshed or re-authorized"
+                                );
+                            }
+                            Some(SchwabApiError::RateLimited { retry_after }) => {
+                                let backoff = retry_after.unwrap_or(timeout);
+                                log::warn!("Rate limited, backing off for {:?}", backoff);
+                                tokio::time::sleep(backoff).await;
+                            }
+                            _ => (),
+                        }
+                        // Don't trade from stale account data.
+                        tokio::time::sleep(sleep_duration).await;
+                        continue;
                     }
 
-                    if let Ok(false) = Self::has_unsettled_trades(&om).await {
-                        if let Err(e) =
-                            Self::perform_trades(&om, &mut internal_account_data, &investments)
-                                .await
-                        {
-                            log::error!("Error when executing trades: '{}'", e);
+                    match Self::has_unsettled_trades(
+                        &client,
+                        &internal_account_data,
+                        &order_tracker,
+                    )
+                    .await
+                    {
+                        Ok(false) if !scheduler.can_trade(phase, now) => (),
+                        Ok(false) => {
+                            if let Err(e) = Self::perform_trades(
+                                &client,
+                                &mut internal_account_data,
+                                &investments,
+                                &paper_broker,
+                                &order_tracker,
+                            )
+                            .await
+                            {
+                                log::error!("Error when executing trades: '{}'", e);
+                            }
                         }
+                        Ok(true) => (),
+                        Err(e) => log::error!("Error when checking for unsettled trades: '{}'", e),
                     }
 
-                    tokio::time::sleep(timeout).await;
+                    tokio::select! {
+                        _ = tokio::time::sleep(sleep_duration) => (),
+                        _ = refresh.notified() => log::info!("Refreshing after account activity"),
+                    }
                 }
             }
         });
@@ -337,3 +1110,132 @@ impl AccountManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountInvestments, AccountManager, Amount, Investment, Security};
+    use crate::schwab::{
+        math::two_decimals,
+        schemas::accounts_and_trading::accounts::{AccountInstrument, Position},
+    };
+    use std::collections::HashMap;
+
+    fn investment(group_name: &str, equities: &[&str], amount: Amount) -> Investment {
+        Investment {
+            group_name: group_name.to_string(),
+            equities: equities.iter().map(|e| e.to_string()).collect(),
+            amount,
+        }
+    }
+
+    fn security(total_value: f64) -> Security {
+        Security {
+            amount: 1.0,
+            total_value,
+        }
+    }
+
+    #[test]
+    fn test_amount_allocations_are_filled_first() {
+        let investments = AccountInvestments {
+            priority_queue_investments: vec![
+                investment("bonds", &["BND"], Amount::AmountValue(500)),
+                investment("us", &["VTI"], Amount::PercentageValue(60.0)),
+                investment("intl", &["VXUS"], Amount::PercentageValue(40.0)),
+            ],
+            target_cash_balance: 0.0,
+        };
+        let securities = HashMap::from([
+            ("BND".to_string(), security(300.0)),
+            ("VTI".to_string(), security(600.0)),
+            ("VXUS".to_string(), security(400.0)),
+        ]);
+
+        let a =
+            AccountManager::allocate_investable_cash(&investments, &securities, 1200.0).unwrap();
+        println!("{:#?}", a);
+        assert_eq!(a[0], 200.0);
+        assert_eq!(two_decimals(a[1]), 600.0);
+        assert_eq!(two_decimals(a[2]), 400.0);
+    }
+
+    #[test]
+    fn test_allocation_with_no_existing_positions() {
+        let investments = AccountInvestments {
+            priority_queue_investments: vec![
+                investment("us", &["VTI"], Amount::PercentageValue(70.0)),
+                investment("intl", &["VXUS"], Amount::PercentageValue(30.0)),
+            ],
+            target_cash_balance: 0.0,
+        };
+
+        let a = AccountManager::allocate_investable_cash(&investments, &HashMap::new(), 1000.0)
+            .unwrap();
+        println!("{:#?}", a);
+        assert_eq!(1000.0, two_decimals(a.iter().sum()));
+        assert_eq!(two_decimals(a[0]), 700.0);
+    }
+
+    #[test]
+    fn test_order_uses_alternate_when_primary_is_too_expensive() {
+        let equities = vec!["VOO".to_string(), "SPLG".to_string()];
+        let prices = HashMap::from([("VOO".to_string(), 500.0), ("SPLG".to_string(), 60.0)]);
+
+        let order = AccountManager::order_for_allocation(&equities, 250.0, &prices).unwrap();
+        let leg = &order.order_leg_collection[0];
+        assert_eq!(leg.quantity, 4.0);
+        assert_eq!(leg.instrument.symbol(), Some("SPLG".to_string()));
+
+        assert!(AccountManager::order_for_allocation(&equities, 50.0, &prices).is_none());
+    }
+
+    #[test]
+    fn test_position_totals_include_every_asset_type() {
+        let positions = vec![
+            Position {
+                long_quantity: 10.0,
+                market_value: 1000.0,
+                current_day_profit_loss: 10.0,
+                long_open_profit_loss: 100.0,
+                instrument: AccountInstrument::Equity {
+                    cusip: "922908769".to_string(),
+                    symbol: "VTI".to_string(),
+                    net_change: 1.0,
+                },
+                ..Default::default()
+            },
+            Position {
+                long_quantity: 500.0,
+                market_value: 500.0,
+                current_day_profit_loss: 0.05,
+                long_open_profit_loss: 0.0,
+                instrument: AccountInstrument::CashEquivalent {
+                    cusip: None,
+                    symbol: "SWVXX".to_string(),
+                    description: None,
+                    instrument_id: None,
+                    net_change: None,
+                    r#type: Some("MONEY_MARKET_FUND".to_string()),
+                },
+                ..Default::default()
+            },
+            Position {
+                long_quantity: 1.0,
+                market_value: 250.5,
+                current_day_profit_loss: -1.5,
+                long_open_profit_loss: 20.25,
+                instrument: AccountInstrument::Unknown,
+                ..Default::default()
+            },
+        ];
+
+        let (market_value, day_change, profit_loss, securities) =
+            AccountManager::position_totals(&positions);
+        assert_eq!(market_value, 1750.5);
+        assert_eq!(day_change, 8.55);
+        assert_eq!(profit_loss, 120.25);
+        assert_eq!(securities.len(), 2);
+        assert_eq!(securities["SWVXX"].total_value, 500.0);
+        assert_eq!(securities["VTI"].amount, 10.0);
+    }
+}
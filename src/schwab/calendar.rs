@@ -0,0 +1,211 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::America::New_York;
+
+// A built in NYSE calendar, used when the market hours endpoint can't be reached.
+
+// The n'th (1 based) weekday of a month.
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u8) -> Option<NaiveDate> {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n)
+}
+
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> Option<NaiveDate> {
+    nth_weekday(year, month, weekday, 5).or(nth_weekday(year, month, weekday, 4))
+}
+
+// Western Easter Sunday, using the anonymous Gregorian algorithm.
+fn easter(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+// Fixed date holidays falling on a weekend are observed on the closest weekday.
+fn observed(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Days::new(1),
+        Weekday::Sun => date + Days::new(1),
+        _ => date,
+    }
+}
+
+fn holidays(year: i32) -> Vec<NaiveDate> {
+    let fixed = |month, day| NaiveDate::from_ymd_opt(year, month, day);
+    let mut holidays = vec![
+        nth_weekday(year, 1, Weekday::Mon, 3),
+        nth_weekday(year, 2, Weekday::Mon, 3),
+        easter(year).map(|e| e - Days::new(2)),
+        last_weekday(year, 5, Weekday::Mon),
+        fixed(7, 4).map(observed),
+        nth_weekday(year, 9, Weekday::Mon, 1),
+        nth_weekday(year, 11, Weekday::Thu, 4),
+        fixed(12, 25).map(observed),
+    ];
+    // The exchange doesn't close on the Friday before when new year's day is a Saturday.
+    holidays.push(
+        fixed(1, 1)
+            .filter(|d| d.weekday() != Weekday::Sat)
+            .map(observed),
+    );
+    if year >= 2022 {
+        holidays.push(fixed(6, 19).map(observed));
+    }
+
+    holidays.into_iter().flatten().collect()
+}
+
+pub fn is_holiday(date: NaiveDate) -> bool {
+    holidays(date.year()).contains(&date)
+}
+
+pub fn is_trading_day(date: NaiveDate) -> bool {
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !is_holiday(date)
+}
+
+// The regular session closes at 1pm the day before Independence Day, the day after Thanksgiving
+// and on Christmas Eve.
+pub fn is_early_close(date: NaiveDate) -> bool {
+    let year = date.year();
+    let early_closes = [
+        NaiveDate::from_ymd_opt(year, 7, 3),
+        nth_weekday(year, 11, Weekday::Thu, 4).map(|t| t + Days::new(1)),
+        NaiveDate::from_ymd_opt(year, 12, 24),
+    ];
+    is_trading_day(date) && early_closes.contains(&Some(date))
+}
+
+// The start and end of a session.
+pub type Session = (DateTime<Utc>, DateTime<Utc>);
+
+// The pre-market, regular and after hours sessions of a trading day as Schwab runs them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sessions {
+    pub pre_market: Option<Session>,
+    pub regular: Option<Session>,
+    pub post_market: Option<Session>,
+}
+
+impl Sessions {
+    pub fn closed() -> Self {
+        Self {
+            pre_market: None,
+            regular: None,
+            post_market: None,
+        }
+    }
+}
+
+fn new_york_time(date: NaiveDate, hour: u32, minute: u32) -> Option<DateTime<Utc>> {
+    New_York
+        .from_local_datetime(&date.and_time(NaiveTime::from_hms_opt(hour, minute, 0)?))
+        .single()
+        .map(|t| t.to_utc())
+}
+
+pub fn sessions(date: NaiveDate) -> Sessions {
+    if !is_trading_day(date) {
+        return Sessions::closed();
+    }
+
+    let close = if is_early_close(date) { 13 } else { 16 };
+    let session = |start: (u32, u32), end: (u32, u32)| {
+        Some((
+            new_york_time(date, start.0, start.1)?,
+            new_york_time(date, end.0, end.1)?,
+        ))
+    };
+    Sessions {
+        pre_market: session((7, 0), (9, 30)),
+        regular: session((9, 30), (close, 0)),
+        post_market: session((close, 0), (close + 4, 0)),
+    }
+}
+
+// The exchange's date at the given time.
+pub fn exchange_date(time: DateTime<Utc>) -> NaiveDate {
+    time.with_timezone(&New_York).date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_early_close, is_holiday, is_trading_day, sessions};
+    use chrono::{Datelike, NaiveDate, TimeZone, Utc};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_nyse_holidays() {
+        let holidays_2025 = [
+            date(2025, 1, 1),
+            date(2025, 1, 20),
+            date(2025, 2, 17),
+            date(2025, 4, 18),
+            date(2025, 5, 26),
+            date(2025, 6, 19),
+            date(2025, 7, 4),
+            date(2025, 9, 1),
+            date(2025, 11, 27),
+            date(2025, 12, 25),
+        ];
+        for holiday in holidays_2025.iter() {
+            assert!(is_holiday(*holiday), "{} should be a holiday", holiday);
+        }
+        // 2025 also had an unscheduled closure, so count a year without one.
+        let mut day = date(2024, 1, 1);
+        let mut trading_days = 0;
+        while day.year() == 2024 {
+            if is_trading_day(day) {
+                trading_days += 1;
+            }
+            day = day.succ_opt().unwrap();
+        }
+        assert_eq!(trading_days, 252);
+
+        // weekend holidays move to the closest weekday, except a Saturday new year's day
+        assert!(is_holiday(date(2027, 12, 24)));
+        assert!(is_holiday(date(2026, 7, 3)));
+        assert!(is_holiday(date(2023, 1, 2)));
+        assert!(is_trading_day(date(2021, 12, 31)));
+        // Juneteenth was only added in 2022
+        assert!(is_trading_day(date(2021, 6, 18)));
+    }
+
+    #[test]
+    fn test_early_close_sessions() {
+        assert!(is_early_close(date(2025, 7, 3)));
+        assert!(is_early_close(date(2025, 11, 28)));
+        assert!(is_early_close(date(2025, 12, 24)));
+        assert!(!is_early_close(date(2025, 12, 23)));
+
+        let early = sessions(date(2025, 11, 28));
+        assert_eq!(
+            early.regular,
+            Some((
+                Utc.with_ymd_and_hms(2025, 11, 28, 14, 30, 0).unwrap(),
+                Utc.with_ymd_and_hms(2025, 11, 28, 18, 0, 0).unwrap()
+            ))
+        );
+        // summer time
+        assert_eq!(
+            sessions(date(2025, 7, 7)).regular,
+            Some((
+                Utc.with_ymd_and_hms(2025, 7, 7, 13, 30, 0).unwrap(),
+                Utc.with_ymd_and_hms(2025, 7, 7, 20, 0, 0).unwrap()
+            ))
+        );
+        assert_eq!(sessions(date(2025, 7, 5)).regular, None);
+    }
+}
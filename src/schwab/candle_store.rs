@@ -0,0 +1,136 @@
+use crate::{
+    Error,
+    schwab::{client::SchwabClient, schemas::market_data::price_history::Candle},
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::Deserializer as jsonDe;
+use std::{fs, io::Write, path::PathBuf};
+
+// How far back the first sync of a symbol goes.
+pub const CANDLE_HISTORY_DAYS: i64 = 5 * 365;
+
+// How often the account manager syncs the candles of the configured symbols.
+pub const CANDLE_SYNC_HOURS: u64 = 6;
+
+// Daily candles kept on disk as one json file per symbol. Each sync only asks Schwab for the days
+// after the newest stored candle.
+pub struct CandleStore {
+    path: PathBuf,
+}
+
+impl CandleStore {
+    pub fn new(path: &str) -> Result<Self, Error> {
+        fs::create_dir_all(path)?;
+        Ok(Self {
+            path: PathBuf::from(path),
+        })
+    }
+
+    fn file(&self, symbol: &str) -> PathBuf {
+        // Future symbols like /ES can't be used as file names as they are.
+        self.path.join(format!("{}.json", symbol.replace('/', "_")))
+    }
+
+    pub fn candles(&self, symbol: &str) -> Result<Vec<Candle>, Error> {
+        match fs::File::open(self.file(symbol)) {
+            Ok(fd) => Ok(Vec::<Candle>::deserialize(&mut jsonDe::from_reader(fd))?),
+            Err(e) => match e.kind() {
+                std::io::ErrorKind::NotFound => Ok(Vec::new()),
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    fn save(&self, symbol: &str, candles: &[Candle]) -> Result<(), Error> {
+        fs::File::create(self.file(symbol))?.write_all(&serde_json::to_vec(candles)?)?;
+        Ok(())
+    }
+
+    // Adds the new candles to the stored ones, ordered by time. A candle for a time that is already
+    // stored replaces it, since the latest day may have been stored before the close.
+    fn merge(mut candles: Vec<Candle>, new_candles: Vec<Candle>) -> Vec<Candle> {
+        for candle in new_candles {
+            match candles.binary_search_by_key(&candle.datetime, |c| c.datetime) {
+                Ok(i) => candles[i] = candle,
+                Err(i) => candles.insert(i, candle),
+            }
+        }
+        candles
+    }
+
+    // Fetches the missing daily candles of a symbol and returns how many candles were added.
+    pub async fn sync_symbol(&self, client: &SchwabClient, symbol: &str) -> Result<usize, Error> {
+        let candles = self.candles(symbol)?;
+        let start_date = candles
+            .last()
+            .and_then(|c| c.date_time())
+            .unwrap_or(Utc::now() - chrono::Duration::days(CANDLE_HISTORY_DAYS));
+        let stored = candles.len();
+
+        let candle_list = client
+            .get_daily_candles(symbol, start_date, Utc::now())
+            .await?;
+        let candles = Self::merge(candles, candle_list.candles);
+        self.save(symbol, &candles)?;
+
+        Ok(candles.len() - stored)
+    }
+
+    // Syncs every symbol, carrying on past symbols that fail so one bad symbol doesn't hold up the
+    // rest.
+    pub async fn sync(&self, client: &SchwabClient, symbols: &[String]) -> usize {
+        let mut added = 0;
+        for symbol in symbols.iter() {
+            match self.sync_symbol(client, symbol).await {
+                Ok(n) => added += n,
+                Err(e) => log::error!("Failed to sync candles for '{}': '{}'", symbol, e),
+            }
+        }
+        log::info!(
+            "Synced daily candles for {} symbols, {} new",
+            symbols.len(),
+            added
+        );
+        added
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CandleStore;
+    use crate::schwab::schemas::market_data::price_history::Candle;
+
+    fn candle(datetime: i64, close: f64) -> Candle {
+        Candle {
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 100,
+            datetime,
+        }
+    }
+
+    #[test]
+    fn test_merge_replaces_and_orders_candles() {
+        let merged = CandleStore::merge(
+            vec![candle(1, 10.0), candle(2, 11.0)],
+            vec![candle(3, 12.0), candle(2, 11.5)],
+        );
+        assert_eq!(
+            merged,
+            vec![candle(1, 10.0), candle(2, 11.5), candle(3, 12.0)]
+        );
+    }
+
+    #[test]
+    fn test_candles_round_trip_through_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CandleStore::new(dir.path().to_str().unwrap()).unwrap();
+        assert!(store.candles("/ES").unwrap().is_empty());
+
+        store.save("/ES", &[candle(1, 10.0)]).unwrap();
+        assert_eq!(store.candles("/ES").unwrap(), vec![candle(1, 10.0)]);
+    }
+}
@@ -0,0 +1,352 @@
+use crate::Error;
+use oauth2::reqwest;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{fs, path::PathBuf, sync::Mutex};
+use tokio::io::AsyncWriteExt;
+
+// Keys whose values identify the account. They are swapped for placeholders everywhere they show
+// up, including urls, so a replayed session still lines up.
+const ACCOUNT_KEYS: [&str; 2] = ["accountNumber", "hashValue"];
+const TOKEN_KEYS: [&str; 7] = [
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "accessToken",
+    "refreshToken",
+    "schwabClientCustomerId",
+    "schwabClientCorrelId",
+];
+
+// One request and the response Schwab gave for it. Urls are kept without the host so a cassette
+// can be replayed against any base url.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    pub request_body: Option<String>,
+    pub status: u16,
+    pub location: Option<String>,
+    pub retry_after: Option<String>,
+    pub body: String,
+}
+
+impl Interaction {
+    fn path(&self) -> &str {
+        self.url.split('?').next().unwrap_or(&self.url)
+    }
+
+    fn into_response(self) -> Result<reqwest::Response, Error> {
+        let mut response = hyper::Response::builder().status(self.status);
+        if let Some(location) = self.location {
+            response = response.header(reqwest::header::LOCATION, location);
+        }
+        if let Some(retry_after) = self.retry_after {
+            response = response.header(reqwest::header::RETRY_AFTER, retry_after);
+        }
+        Ok(reqwest::Response::from(response.body(self.body)?))
+    }
+}
+
+// Swaps secrets for placeholders. The same secret always gets the same placeholder.
+#[derive(Debug, Default)]
+struct Redactor {
+    replacements: Vec<(String, String)>,
+}
+
+impl Redactor {
+    fn add(&mut self, secret: String, placeholder: impl FnOnce(usize) -> String) {
+        if !secret.is_empty() && !self.replacements.iter().any(|(s, _)| *s == secret) {
+            let placeholder = placeholder(self.replacements.len() + 1);
+            self.replacements.push((secret, placeholder));
+            // Longer secrets first, so one that contains another is replaced whole.
+            self.replacements
+                .sort_by_key(|(s, _)| std::cmp::Reverse(s.len()));
+        }
+    }
+
+    // Picks up the secrets in a json body.
+    fn collect(&mut self, value: &Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter() {
+                    let secret = match value {
+                        Value::String(s) => Some(s.clone()),
+                        Value::Number(n) => Some(n.to_string()),
+                        _ => None,
+                    };
+                    match (secret, key.as_str()) {
+                        (Some(secret), "hashValue") => {
+                            self.add(secret, |n| format!("REDACTEDHASH{}", n))
+                        }
+                        // Kept numeric, since some schemas read account numbers as numbers.
+                        (Some(secret), k) if ACCOUNT_KEYS.contains(&k) => {
+                            self.add(secret, |n| format!("{}", 90000000 + n))
+                        }
+                        (Some(secret), k) if TOKEN_KEYS.contains(&k) => {
+                            self.add(secret, |_| "REDACTED".to_string())
+                        }
+                        _ => self.collect(value),
+                    }
+                }
+            }
+            Value::Array(values) => values.iter().for_each(|v| self.collect(v)),
+            _ => (),
+        }
+    }
+
+    fn redact(&self, text: &str) -> String {
+        self.replacements
+            .iter()
+            .fold(text.to_string(), |text, (secret, placeholder)| {
+                replace_whole(&text, secret, placeholder)
+            })
+    }
+
+    fn redact_interaction(&self, interaction: &Interaction) -> Interaction {
+        Interaction {
+            method: interaction.method.clone(),
+            url: self.redact(&interaction.url),
+            request_body: interaction.request_body.as_deref().map(|b| self.redact(b)),
+            status: interaction.status,
+            location: interaction.location.as_deref().map(|l| self.redact(l)),
+            retry_after: interaction.retry_after.clone(),
+            body: self.redact(&interaction.body),
+        }
+    }
+}
+
+// Replaces the secret only where it isn't part of a longer word or number.
+fn replace_whole(text: &str, secret: &str, placeholder: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = 0;
+    for (i, _) in text.match_indices(secret) {
+        if i < rest
+            || is_word(text[..i].chars().next_back())
+            || is_word(text[i + secret.len()..].chars().next())
+        {
+            continue;
+        }
+        redacted.push_str(&text[rest..i]);
+        redacted.push_str(placeholder);
+        rest = i + secret.len();
+    }
+    redacted.push_str(&text[rest..]);
+    redacted
+}
+
+fn relative_url(url: &reqwest::Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+#[derive(Debug)]
+enum Mode {
+    // The interactions as they were made, so they can be redacted again when a secret turns up
+    // only after they were recorded.
+    Record(Redactor, Vec<Interaction>),
+    // Which interactions were already served.
+    Replay(Vec<bool>),
+}
+
+#[derive(Debug)]
+struct CassetteState {
+    mode: Mode,
+    interactions: Vec<Interaction>,
+}
+
+// Requests and responses saved to a json lines file, one interaction per line. A recording
+// cassette appends every interaction the client makes, with tokens and account numbers redacted,
+// and a replaying one answers the client's requests from the file instead of Schwab.
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    state: Mutex<CassetteState>,
+    // Opened on the first recorded interaction. Held while appending, so lines are written in the
+    // order the interactions are recorded.
+    file: tokio::sync::Mutex<Option<tokio::fs::File>>,
+}
+
+impl Cassette {
+    pub fn record(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            state: Mutex::new(CassetteState {
+                mode: Mode::Record(Redactor::default(), Vec::new()),
+                interactions: Vec::new(),
+            }),
+            file: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn replay(path: &str) -> Result<Self, Error> {
+        let interactions = fs::read_to_string(path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<Interaction>, _>>()?;
+        Ok(Self {
+            path: PathBuf::from(path),
+            state: Mutex::new(CassetteState {
+                mode: Mode::Replay(vec![false; interactions.len()]),
+                interactions,
+            }),
+            file: tokio::sync::Mutex::new(None),
+        })
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.state.lock().unwrap().mode, Mode::Replay(_))
+    }
+
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.state.lock().unwrap().interactions.clone()
+    }
+
+    // Reads the response, saves the redacted interaction and hands back an identical response.
+    pub async fn record_response(
+        &self,
+        method: &reqwest::Method,
+        url: &reqwest::Url,
+        request_body: Option<String>,
+        response: reqwest::Response,
+    ) -> Result<reqwest::Response, Error> {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        };
+        let mut interaction = Interaction {
+            method: method.to_string(),
+            url: relative_url(url),
+            request_body,
+            status: response.status().as_u16(),
+            location: header(reqwest::header::LOCATION),
+            retry_after: header(reqwest::header::RETRY_AFTER),
+            body: String::new(),
+        };
+        interaction.body = response.text().await?;
+
+        let mut file = self.file.lock().await;
+        let (lines, rewrite) = {
+            let mut state = self.state.lock().unwrap();
+            let CassetteState { mode, interactions } = &mut *state;
+            let Mode::Record(redactor, recorded) = mode else {
+                return Err("Can't record to a replaying cassette".into());
+            };
+            let known_secrets = redactor.replacements.len();
+            for body in [Some(&interaction.body), interaction.request_body.as_ref()]
+                .into_iter()
+                .flatten()
+            {
+                if let Ok(value) = serde_json::from_str::<Value>(body) {
+                    redactor.collect(&value);
+                }
+            }
+            recorded.push(interaction.clone());
+
+            // An earlier interaction may hold a secret only learned now, e.g. a url with the
+            // account hash recorded before the account numbers, so everything is redacted and
+            // written again.
+            let rewrite = redactor.replacements.len() > known_secrets && recorded.len() > 1;
+            let written = match rewrite {
+                true => {
+                    *interactions = recorded
+                        .iter()
+                        .map(|i| redactor.redact_interaction(i))
+                        .collect();
+                    &interactions[..]
+                }
+                false => {
+                    interactions.push(redactor.redact_interaction(&interaction));
+                    &interactions[interactions.len() - 1..]
+                }
+            };
+            let mut lines = Vec::new();
+            for interaction in written {
+                lines.extend(serde_json::to_vec(interaction)?);
+                lines.push(b'\n');
+            }
+            (lines, rewrite)
+        };
+
+        // Otherwise only the new line is written, so a long session doesn't rewrite the whole file
+        // on every request.
+        let file = match file.as_mut() {
+            Some(file) if !rewrite => file,
+            _ => file.insert(tokio::fs::File::create(&self.path).await?),
+        };
+        file.write_all(&lines).await?;
+        file.flush().await?;
+
+        interaction.into_response()
+    }
+
+    // The response recorded for the request. Interactions are served in the order they were
+    // recorded, preferring the same query; timestamps in queries differ between runs, so any
+    // request to the same path will do otherwise. Once every interaction for a path is used the
+    // last one is served again, as for a polling loop that ran longer than the recording.
+    pub fn replay_response(
+        &self,
+        method: &reqwest::Method,
+        url: &reqwest::Url,
+    ) -> Result<reqwest::Response, Error> {
+        let url = relative_url(url);
+        let mut state = self.state.lock().unwrap();
+        let CassetteState { mode, interactions } = &mut *state;
+        let Mode::Replay(used) = mode else {
+            return Err("Can't replay from a recording cassette".into());
+        };
+
+        let same_path = |i: &Interaction| {
+            i.method == method.as_str() && i.path() == url.split('?').next().unwrap_or(&url)
+        };
+        let unused = |i: &usize| !used[*i] && same_path(&interactions[*i]);
+        let index = (0..interactions.len())
+            .find(|i| unused(i) && interactions[*i].url == url)
+            .or_else(|| (0..interactions.len()).find(unused))
+            .or_else(|| {
+                (0..interactions.len())
+                    .rev()
+                    .find(|i| same_path(&interactions[*i]))
+            })
+            .ok_or(format!("No recorded interaction for {} {}", method, url))?;
+        used[index] = true;
+
+        interactions[index].clone().into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Redactor, replace_whole};
+    use serde_json::json;
+
+    #[test]
+    fn test_secrets_are_replaced_consistently() {
+        let mut redactor = Redactor::default();
+        redactor.collect(&json!([{"accountNumber": "12345678", "hashValue": "ABC123"}]));
+        redactor.collect(&json!({"securitiesAccount": {"accountNumber": 12345678}}));
+        redactor.collect(&json!({"access_token": "secret-token"}));
+
+        assert_eq!(
+            redactor.redact("/trader/v1/accounts/ABC123/orders?accountNumber=12345678"),
+            "/trader/v1/accounts/REDACTEDHASH2/orders?accountNumber=90000001"
+        );
+        assert_eq!(
+            redactor.redact(r#"{"accountNumber":12345678,"token":"secret-token"}"#),
+            r#"{"accountNumber":90000001,"token":"REDACTED"}"#
+        );
+        // Only whole numbers are replaced.
+        assert_eq!(
+            replace_whole("123456789 12345678", "12345678", "X"),
+            "123456789 X"
+        );
+    }
+}
@@ -1,49 +1,232 @@
 use crate::{
     Error,
-    oauth::token,
+    oauth::token_provider::TokenProvider,
     schwab::{
-        endpoints,
-        schemas::accounts_and_trading::accounts::{
-            AccountNumbers, AccountTypes, Accounts, Order, OrderRequest, Transaction,
-            TransactionType, Transactions,
+        cassette::Cassette,
+        endpoints::Endpoints,
+        error::SchwabApiError,
+        history::{
+            DateWindows, MAX_ORDER_RANGE_DAYS, MAX_ORDER_RESULTS, MAX_TRANSACTION_RANGE_DAYS,
+            MAX_TRANSACTION_RESULTS, fetch_windows,
+        },
+        order_tracker::{CLOCK_SKEW_SECONDS, parse_entered_time},
+        query::{OrderQuery, TransactionQuery},
+        rate_limiter::{RateLimitConfig, RateLimiter},
+        retry::RetryPolicy,
+        schemas::{
+            accounts_and_trading::accounts::{
+                AccountNumbers, AccountTypes, Accounts, Order, OrderRequest, OrderStatus,
+                PreviewOrder, Transaction, Transactions,
+            },
+            accounts_and_trading::user_preference::UserPreference,
+            market_data::{
+                instruments::{Instrument, InstrumentsResponse, Projection},
+                market_hours::{MarketHoursResponse, MarketType},
+                movers::{MoverIndex, MoverSort, MoversResponse},
+                option_chains::{ExpirationChain, OptionChain, PutCall},
+                price_history::{CandleList, FrequencyType, PeriodType},
+                quotes::QuoteResponse,
+            },
         },
     },
 };
-use chrono::{DateTime, Utc};
-use oauth2::{TokenResponse, reqwest};
+use chrono::{DateTime, NaiveDate, Utc};
+use oauth2::reqwest;
 use serde::{de::Deserialize, ser::Serialize};
+use std::sync::Arc;
+
+// Whether a failed post_order certainly didn't create the order: Schwab rejected it or never
+// handled it. After any other error the order may still show up in the orders list.
+pub fn is_order_not_created(e: &Error) -> bool {
+    RetryPolicy::is_unhandled(e)
+        || (e.downcast_ref::<SchwabApiError>().is_some() && !RetryPolicy::is_transient(e))
+}
+
+// How much earlier than our submission Schwab may say an order was entered, for the clock
+// difference between us and Schwab and the entered time's whole seconds.
+const ORDER_ENTRY_SKEW_SECONDS: i64 = 5;
+
+// An order Schwab accepted. The order id comes from the Location header since the response has no
+// body.
+#[derive(Debug, Clone)]
+pub struct PlacedOrder {
+    pub order_id: i64,
+    pub location: String,
+}
 
+impl PlacedOrder {
+    fn from_location(location: &str) -> Result<Self, Error> {
+        let order_id = location
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .and_then(|id| id.parse().ok())
+            .ok_or(format!("No order id in Location header '{}'", location))?;
+        Ok(Self {
+            order_id,
+            location: location.to_string(),
+        })
+    }
+}
+
+// Built once and cloned into every task that talks to Schwab. Clones share the connection pool,
+// the token provider, the rate limiter and the cassette, so every request counts against the same
+// limits.
+#[derive(Clone)]
 pub struct SchwabClient {
     client: reqwest::Client,
-    auth_token: token::OauthTokenResponse,
+    tokens: TokenProvider,
+    endpoints: Endpoints,
+    rate_limiter: Arc<RateLimiter>,
+    retry_policy: RetryPolicy,
+    cassette: Option<Arc<Cassette>>,
 }
 
 impl SchwabClient {
-    pub fn new(auth_token: token::OauthTokenResponse) -> Self {
+    pub fn new(tokens: impl Into<TokenProvider>, endpoints: Endpoints) -> Self {
         Self {
             client: reqwest::Client::new(),
-            auth_token: auth_token,
+            tokens: tokens.into(),
+            endpoints,
+            rate_limiter: Arc::new(RateLimiter::default()),
+            retry_policy: RetryPolicy::default(),
+            cassette: None,
         }
     }
 
+    pub fn with_rate_limits(mut self, config: &RateLimitConfig) -> Self {
+        self.rate_limiter = Arc::new(RateLimiter::new(config));
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: &RetryPolicy) -> Self {
+        self.retry_policy = retry_policy.clone();
+        self
+    }
+
+    pub fn rate_limiter(&self) -> Arc<RateLimiter> {
+        self.rate_limiter.clone()
+    }
+
+    // Records every interaction to the cassette, or answers requests from it when it is
+    // replaying.
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(Arc::new(cassette));
+        self
+    }
+
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    pub async fn access_token(&self) -> Result<String, Error> {
+        self.tokens.access_token().await
+    }
+
+    // Whether there is an unexpired access token to make requests with.
+    pub async fn is_authorized(&self) -> bool {
+        self.tokens.has_token().await
+    }
+
+    async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response, Error> {
+        if let Some(cassette) = self.cassette.as_deref()
+            && cassette.is_replaying()
+        {
+            return cassette.replay_response(request.method(), request.url());
+        }
+
+        self.rate_limiter
+            .acquire(self.endpoints.api(request.url().as_str()))
+            .await;
+        match self.cassette.as_deref() {
+            Some(cassette) => {
+                let method = request.method().clone();
+                let url = request.url().clone();
+                let request_body = request
+                    .body()
+                    .and_then(|b| b.as_bytes())
+                    .map(|b| String::from_utf8_lossy(b).to_string());
+                let response = self.client.execute(request).await?;
+                cassette
+                    .record_response(&method, &url, request_body, response)
+                    .await
+            }
+            None => Ok(self.client.execute(request).await?),
+        }
+    }
+
+    // Sends the request and turns any non 2xx response into a SchwabApiError. Waits for the rate
+    // limiter first, so callers queue up rather than being turned away by Schwab. A request
+    // rejected as unauthorized is sent once more after refreshing the token.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, Error> {
+        let request = request.build()?;
+        let mut refreshed = false;
+        loop {
+            let access_token = self.tokens.access_token().await?;
+            let mut attempt = request.try_clone().ok_or("Request can't be sent again")?;
+            attempt.headers_mut().insert(
+                reqwest::header::AUTHORIZATION,
+                reqwest::header::HeaderValue::from_str(&format!("Bearer {}", access_token))?,
+            );
+            let response = self.execute(attempt).await?;
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(response);
+            }
+            if status == reqwest::StatusCode::UNAUTHORIZED && !refreshed {
+                refreshed = true;
+                match self.tokens.refresh(&access_token).await {
+                    Ok(()) => {
+                        log::info!("Access token was rejected, retrying with a refreshed token");
+                        continue;
+                    }
+                    Err(e) => log::warn!("Couldn't refresh rejected access token: '{}'", e),
+                }
+            }
+
+            let headers = response.headers().clone();
+            let body = response.text().await?;
+            log::debug!("Error response {} from '{}'", status, body);
+            return Err(SchwabApiError::from_response(status, &headers, &body).into());
+        }
+    }
+
+    async fn get_once(&self, endpoint: &str) -> Result<String, Error> {
+        Ok(self.send(self.client.get(endpoint)).await?.text().await?)
+    }
+
+    // GETs are idempotent, so transient failures are retried as the retry policy allows.
     pub async fn get(&self, endpoint: String) -> Result<String, Error> {
-        Ok(self
-            .client
-            .get(endpoint)
-            .bearer_auth(self.auth_token.access_token().secret())
-            .send()
-            .await?
-            .text()
-            .await?)
+        let mut retry = 0;
+        loop {
+            match self.get_once(&endpoint).await {
+                Ok(body) => return Ok(body),
+                Err(e) => match self.retry_policy.delay(retry, &e) {
+                    Some(delay) => {
+                        log::warn!(
+                            "Retrying '{}' in {}ms after: '{}'",
+                            endpoint,
+                            delay.as_millis(),
+                            e
+                        );
+                        tokio::time::sleep(delay).await;
+                        retry += 1;
+                    }
+                    None => return Err(e),
+                },
+            }
+        }
     }
 
     pub async fn post(&self, endpoint: String, body: String) -> Result<String, Error> {
         Ok(self
-            .client
-            .post(endpoint)
-            .body(body)
-            .bearer_auth(self.auth_token.access_token().secret())
-            .send()
+            .send(
+                self.client
+                    .post(endpoint)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(body),
+            )
             .await?
             .text()
             .await?)
@@ -66,19 +249,137 @@ impl SchwabClient {
     }
 
     pub async fn get_account_numbers(&self) -> Result<AccountNumbers, Error> {
-        self.get_json(endpoints::account_numbers()).await
+        self.get_json(self.endpoints.account_numbers()).await
+    }
+
+    // Account nicknames and what's needed to connect to the streamer.
+    pub async fn get_user_preference(&self) -> Result<UserPreference, Error> {
+        self.get_json(self.endpoints.user_preference()).await
     }
 
     pub async fn get_accounts(&self) -> Result<Accounts, Error> {
-        self.get_json(endpoints::accounts()).await
+        self.get_json(self.endpoints.accounts()).await
     }
 
     pub async fn get_account(&self, account_hash: &str) -> Result<AccountTypes, Error> {
-        self.get_json(endpoints::account(account_hash)).await
+        self.get_json(self.endpoints.account(account_hash)).await
+    }
+
+    pub async fn get_quote(&self, ticker: &str) -> Result<QuoteResponse, Error> {
+        self.get_json(self.endpoints.ticker_quotes_data(ticker))
+            .await
+    }
+
+    // Quotes for every symbol in a single request.
+    pub async fn get_quotes(&self, symbols: &[String]) -> Result<QuoteResponse, Error> {
+        self.get_json(self.endpoints.quotes(symbols)).await
+    }
+
+    pub async fn get_price_history(
+        &self,
+        symbol: &str,
+        period_type: &PeriodType,
+        frequency_type: &FrequencyType,
+        frequency: u32,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<CandleList, Error> {
+        self.get_json(self.endpoints.price_history(
+            symbol,
+            period_type,
+            frequency_type,
+            frequency,
+            start_date,
+            end_date,
+        ))
+        .await
+    }
+
+    pub async fn get_daily_candles(
+        &self,
+        symbol: &str,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<CandleList, Error> {
+        self.get_price_history(
+            symbol,
+            &PeriodType::Year,
+            &FrequencyType::Daily,
+            1,
+            start_date,
+            end_date,
+        )
+        .await
+    }
+
+    pub async fn get_option_chain(
+        &self,
+        symbol: &str,
+        contract_type: Option<&PutCall>,
+        from_date: NaiveDate,
+        to_date: NaiveDate,
+    ) -> Result<OptionChain, Error> {
+        self.get_json(
+            self.endpoints
+                .option_chain(symbol, contract_type, from_date, to_date),
+        )
+        .await
+    }
+
+    pub async fn get_expiration_chain(&self, symbol: &str) -> Result<ExpirationChain, Error> {
+        self.get_json(self.endpoints.expiration_chain(symbol)).await
+    }
+
+    pub async fn search_instruments(
+        &self,
+        symbol: &str,
+        projection: &Projection,
+    ) -> Result<Vec<Instrument>, Error> {
+        Ok(self
+            .get_json::<InstrumentsResponse>(self.endpoints.instruments(symbol, projection))
+            .await?
+            .instruments)
+    }
+
+    // The instruments of the symbols that exist; unknown symbols are left out.
+    pub async fn get_instruments(&self, symbols: &[String]) -> Result<Vec<Instrument>, Error> {
+        self.search_instruments(&symbols.join(","), &Projection::SymbolSearch)
+            .await
+    }
+
+    // Like get_instruments, with the fundamentals of each instrument filled in.
+    pub async fn get_fundamentals(&self, symbols: &[String]) -> Result<Vec<Instrument>, Error> {
+        self.search_instruments(&symbols.join(","), &Projection::Fundamental)
+            .await
     }
 
-    pub async fn get_quotes(&self, ticker: &str) -> Result<String, Error> {
-        self.get(endpoints::ticker_quotes_data(ticker)).await
+    pub async fn get_instrument_by_cusip(&self, cusip: &str) -> Result<Instrument, Error> {
+        self.get_json::<InstrumentsResponse>(self.endpoints.instrument_by_cusip(cusip))
+            .await?
+            .instruments
+            .into_iter()
+            .next()
+            .ok_or(format!("No instrument with CUSIP '{}'", cusip).into())
+    }
+
+    // The top movers of an index, ordered by the sort.
+    pub async fn get_movers(
+        &self,
+        index: &MoverIndex,
+        sort: &MoverSort,
+        frequency: u32,
+    ) -> Result<MoversResponse, Error> {
+        self.get_json(self.endpoints.movers(index, sort, frequency))
+            .await
+    }
+
+    pub async fn get_market_hours(
+        &self,
+        markets: &[MarketType],
+        date: NaiveDate,
+    ) -> Result<MarketHoursResponse, Error> {
+        self.get_json(self.endpoints.market_hours(markets, date))
+            .await
     }
 
     pub async fn get_orders(
@@ -87,31 +388,286 @@ impl SchwabClient {
         from_entered_time: DateTime<Utc>,
         to_entered_time: DateTime<Utc>,
     ) -> Result<Vec<Order>, Error> {
-        self.get_json(endpoints::orders_range(
+        self.query_orders(
             account_hash,
-            from_entered_time,
-            to_entered_time,
-        ))
+            &OrderQuery::new(from_entered_time, to_entered_time),
+        )
         .await
     }
 
-    pub async fn post_order(&self, account_hash: &str, order: OrderRequest) -> Result<(), Error> {
-        self.post_json(endpoints::orders(account_hash), order).await
+    pub async fn query_orders(
+        &self,
+        account_hash: &str,
+        query: &OrderQuery,
+    ) -> Result<Vec<Order>, Error> {
+        self.get_json(self.endpoints.orders_query(account_hash, query))
+            .await
+    }
+
+    // Orders are placed and replaced with an empty 201 response that points at the new order.
+    async fn send_order(
+        &self,
+        request: reqwest::RequestBuilder,
+        order: &OrderRequest,
+    ) -> Result<PlacedOrder, Error> {
+        let response = self
+            .send(
+                request
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(serde_json::to_string(order)?),
+            )
+            .await?;
+
+        PlacedOrder::from_location(
+            response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .ok_or("Order response has no Location header")?
+                .to_str()?,
+        )
+    }
+
+    // Placing an order isn't idempotent, so it is only sent again after an error that proves
+    // Schwab never handled it. After any other transient error, e.g. a timeout or a 502, the order
+    // may have been created, so the orders list is polled for it instead; if it doesn't show up the
+    // original error is returned. Orders already known to the caller are never taken for this one.
+    pub async fn post_order(
+        &self,
+        account_hash: &str,
+        order: &OrderRequest,
+        known_order_ids: &[i64],
+    ) -> Result<PlacedOrder, Error> {
+        let mut retry = 0;
+        loop {
+            let submitted_at = Utc::now();
+            let e = match self
+                .send_order(self.client.post(self.endpoints.orders(account_hash)), order)
+                .await
+            {
+                Ok(placed) => return Ok(placed),
+                Err(e) => e,
+            };
+
+            if RetryPolicy::is_unhandled(&e) {
+                let Some(delay) = self.retry_policy.delay(retry, &e) else {
+                    return Err(e);
+                };
+                log::warn!(
+                    "Placing the order again in {}ms after: '{}'",
+                    delay.as_millis(),
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                retry += 1;
+                continue;
+            }
+            if !RetryPolicy::is_transient(&e) {
+                return Err(e);
+            }
+
+            return match self
+                .confirm_order(account_hash, order, submitted_at, known_order_ids)
+                .await
+            {
+                Some(placed) => {
+                    log::warn!("Order {} was created despite: '{}'", placed.order_id, e);
+                    Ok(placed)
+                }
+                None => {
+                    log::error!(
+                        "Order didn't show up in the orders list, not placing it again: '{}'",
+                        e
+                    );
+                    Err(e)
+                }
+            };
+        }
+    }
+
+    // Polls the orders list for the order over the confirmation window, since Schwab can be slow
+    // to list an order it created.
+    async fn confirm_order(
+        &self,
+        account_hash: &str,
+        order: &OrderRequest,
+        submitted_at: DateTime<Utc>,
+        known_order_ids: &[i64],
+    ) -> Option<PlacedOrder> {
+        let deadline = tokio::time::Instant::now()
+            + std::time::Duration::from_millis(self.retry_policy.order_confirmation_ms);
+        loop {
+            tokio::time::sleep(std::time::Duration::from_millis(
+                self.retry_policy.order_poll_ms,
+            ))
+            .await;
+            match self
+                .find_created_order(account_hash, order, submitted_at, known_order_ids)
+                .await
+            {
+                Ok(Some(placed)) => return Some(placed),
+                Ok(None) => (),
+                Err(e) => log::warn!("Couldn't check whether the order was created: '{}'", e),
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return None;
+            }
+        }
+    }
+
+    // An order entered since the submission, other than the known ones, whose legs are the same as
+    // the request's.
+    async fn find_created_order(
+        &self,
+        account_hash: &str,
+        order: &OrderRequest,
+        submitted_at: DateTime<Utc>,
+        known_order_ids: &[i64],
+    ) -> Result<Option<PlacedOrder>, Error> {
+        let orders = self
+            .get_orders(
+                account_hash,
+                submitted_at - chrono::Duration::seconds(CLOCK_SKEW_SECONDS),
+                Utc::now() + chrono::Duration::seconds(CLOCK_SKEW_SECONDS),
+            )
+            .await?;
+        let entered_since_submission = |o: &Order| {
+            parse_entered_time(&o.entered_time).is_some_and(|t| {
+                t >= submitted_at - chrono::Duration::seconds(ORDER_ENTRY_SKEW_SECONDS)
+            })
+        };
+        let same_legs = |o: &Order| {
+            o.order_leg_collection.len() == order.order_leg_collection.len()
+                && o.order_leg_collection
+                    .iter()
+                    .zip(order.order_leg_collection.iter())
+                    .all(|(a, b)| {
+                        a.instruction == b.instruction
+                            && a.quantity == b.quantity
+                            && a.instrument.symbol() == b.instrument.symbol()
+                    })
+        };
+
+        Ok(orders
+            .iter()
+            .filter(|o| {
+                !known_order_ids.contains(&o.order_id)
+                    && entered_since_submission(o)
+                    && same_legs(o)
+            })
+            .min_by_key(|o| o.order_id)
+            .map(|o| PlacedOrder {
+                order_id: o.order_id,
+                location: self.endpoints.order(account_hash, o.order_id),
+            }))
+    }
+
+    // Validates the order and estimates its costs without placing it.
+    pub async fn preview_order(
+        &self,
+        account_hash: &str,
+        order: &OrderRequest,
+    ) -> Result<PreviewOrder, Error> {
+        self.post_json(self.endpoints.preview_order(account_hash), order)
+            .await
+    }
+
+    pub async fn get_order(&self, account_hash: &str, order_id: i64) -> Result<Order, Error> {
+        self.get_json(self.endpoints.order(account_hash, order_id))
+            .await
+    }
+
+    pub async fn cancel_order(&self, account_hash: &str, order_id: i64) -> Result<(), Error> {
+        self.send(
+            self.client
+                .delete(self.endpoints.order(account_hash, order_id)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    // Replacing cancels the existing order and creates a new one, which is returned.
+    pub async fn replace_order(
+        &self,
+        account_hash: &str,
+        order_id: i64,
+        order: &OrderRequest,
+    ) -> Result<PlacedOrder, Error> {
+        self.send_order(
+            self.client
+                .put(self.endpoints.order(account_hash, order_id)),
+            order,
+        )
+        .await
+    }
+
+    pub async fn get_all_orders(
+        &self,
+        from_entered_time: DateTime<Utc>,
+        to_entered_time: DateTime<Utc>,
+        status: Option<&OrderStatus>,
+    ) -> Result<Vec<Order>, Error> {
+        let mut query = OrderQuery::new(from_entered_time, to_entered_time);
+        if let Some(status) = status {
+            query = query.status(status.clone());
+        }
+        self.query_all_orders(&query).await
+    }
+
+    pub async fn query_all_orders(&self, query: &OrderQuery) -> Result<Vec<Order>, Error> {
+        self.get_json(self.endpoints.all_orders(query)).await
     }
 
     pub async fn get_transactions(
         &self,
         account_hash: &str,
-        start_date: DateTime<Utc>,
-        end_date: DateTime<Utc>,
-        transaction_type: TransactionType,
+        query: &TransactionQuery,
     ) -> Result<Transactions, Error> {
-        self.get_json(endpoints::transactions(
-            account_hash,
-            start_date,
-            end_date,
-            transaction_type,
-        ))
+        self.get_json(self.endpoints.transactions(account_hash, query))
+            .await
+    }
+
+    // Orders entered over any range, fetched in windows Schwab accepts. A window is split when it
+    // returns the query's max results, or Schwab's default when none is given.
+    pub async fn get_order_history(
+        &self,
+        account_hash: &str,
+        query: &OrderQuery,
+    ) -> Result<Vec<Order>, Error> {
+        fetch_windows(
+            DateWindows::new(
+                query.from_entered_time,
+                query.to_entered_time,
+                chrono::Duration::days(MAX_ORDER_RANGE_DAYS),
+            ),
+            query.max_results.unwrap_or(MAX_ORDER_RESULTS),
+            |o: &Order| o.order_id,
+            |from, to| {
+                let query = query.with_range(from, to);
+                async move { self.query_orders(account_hash, &query).await }
+            },
+        )
+        .await
+    }
+
+    // Transactions over any range, e.g. several years, fetched a year at a time.
+    pub async fn get_transaction_history(
+        &self,
+        account_hash: &str,
+        query: &TransactionQuery,
+    ) -> Result<Transactions, Error> {
+        fetch_windows(
+            DateWindows::new(
+                query.start_date,
+                query.end_date,
+                chrono::Duration::days(MAX_TRANSACTION_RANGE_DAYS),
+            ),
+            MAX_TRANSACTION_RESULTS,
+            |t: &Transaction| t.activity_id,
+            |start, end| {
+                let query = query.with_range(start, end);
+                async move { self.get_transactions(account_hash, &query).await }
+            },
+        )
         .await
     }
 
@@ -120,7 +676,27 @@ impl SchwabClient {
         account_hash: &str,
         transaction_id: &str,
     ) -> Result<Transaction, Error> {
-        self.get_json(endpoints::transaction(account_hash, transaction_id))
+        self.get_json(self.endpoints.transaction(account_hash, transaction_id))
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PlacedOrder;
+
+    #[test]
+    fn test_order_id_from_location() {
+        let placed = PlacedOrder::from_location(
+            "https://api.schwabapi.com/trader/v1/accounts/ABC123/orders/1004055538",
+        )
+        .unwrap();
+        assert_eq!(placed.order_id, 1004055538);
+        assert!(
+            PlacedOrder::from_location(
+                "https://api.schwabapi.com/trader/v1/accounts/ABC123/orders"
+            )
+            .is_err()
+        );
+    }
+}
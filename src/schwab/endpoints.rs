@@ -1,65 +1,209 @@
-use crate::schwab::schemas::accounts_and_trading::accounts::TransactionType;
-use chrono::{DateTime, Utc, format::SecondsFormat};
+use crate::schwab::{
+    query::{OrderQuery, TransactionQuery},
+    rate_limiter::Api,
+    schemas::market_data::{
+        instruments::Projection,
+        market_hours::MarketType,
+        movers::{MoverIndex, MoverSort},
+        option_chains::PutCall,
+        price_history::{FrequencyType, PeriodType},
+    },
+};
+use chrono::{DateTime, NaiveDate, Utc};
 
-const MARKET_DATA_ENDPOINT: &str = "https://api.schwabapi.com/marketdata/v1";
-const TRADER_ENDPOINT: &str = "https://api.schwabapi.com/trader/v1";
+pub const DEFAULT_BASE_URL: &str = "https://api.schwabapi.com";
 
-pub fn accounts() -> String {
-    format!("{}/accounts?fields=positions", TRADER_ENDPOINT)
+// The Schwab API urls, rooted at a configurable base url so the client can be pointed at a mock
+// server.
+#[derive(Clone, Debug)]
+pub struct Endpoints {
+    market_data: String,
+    trader: String,
 }
 
-pub fn account(account_number: &str) -> String {
-    format!(
-        "{}/accounts/{}?fields=positions",
-        TRADER_ENDPOINT, account_number
-    )
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
+    }
 }
 
-pub fn account_numbers() -> String {
-    format!("{}/accounts/accountNumbers", TRADER_ENDPOINT)
-}
+impl Endpoints {
+    pub fn new(base_url: &str) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        Self {
+            market_data: format!("{}/marketdata/v1", base_url),
+            trader: format!("{}/trader/v1", base_url),
+        }
+    }
 
-pub fn ticker_quotes_data(ticker: &str) -> String {
-    format!("{}/{}/quotes", MARKET_DATA_ENDPOINT, ticker)
-}
+    // Which API's limit a url counts against.
+    pub fn api(&self, url: &str) -> Api {
+        match url.starts_with(&self.market_data) {
+            true => Api::MarketData,
+            false => Api::Trader,
+        }
+    }
 
-pub fn transactions(
-    account_number: &str,
-    start_date: DateTime<Utc>,
-    end_date: DateTime<Utc>,
-    transaction_type: TransactionType,
-) -> String {
-    format!(
-        "{}/accounts/{}/transactions?startDate={}&endDate={}&types={}",
-        TRADER_ENDPOINT,
-        account_number,
-        urlencoding::encode(&start_date.to_rfc3339_opts(SecondsFormat::Secs, true)),
-        urlencoding::encode(&end_date.to_rfc3339_opts(SecondsFormat::Secs, true)),
-        transaction_type
-    )
-}
+    pub fn accounts(&self) -> String {
+        format!("{}/accounts?fields=positions", self.trader)
+    }
 
-pub fn transaction(account_number: &str, transaction_id: &str) -> String {
-    format!(
-        "{}/accounts/{}/transactions/{}",
-        TRADER_ENDPOINT, account_number, transaction_id
-    )
-}
+    pub fn account(&self, account_number: &str) -> String {
+        format!(
+            "{}/accounts/{}?fields=positions",
+            self.trader, account_number
+        )
+    }
 
-pub fn orders(account_number: &str) -> String {
-    format!("{}/accounts/{}/orders", TRADER_ENDPOINT, account_number)
-}
+    pub fn account_numbers(&self) -> String {
+        format!("{}/accounts/accountNumbers", self.trader)
+    }
+
+    pub fn user_preference(&self) -> String {
+        format!("{}/userPreference", self.trader)
+    }
+
+    pub fn ticker_quotes_data(&self, ticker: &str) -> String {
+        format!("{}/{}/quotes", self.market_data, ticker)
+    }
+
+    pub fn quotes(&self, symbols: &[String]) -> String {
+        format!(
+            "{}/quotes?symbols={}&indicative=false",
+            self.market_data,
+            urlencoding::encode(&symbols.join(","))
+        )
+    }
+
+    pub fn price_history(
+        &self,
+        symbol: &str,
+        period_type: &PeriodType,
+        frequency_type: &FrequencyType,
+        frequency: u32,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> String {
+        format!(
+            "{}/pricehistory?symbol={}&periodType={}&frequencyType={}&frequency={}&startDate={}&endDate={}&needExtendedHoursData=false",
+            self.market_data,
+            urlencoding::encode(symbol),
+            period_type,
+            frequency_type,
+            frequency,
+            start_date.timestamp_millis(),
+            end_date.timestamp_millis(),
+        )
+    }
+
+    // Contracts of both types are returned when no contract type is given.
+    pub fn option_chain(
+        &self,
+        symbol: &str,
+        contract_type: Option<&PutCall>,
+        from_date: NaiveDate,
+        to_date: NaiveDate,
+    ) -> String {
+        format!(
+            "{}/chains?symbol={}&contractType={}&fromDate={}&toDate={}",
+            self.market_data,
+            urlencoding::encode(symbol),
+            contract_type
+                .map(|c| c.to_string())
+                .unwrap_or("ALL".to_string()),
+            from_date.format("%Y-%m-%d"),
+            to_date.format("%Y-%m-%d"),
+        )
+    }
+
+    pub fn expiration_chain(&self, symbol: &str) -> String {
+        format!(
+            "{}/expirationchain?symbol={}",
+            self.market_data,
+            urlencoding::encode(symbol)
+        )
+    }
+
+    pub fn market_hours(&self, markets: &[MarketType], date: NaiveDate) -> String {
+        format!(
+            "{}/markets?markets={}&date={}",
+            self.market_data,
+            markets
+                .iter()
+                .map(|m| m.to_string())
+                .collect::<Vec<String>>()
+                .join(","),
+            date.format("%Y-%m-%d"),
+        )
+    }
+
+    // Several symbols can be searched for at once, e.g. "VTI,VXUS", and the regex projections take
+    // a pattern such as "VT.*".
+    pub fn instruments(&self, symbol: &str, projection: &Projection) -> String {
+        format!(
+            "{}/instruments?symbol={}&projection={}",
+            self.market_data,
+            urlencoding::encode(symbol),
+            projection
+        )
+    }
+
+    pub fn instrument_by_cusip(&self, cusip: &str) -> String {
+        format!("{}/instruments/{}", self.market_data, cusip)
+    }
+
+    pub fn movers(&self, index: &MoverIndex, sort: &MoverSort, frequency: u32) -> String {
+        format!(
+            "{}/movers/{}?sort={}&frequency={}",
+            self.market_data,
+            urlencoding::encode(&index.to_string()),
+            sort,
+            frequency
+        )
+    }
+
+    pub fn transactions(&self, account_number: &str, query: &TransactionQuery) -> String {
+        format!(
+            "{}/accounts/{}/transactions?{}",
+            self.trader,
+            account_number,
+            query.query_string()
+        )
+    }
+
+    pub fn transaction(&self, account_number: &str, transaction_id: &str) -> String {
+        format!(
+            "{}/accounts/{}/transactions/{}",
+            self.trader, account_number, transaction_id
+        )
+    }
+
+    pub fn orders(&self, account_number: &str) -> String {
+        format!("{}/accounts/{}/orders", self.trader, account_number)
+    }
+
+    pub fn orders_query(&self, account_number: &str, query: &OrderQuery) -> String {
+        format!(
+            "{}/accounts/{}/orders?{}",
+            self.trader,
+            account_number,
+            query.query_string()
+        )
+    }
+
+    pub fn preview_order(&self, account_number: &str) -> String {
+        format!("{}/accounts/{}/previewOrder", self.trader, account_number)
+    }
+
+    pub fn order(&self, account_number: &str, order_id: i64) -> String {
+        format!(
+            "{}/accounts/{}/orders/{}",
+            self.trader, account_number, order_id
+        )
+    }
 
-pub fn orders_range(
-    account_number: &str,
-    from_entered_time: DateTime<Utc>,
-    to_entered_time: DateTime<Utc>,
-) -> String {
-    format!(
-        "{}/accounts/{}/orders?fromEnteredTime={}&toEnteredTime={}",
-        TRADER_ENDPOINT,
-        account_number,
-        urlencoding::encode(&from_entered_time.to_rfc3339_opts(SecondsFormat::Secs, true)),
-        urlencoding::encode(&to_entered_time.to_rfc3339_opts(SecondsFormat::Secs, true)),
-    )
+    // Orders across every linked account.
+    pub fn all_orders(&self, query: &OrderQuery) -> String {
+        format!("{}/orders?{}", self.trader, query.query_string())
+    }
 }
@@ -0,0 +1,135 @@
+use crate::schwab::schemas::accounts_and_trading::accounts::ApiError;
+use chrono::{DateTime, Utc};
+use oauth2::reqwest::{StatusCode, header::HeaderMap};
+use std::{fmt, time::Duration};
+
+// A non 2xx response from the Schwab API, sorted into the cases callers handle differently.
+#[derive(Debug)]
+pub enum SchwabApiError {
+    // The access token is missing, expired or was revoked.
+    Unauthorized(ApiError),
+    Forbidden(ApiError),
+    NotFound(ApiError),
+    RateLimited { retry_after: Option<Duration> },
+    // The request was rejected, e.g. an invalid order or query parameter.
+    Validation(ApiError),
+    ServerError { status: u16, error: ApiError },
+    Unexpected { status: u16, body: String },
+}
+
+impl SchwabApiError {
+    pub fn from_response(status: StatusCode, headers: &HeaderMap, body: &str) -> Self {
+        let api_error = || {
+            serde_json::from_str::<ApiError>(body).unwrap_or_else(|_| ApiError {
+                message: (!body.trim().is_empty()).then(|| body.trim().to_string()),
+                errors: Vec::new(),
+            })
+        };
+
+        match status {
+            StatusCode::UNAUTHORIZED => Self::Unauthorized(api_error()),
+            StatusCode::FORBIDDEN => Self::Forbidden(api_error()),
+            StatusCode::NOT_FOUND => Self::NotFound(api_error()),
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited {
+                retry_after: headers
+                    .get(oauth2::reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| parse_retry_after(v, Utc::now())),
+            },
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+                Self::Validation(api_error())
+            }
+            s if s.is_server_error() => Self::ServerError {
+                status: s.as_u16(),
+                error: api_error(),
+            },
+            s => Self::Unexpected {
+                status: s.as_u16(),
+                body: body.to_string(),
+            },
+        }
+    }
+}
+
+// Retry-After is either a number of seconds or an http date.
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    match value.trim().parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => DateTime::parse_from_rfc2822(value.trim())
+            .ok()
+            .map(|t| (t.to_utc() - now).to_std().unwrap_or(Duration::ZERO)),
+    }
+}
+
+impl fmt::Display for SchwabApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unauthorized(e) => write!(f, "Unauthorized: {}", e),
+            Self::Forbidden(e) => write!(f, "Forbidden: {}", e),
+            Self::NotFound(e) => write!(f, "Not found: {}", e),
+            Self::RateLimited {
+                retry_after: Some(d),
+            } => write!(f, "Rate limited, retry after {}s", d.as_secs()),
+            Self::RateLimited { retry_after: None } => write!(f, "Rate limited"),
+            Self::Validation(e) => write!(f, "Invalid request: {}", e),
+            Self::ServerError { status, error } => write!(f, "Server error {}: {}", status, error),
+            Self::Unexpected { status, body } => {
+                write!(f, "Unexpected response {}: {}", status, body)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchwabApiError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{SchwabApiError, parse_retry_after};
+    use chrono::{TimeZone, Utc};
+    use oauth2::reqwest::{StatusCode, header::HeaderMap};
+    use std::time::Duration;
+
+    #[test]
+    fn test_validation_error_body_is_parsed() {
+        let e = SchwabApiError::from_response(
+            StatusCode::BAD_REQUEST,
+            &HeaderMap::new(),
+            r#"{"message":"Order rejected","errors":["Insufficient funds"]}"#,
+        );
+        match &e {
+            SchwabApiError::Validation(api_error) => {
+                assert_eq!(api_error.message.as_deref(), Some("Order rejected"))
+            }
+            _ => panic!("expected a validation error, got {:?}", e),
+        }
+        assert_eq!(
+            e.to_string(),
+            "Invalid request: Order rejected (Insufficient funds)"
+        );
+
+        let e = SchwabApiError::from_response(
+            StatusCode::NOT_FOUND,
+            &HeaderMap::new(),
+            r#"{"errors":[{"id":"1","status":"404","title":"Not Found","detail":"No symbol"}]}"#,
+        );
+        assert_eq!(e.to_string(), "Not found: Not Found: No symbol");
+    }
+
+    #[test]
+    fn test_rate_limit_reads_retry_after() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Retry-After", "7".parse().unwrap());
+        match SchwabApiError::from_response(StatusCode::TOO_MANY_REQUESTS, &headers, "") {
+            SchwabApiError::RateLimited { retry_after } => {
+                assert_eq!(retry_after, Some(Duration::from_secs(7)))
+            }
+            e => panic!("expected a rate limit error, got {:?}", e),
+        }
+
+        let now = Utc.with_ymd_and_hms(2025, 1, 6, 15, 0, 0).unwrap();
+        assert_eq!(
+            parse_retry_after("Mon, 06 Jan 2025 15:00:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::Error;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashSet, VecDeque};
+
+// Schwab only accepts order queries spanning 60 days, and transaction queries spanning a year.
+pub const MAX_ORDER_RANGE_DAYS: i64 = 60;
+pub const MAX_TRANSACTION_RANGE_DAYS: i64 = 365;
+
+// The default maxResults of the orders endpoints, and the most transactions Schwab returns for
+// one query. A window that comes back full may have had results cut off, so it is split and
+// queried again.
+pub const MAX_ORDER_RESULTS: usize = 3000;
+pub const MAX_TRANSACTION_RESULTS: usize = 3000;
+
+// Windows that are never split further, even when they come back full.
+const MIN_WINDOW_MINUTES: i64 = 60;
+
+// Splits a date range into consecutive windows no longer than the maximum, oldest first.
+// Neighbouring windows share their boundary, so results on it come back twice and must be
+// de-duplicated.
+#[derive(Debug, Clone)]
+pub struct DateWindows {
+    next: DateTime<Utc>,
+    end: DateTime<Utc>,
+    max: Duration,
+}
+
+impl DateWindows {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>, max: Duration) -> Self {
+        Self {
+            next: start,
+            end,
+            max,
+        }
+    }
+}
+
+impl Iterator for DateWindows {
+    type Item = (DateTime<Utc>, DateTime<Utc>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.end || self.max <= Duration::zero() {
+            return None;
+        }
+        let start = self.next;
+        let end = (start + self.max).min(self.end);
+        self.next = end;
+        Some((start, end))
+    }
+}
+
+// Fetches every window and merges the results, keeping the first copy of each id. When a window
+// returns `max_results` items it is split in half and both halves fetched instead, down to
+// MIN_WINDOW_MINUTES; a full window of that size is kept as is and a warning logged.
+pub async fn fetch_windows<T, F, Fut>(
+    windows: DateWindows,
+    max_results: usize,
+    id: impl Fn(&T) -> i64,
+    mut fetch: F,
+) -> Result<Vec<T>, Error>
+where
+    F: FnMut(DateTime<Utc>, DateTime<Utc>) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, Error>>,
+{
+    let mut queue: VecDeque<(DateTime<Utc>, DateTime<Utc>)> = windows.collect();
+    let mut seen = HashSet::new();
+    let mut merged = Vec::new();
+    while let Some((start, end)) = queue.pop_front() {
+        let items = fetch(start, end).await?;
+        if items.len() >= max_results {
+            if end - start > Duration::minutes(MIN_WINDOW_MINUTES) {
+                let middle = start + (end - start) / 2;
+                log::debug!(
+                    "{} results from {} to {}, splitting the window",
+                    items.len(),
+                    start,
+                    end
+                );
+                queue.push_front((middle, end));
+                queue.push_front((start, middle));
+                continue;
+            }
+            log::warn!(
+                "{} results from {} to {} and the window can't be split further, some may be \
+                 missing",
+                items.len(),
+                start,
+                end
+            );
+        }
+
+        merged.extend(items.into_iter().filter(|item| seen.insert(id(item))));
+    }
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DateWindows, fetch_windows};
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_range_is_split_into_windows() {
+        let start = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+        let windows: Vec<_> = DateWindows::new(start, end, Duration::days(365)).collect();
+
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[0].0, start);
+        assert_eq!(windows[2].1, end);
+        assert!(windows.windows(2).all(|w| w[0].1 == w[1].0));
+        assert!(windows.iter().all(|(s, e)| *e - *s <= Duration::days(365)));
+        assert_eq!(DateWindows::new(end, start, Duration::days(1)).count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_windows_are_split_and_duplicates_dropped() {
+        let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        // One item a day, with the ones on a window boundary returned by both windows.
+        let items: Vec<i64> = (0..=20).collect();
+        let mut queries = 0;
+        let merged = fetch_windows(
+            DateWindows::new(start, start + Duration::days(20), Duration::days(10)),
+            8,
+            |i: &i64| *i,
+            |from, to| {
+                queries += 1;
+                let items: Vec<i64> = items
+                    .iter()
+                    .filter(|i| {
+                        let t = start + Duration::days(**i);
+                        t >= from && t <= to
+                    })
+                    .cloned()
+                    .collect();
+                async move { Ok(items) }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(merged, items);
+        // Both 10 day windows were full and split in half.
+        assert_eq!(queries, 6);
+    }
+
+    #[tokio::test]
+    async fn test_full_minimum_windows_are_kept() {
+        let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let mut queries = 0;
+        let merged = fetch_windows(
+            DateWindows::new(start, start + Duration::minutes(60), Duration::days(1)),
+            2,
+            |i: &i64| *i,
+            |_, _| {
+                queries += 1;
+                async move { Ok(vec![1, 2, 3]) }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(merged, [1, 2, 3]);
+        assert_eq!(queries, 1);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{
+    Error,
+    schwab::{client::SchwabClient, schemas::market_data::instruments::Instrument},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Deserializer as jsonDe;
+use std::{collections::HashMap, fs, io::Write, path::PathBuf};
+
+// Names and asset types rarely change, so cached instruments are only looked up again after this
+// many days.
+pub const INSTRUMENT_CACHE_DAYS: i64 = 7;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct CachedInstrument {
+    fetched_at: DateTime<Utc>,
+    instrument: Instrument,
+}
+
+// Instruments kept on disk in a single json file keyed by symbol, so the names and asset types of
+// the traded symbols don't have to be fetched from Schwab on every start.
+pub struct InstrumentCache {
+    path: PathBuf,
+}
+
+impl InstrumentCache {
+    pub fn new(path: &str) -> Result<Self, Error> {
+        let path = PathBuf::from(path);
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self { path })
+    }
+
+    fn load(&self) -> Result<HashMap<String, CachedInstrument>, Error> {
+        match fs::File::open(&self.path) {
+            Ok(fd) => Ok(HashMap::<String, CachedInstrument>::deserialize(
+                &mut jsonDe::from_reader(fd),
+            )?),
+            Err(e) => match e.kind() {
+                std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    fn save(&self, cached: &HashMap<String, CachedInstrument>) -> Result<(), Error> {
+        fs::File::create(&self.path)?.write_all(&serde_json::to_vec(cached)?)?;
+        Ok(())
+    }
+
+    // The instruments of the symbols, fetching the missing and stale ones from Schwab in a single
+    // request. Symbols Schwab doesn't know are left out and asked for again next time.
+    pub async fn instruments(
+        &self,
+        client: &SchwabClient,
+        symbols: &[String],
+    ) -> Result<HashMap<String, Instrument>, Error> {
+        let mut cached = self.load()?;
+        let now = Utc::now();
+        let stale: Vec<String> = symbols
+            .iter()
+            .filter(|symbol| {
+                cached.get(*symbol).is_none_or(|c| {
+                    now - c.fetched_at > chrono::Duration::days(INSTRUMENT_CACHE_DAYS)
+                })
+            })
+            .cloned()
+            .collect();
+
+        if !stale.is_empty() {
+            for instrument in client.get_instruments(&stale).await? {
+                cached.insert(
+                    instrument.symbol.clone(),
+                    CachedInstrument {
+                        fetched_at: now,
+                        instrument,
+                    },
+                );
+            }
+            self.save(&cached)?;
+        }
+
+        Ok(symbols
+            .iter()
+            .filter_map(|symbol| Some((symbol.clone(), cached.get(symbol)?.instrument.clone())))
+            .collect())
+    }
+}
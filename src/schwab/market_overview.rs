@@ -0,0 +1,64 @@
+use crate::{
+    schwab::{
+        client::SchwabClient,
+        schemas::market_data::movers::{MoverIndex, MoverSort},
+    },
+    server::web_resources::files::html::{IndexMovers, Mover},
+};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct MarketOverviewConfig {
+    pub indices: Vec<MoverIndex>,
+    pub sort: MoverSort,
+    // Movers over the last 0, 1, 5, 10, 30 or 60 minutes.
+    pub frequency: u32,
+    pub movers_per_index: usize,
+    pub refresh_seconds: u64,
+}
+
+impl Default for MarketOverviewConfig {
+    fn default() -> Self {
+        Self {
+            indices: vec![MoverIndex::Dji, MoverIndex::Compx, MoverIndex::Spx],
+            sort: MoverSort::PercentChangeUp,
+            frequency: 0,
+            movers_per_index: 5,
+            refresh_seconds: 300,
+        }
+    }
+}
+
+// The top movers of each configured index, as shown on the dashboard. An index that can't be
+// fetched is left out rather than hiding the others.
+pub async fn market_overview(
+    client: &SchwabClient,
+    config: &MarketOverviewConfig,
+) -> Vec<IndexMovers> {
+    let mut overview = Vec::new();
+    for index in config.indices.iter() {
+        match client
+            .get_movers(index, &config.sort, config.frequency)
+            .await
+        {
+            Ok(response) => overview.push(IndexMovers {
+                name: index.name().to_string(),
+                movers: response
+                    .screeners
+                    .into_iter()
+                    .take(config.movers_per_index)
+                    .map(|screener| Mover {
+                        percent_change: screener.percent_change(),
+                        symbol: screener.symbol,
+                        description: screener.description,
+                        last_price: screener.last_price,
+                        volume: screener.volume.or(screener.total_volume),
+                    })
+                    .collect(),
+            }),
+            Err(e) => log::warn!("Couldn't get the movers of {}: '{}'", index, e),
+        }
+    }
+    overview
+}
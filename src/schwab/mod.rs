@@ -1,5 +1,20 @@
 pub mod account_manager;
+pub mod calendar;
+pub mod candle_store;
+pub mod cassette;
 pub mod client;
 pub mod endpoints;
+pub mod error;
+pub mod history;
+pub mod instrument_cache;
+pub mod market_overview;
 pub mod math;
+pub mod options;
+pub mod order_tracker;
+pub mod paper_broker;
+pub mod query;
+pub mod rate_limiter;
+pub mod retry;
+pub mod scheduler;
 pub mod schemas;
+pub mod streamer;
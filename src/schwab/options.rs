@@ -0,0 +1,51 @@
+use crate::{
+    Error,
+    config::TradingCollection,
+    schwab::{
+        client::SchwabClient,
+        error::SchwabApiError,
+        schemas::market_data::option_chains::{ContractSelection, OptionContract},
+    },
+};
+use chrono::{Days, Utc};
+
+// Picks a contract on the first symbol of the collection that has one matching the selection, e.g.
+// a put on an alternate of the collection when harvesting a loss.
+pub async fn select_collection_contract(
+    client: &SchwabClient,
+    collection: &TradingCollection,
+    selection: &ContractSelection,
+) -> Result<Option<OptionContract>, Error> {
+    let today = Utc::now().date_naive();
+    let from_date = today + Days::new(selection.min_days_to_expiration.max(0) as u64);
+    let to_date = today + Days::new(selection.max_days_to_expiration.max(0) as u64);
+
+    for symbol in collection.collection.iter() {
+        let chain = match client
+            .get_option_chain(symbol, Some(&selection.put_call), from_date, to_date)
+            .await
+        {
+            Ok(chain) => chain,
+            Err(e) => match e.downcast_ref::<SchwabApiError>() {
+                Some(SchwabApiError::NotFound(_)) => {
+                    log::info!("No option chain for '{}'", symbol);
+                    continue;
+                }
+                _ => return Err(e),
+            },
+        };
+        if let Some(contract) = chain.select_contract(selection) {
+            log::info!(
+                "Selected {} for collection '{}' ({} {} expiring {})",
+                contract.symbol,
+                collection.id,
+                contract.put_call,
+                contract.strike_price,
+                contract.expiration_date
+            );
+            return Ok(Some(contract.clone()));
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,518 @@
+use crate::schwab::{
+    account_manager::Security,
+    schemas::{
+        accounts_and_trading::accounts::{Order, OrderInstruction, OrderRequest, OrderStatus},
+        streamer::{AccountActivity, ActivityType},
+    },
+};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+
+// Orders entered up to this long before we submitted them are still considered a match, to allow
+// for clock skew between us and Schwab.
+pub const CLOCK_SKEW_SECONDS: i64 = 60;
+
+// An order that never shows up in the orders list is assumed to have not been created after this
+// long. One with an id is given up on as well, e.g. when it was rejected before being listed.
+const UNMATCHED_ORDER_TIMEOUT_MINUTES: i64 = 10;
+
+pub fn parse_entered_time(entered_time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_str(entered_time, "%Y-%m-%dT%H:%M:%S%z")
+        .or_else(|_| DateTime::parse_from_rfc3339(entered_time))
+        .ok()
+        .map(|t| t.to_utc())
+}
+
+// How far along an order is, so a late streamer message can't move it back, e.g. from working to
+// accepted.
+fn stage(status: &OrderStatus) -> u8 {
+    match status {
+        s if s.is_terminal() => 3,
+        OrderStatus::Working | OrderStatus::PendingCancel | OrderStatus::PendingReplace => 2,
+        OrderStatus::New | OrderStatus::Unknown => 0,
+        _ => 1,
+    }
+}
+
+// One leg of an order placed by the trader.
+#[derive(Debug, Clone)]
+pub struct TrackedOrder {
+    pub order_id: Option<i64>,
+    pub symbol: String,
+    pub instruction: OrderInstruction,
+    pub quantity: f64,
+    pub filled_quantity: f64,
+    // The quantity held when the order was submitted, used to tell when a fill is reflected in the
+    // account positions.
+    pub position_before: f64,
+    pub status: OrderStatus,
+    pub submitted_at: DateTime<Utc>,
+    pub terminal_at: Option<DateTime<Utc>>,
+    // Whether the order has been seen in the orders list.
+    pub listed: bool,
+}
+
+impl TrackedOrder {
+    fn matches(&self, order: &Order) -> bool {
+        // If we can't read the time, rely on the legs alone rather than never matching.
+        let entered_in_time = parse_entered_time(&order.entered_time)
+            .map(|t| t >= self.submitted_at - chrono::Duration::seconds(CLOCK_SKEW_SECONDS))
+            .unwrap_or(true);
+
+        entered_in_time
+            && order.order_leg_collection.iter().any(|leg| {
+                leg.instruction == self.instruction
+                    && leg.quantity == self.quantity
+                    && leg.instrument.symbol().as_ref() == Some(&self.symbol)
+            })
+    }
+
+    fn update(&mut self, order: &Order, now: DateTime<Utc>) {
+        if self.status != order.status {
+            log::info!(
+                "Order {} for {} {} moved from {:?} to {:?}",
+                order.order_id,
+                self.quantity,
+                self.symbol,
+                self.status,
+                order.status
+            );
+        }
+        self.order_id = Some(order.order_id);
+        self.listed = true;
+        self.status = order.status.clone();
+        self.filled_quantity = order.filled_quantity;
+        if self.status.is_terminal() && self.terminal_at.is_none() {
+            self.terminal_at = Some(now);
+        }
+    }
+
+    // Follows the order that replaced this one, taking on its quantity for our leg.
+    fn replace(&mut self, order_id: i64, replacement: &Order, now: DateTime<Utc>) {
+        log::info!(
+            "Order {} for {} {} was replaced by order {}",
+            order_id,
+            self.quantity,
+            self.symbol,
+            replacement.order_id
+        );
+        if let Some(leg) = replacement.order_leg_collection.iter().find(|leg| {
+            leg.instruction == self.instruction
+                && leg.instrument.symbol().as_ref() == Some(&self.symbol)
+        }) {
+            self.quantity = leg.quantity;
+        }
+        self.update(replacement, now);
+    }
+
+    fn is_reflected(&self, securities: &HashMap<String, Security>) -> bool {
+        let held = securities
+            .get(&self.symbol)
+            .map(|s| s.amount)
+            .unwrap_or(0.0);
+        match self.instruction {
+            OrderInstruction::Buy => held >= self.position_before + self.filled_quantity,
+            OrderInstruction::Sell => held <= self.position_before - self.filled_quantity,
+            _ => true,
+        }
+    }
+}
+
+// Follows every order the trader submits until it is terminal and the result shows up in the
+// account, so a new trading round never works from stale positions or cash.
+#[derive(Default)]
+pub struct OrderTracker {
+    orders: Vec<TrackedOrder>,
+}
+
+impl OrderTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn track_legs(
+        &mut self,
+        order: &OrderRequest,
+        securities: &HashMap<String, Security>,
+        order_id: Option<i64>,
+        status: OrderStatus,
+    ) {
+        let now = Utc::now();
+        for leg in order.order_leg_collection.iter() {
+            if let Some(symbol) = leg.instrument.symbol() {
+                self.orders.push(TrackedOrder {
+                    order_id,
+                    position_before: securities.get(&symbol).map(|s| s.amount).unwrap_or(0.0),
+                    symbol,
+                    instruction: leg.instruction.clone(),
+                    quantity: leg.quantity,
+                    filled_quantity: match status {
+                        OrderStatus::Filled => leg.quantity,
+                        _ => 0.0,
+                    },
+                    terminal_at: status.is_terminal().then_some(now),
+                    status: status.clone(),
+                    submitted_at: now,
+                    listed: false,
+                });
+            }
+        }
+    }
+
+    // Records a submitted order. Without an order id, e.g. when the placement response was lost, the
+    // order is matched against the orders list by its leg and entered time instead.
+    pub fn track(
+        &mut self,
+        order: &OrderRequest,
+        securities: &HashMap<String, Security>,
+        order_id: Option<i64>,
+    ) {
+        self.track_legs(order, securities, order_id, OrderStatus::New);
+    }
+
+    // Records an order that was filled as soon as it was placed, like the paper broker's.
+    pub fn track_filled(
+        &mut self,
+        order: &OrderRequest,
+        securities: &HashMap<String, Security>,
+        order_id: i64,
+    ) {
+        self.track_legs(order, securities, Some(order_id), OrderStatus::Filled);
+    }
+
+    pub fn orders(&self) -> &[TrackedOrder] {
+        &self.orders
+    }
+
+    // The ids of the tracked orders Schwab has assigned one to.
+    pub fn order_ids(&self) -> Vec<i64> {
+        self.orders.iter().filter_map(|o| o.order_id).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    pub fn has_pending_orders(&self) -> bool {
+        self.orders.iter().any(|o| !o.status.is_terminal())
+    }
+
+    pub fn earliest_submission(&self) -> Option<DateTime<Utc>> {
+        self.orders.iter().map(|o| o.submitted_at).min()
+    }
+
+    // Moves the tracked orders through their states using the orders list returned by Schwab.
+    pub fn update(&mut self, orders: &[Order]) {
+        let now = Utc::now();
+        let mut claimed: HashSet<i64> = self.orders.iter().filter_map(|o| o.order_id).collect();
+
+        for tracked in self.orders.iter_mut().filter(|o| !o.status.is_terminal()) {
+            let order = match tracked.order_id {
+                Some(order_id) => orders.iter().find(|o| o.order_id == order_id),
+                None => orders
+                    .iter()
+                    .find(|o| !claimed.contains(&o.order_id) && tracked.matches(o)),
+            };
+            if let Some(order) = order {
+                claimed.insert(order.order_id);
+                // A replaced order lives on as its replacement, which is followed in its place.
+                // Without one in the response there is nothing left to wait for.
+                match order.replacing_order_collection.first() {
+                    Some(replacement) if order.status == OrderStatus::Replaced => {
+                        let replacement = orders
+                            .iter()
+                            .find(|o| o.order_id == replacement.order_id)
+                            .unwrap_or(replacement);
+                        claimed.insert(replacement.order_id);
+                        tracked.replace(order.order_id, replacement, now);
+                    }
+                    _ => tracked.update(order, now),
+                }
+            }
+        }
+
+        self.orders.retain(|o| {
+            let expired = !o.listed
+                && !o.status.is_terminal()
+                && now - o.submitted_at
+                    > chrono::Duration::minutes(UNMATCHED_ORDER_TIMEOUT_MINUTES);
+            if expired {
+                match o.order_id {
+                    Some(order_id) => log::warn!(
+                        "Order {} for {} {} never showed up in the orders list; giving up on it",
+                        order_id,
+                        o.quantity,
+                        o.symbol
+                    ),
+                    None => log::warn!(
+                        "Order for {} {} never showed up in the orders list; assuming it wasn't \
+                         placed",
+                        o.quantity,
+                        o.symbol
+                    ),
+                }
+            }
+            !expired
+        });
+    }
+
+    // Moves the tracked order through its states as the streamer reports activity on it, without
+    // waiting for the next poll of the orders list. Messages can arrive late, so the state only
+    // ever moves forward. Returns true when a tracked order's state or filled quantity changed.
+    pub fn apply(&mut self, activity: &AccountActivity) -> bool {
+        let Some(order_id) = activity.order_id else {
+            return false;
+        };
+        let now = Utc::now();
+        let mut changed = false;
+        for tracked in self
+            .orders
+            .iter_mut()
+            .filter(|o| o.order_id == Some(order_id) && !o.status.is_terminal())
+        {
+            let filled_before = tracked.filled_quantity;
+            match activity.activity_type {
+                ActivityType::OrderPartialFill => {
+                    tracked.filled_quantity = (tracked.filled_quantity
+                        + activity.quantity.unwrap_or(0.0))
+                    .min(tracked.quantity);
+                }
+                ActivityType::OrderFill => tracked.filled_quantity = tracked.quantity,
+                _ => (),
+            }
+            let status_changed = match activity.order_status() {
+                Some(status) if stage(&status) > stage(&tracked.status) => {
+                    log::info!(
+                        "{} for order {} of {} {}",
+                        activity.activity_type,
+                        order_id,
+                        tracked.quantity,
+                        tracked.symbol
+                    );
+                    tracked.status = status;
+                    if tracked.status.is_terminal() {
+                        tracked.terminal_at = Some(now);
+                    }
+                    true
+                }
+                _ => false,
+            };
+            changed |= status_changed || tracked.filled_quantity != filled_before;
+        }
+        changed
+    }
+
+    // Drops the terminal orders whose results are reflected in an account refresh made after they
+    // finished. Returns true when nothing is left outstanding.
+    pub fn settle(
+        &mut self,
+        securities: &HashMap<String, Security>,
+        account_updated_at: Option<DateTime<Utc>>,
+    ) -> bool {
+        self.orders.retain(|o| {
+            let settled = match (o.terminal_at, account_updated_at) {
+                (Some(terminal_at), Some(updated_at)) => {
+                    updated_at > terminal_at && o.is_reflected(securities)
+                }
+                _ => false,
+            };
+            !settled
+        });
+        self.orders.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrderTracker;
+    use crate::schwab::{
+        account_manager::Security,
+        schemas::{
+            accounts_and_trading::accounts::{
+                AccountsInstrument, AssetType, Order, OrderInstruction, OrderLegCollection,
+                OrderRequest, OrderStatus,
+            },
+            streamer::{AccountActivity, ActivityType},
+        },
+    };
+    use std::collections::HashMap;
+
+    fn order(order_id: i64, symbol: &str, quantity: f64, status: OrderStatus) -> Order {
+        Order {
+            order_id,
+            status,
+            quantity,
+            filled_quantity: quantity,
+            entered_time: chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%z").to_string(),
+            order_leg_collection: vec![OrderLegCollection {
+                instruction: OrderInstruction::Buy,
+                quantity,
+                instrument: AccountsInstrument::Equity {
+                    asset_type: AssetType::Equity,
+                    cusip: "000000000".to_string(),
+                    symbol: symbol.to_string(),
+                    instrument_id: 1,
+                },
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn securities(symbol: &str, amount: f64) -> HashMap<String, Security> {
+        HashMap::from([(
+            symbol.to_string(),
+            Security {
+                amount,
+                total_value: 0.0,
+            },
+        )])
+    }
+
+    #[test]
+    fn test_order_is_matched_and_settled() {
+        let mut tracker = OrderTracker::new();
+        tracker.track(
+            &OrderRequest::new_fill_or_kill_etf(5.0, "VTI".to_string()),
+            &securities("VTI", 10.0),
+            None,
+        );
+        assert!(tracker.has_pending_orders());
+
+        tracker.update(&[
+            order(1, "VXUS", 5.0, OrderStatus::Working),
+            order(2, "VTI", 5.0, OrderStatus::Working),
+        ]);
+        assert_eq!(tracker.orders()[0].order_id, Some(2));
+        assert!(tracker.has_pending_orders());
+
+        tracker.update(&[order(2, "VTI", 5.0, OrderStatus::Filled)]);
+        assert!(!tracker.has_pending_orders());
+
+        // the fill isn't in the positions yet
+        let refreshed_at = tracker.orders()[0].terminal_at.unwrap() + chrono::Duration::seconds(1);
+        assert!(!tracker.settle(&securities("VTI", 10.0), Some(refreshed_at)));
+        assert!(tracker.settle(&securities("VTI", 15.0), Some(refreshed_at)));
+    }
+
+    #[test]
+    fn test_orders_never_listed_are_given_up_on() {
+        let mut tracker = OrderTracker::new();
+        let request = OrderRequest::new_fill_or_kill_etf(5.0, "VTI".to_string());
+        tracker.track(&request, &securities("VTI", 10.0), Some(1));
+        tracker.track(&request, &securities("VTI", 10.0), Some(2));
+
+        tracker.update(&[order(2, "VTI", 5.0, OrderStatus::Working)]);
+        assert_eq!(tracker.orders().len(), 2);
+
+        for tracked in tracker.orders.iter_mut() {
+            tracked.submitted_at -= chrono::Duration::minutes(11);
+        }
+        // Order 1 was never listed, while order 2 is still working and kept even when it's
+        // missing from one response.
+        tracker.update(&[]);
+        assert_eq!(tracker.order_ids(), [2]);
+    }
+
+    #[test]
+    fn test_replaced_orders_are_followed() {
+        let mut tracker = OrderTracker::new();
+        tracker.track(
+            &OrderRequest::new_fill_or_kill_etf(5.0, "VTI".to_string()),
+            &securities("VTI", 10.0),
+            Some(1),
+        );
+
+        let mut replaced = order(1, "VTI", 5.0, OrderStatus::Replaced);
+        replaced.replacing_order_collection = vec![order(2, "VTI", 4.0, OrderStatus::Working)];
+        tracker.update(&[replaced]);
+        assert_eq!(tracker.order_ids(), [2]);
+        assert_eq!(tracker.orders()[0].quantity, 4.0);
+        assert!(tracker.has_pending_orders());
+
+        tracker.update(&[order(2, "VTI", 4.0, OrderStatus::Filled)]);
+        assert!(!tracker.has_pending_orders());
+        let refreshed_at = tracker.orders()[0].terminal_at.unwrap() + chrono::Duration::seconds(1);
+        assert!(!tracker.settle(&securities("VTI", 10.0), Some(refreshed_at)));
+        assert!(tracker.settle(&securities("VTI", 14.0), Some(refreshed_at)));
+    }
+
+    #[test]
+    fn test_account_refresh_must_follow_terminal_state() {
+        let mut tracker = OrderTracker::new();
+        tracker.track_filled(
+            &OrderRequest::new_fill_or_kill_etf(5.0, "VTI".to_string()),
+            &securities("VTI", 0.0),
+            7,
+        );
+        let terminal_at = tracker.orders()[0].terminal_at.unwrap();
+
+        assert!(!tracker.settle(
+            &securities("VTI", 5.0),
+            Some(terminal_at - chrono::Duration::seconds(1))
+        ));
+        assert!(!tracker.settle(&securities("VTI", 5.0), Some(terminal_at)));
+        assert!(tracker.settle(
+            &securities("VTI", 5.0),
+            Some(terminal_at + chrono::Duration::seconds(1))
+        ));
+    }
+
+    fn activity(activity_type: ActivityType, order_id: i64, quantity: f64) -> AccountActivity {
+        AccountActivity {
+            account_number: "12345678".to_string(),
+            activity_type,
+            order_id: Some(order_id),
+            symbol: Some("VTI".to_string()),
+            quantity: Some(quantity),
+            price: Some(250.0),
+            data: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_streamed_fills_finish_the_order() {
+        let mut tracker = OrderTracker::new();
+        tracker.track(
+            &OrderRequest::new_fill_or_kill_etf(5.0, "VTI".to_string()),
+            &securities("VTI", 10.0),
+            Some(3),
+        );
+
+        assert!(!tracker.apply(&activity(ActivityType::OrderFill, 4, 5.0)));
+        assert!(tracker.apply(&activity(ActivityType::OrderPartialFill, 3, 2.0)));
+        assert_eq!(tracker.orders()[0].status, OrderStatus::Working);
+        assert_eq!(tracker.orders()[0].filled_quantity, 2.0);
+
+        assert!(tracker.apply(&activity(ActivityType::OrderFill, 3, 3.0)));
+        assert_eq!(tracker.orders()[0].filled_quantity, 5.0);
+        assert!(!tracker.has_pending_orders());
+        let refreshed_at = tracker.orders()[0].terminal_at.unwrap() + chrono::Duration::seconds(1);
+        assert!(tracker.settle(&securities("VTI", 15.0), Some(refreshed_at)));
+    }
+
+    #[test]
+    fn test_streamed_activity_only_moves_orders_forward() {
+        let mut tracker = OrderTracker::new();
+        tracker.track(
+            &OrderRequest::new_fill_or_kill_etf(5.0, "VTI".to_string()),
+            &securities("VTI", 10.0),
+            Some(3),
+        );
+
+        assert!(tracker.apply(&activity(ActivityType::OrderActivation, 3, 0.0)));
+        assert_eq!(tracker.orders()[0].status, OrderStatus::Working);
+        // A late entry request doesn't move the order back to accepted.
+        assert!(!tracker.apply(&activity(ActivityType::OrderEntryRequest, 3, 0.0)));
+        assert_eq!(tracker.orders()[0].status, OrderStatus::Working);
+        // Activity that changes nothing doesn't count as a change.
+        assert!(!tracker.apply(&activity(ActivityType::OrderActivation, 3, 0.0)));
+        assert!(!tracker.apply(&activity(ActivityType::OrderCancelRequest, 3, 0.0)));
+        assert!(!tracker.apply(&activity(
+            ActivityType::Unknown("OrderAmended".to_string()),
+            3,
+            0.0
+        )));
+        assert_eq!(tracker.orders()[0].filled_quantity, 0.0);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    Error,
+    schwab::{
+        math::two_decimals,
+        schemas::accounts_and_trading::accounts::{OrderInstruction, OrderRequest},
+    },
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Deserializer as jsonDe;
+use std::{collections::HashMap, fs, io::Write};
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct PaperPosition {
+    pub quantity: f64,
+    // Total amount paid for the quantity held, used for the profit/loss shown on the dashboard.
+    pub cost: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct PaperAccount {
+    seeded: bool,
+    cash: f64,
+    positions: HashMap<String, PaperPosition>,
+    next_order_id: i64,
+}
+
+// A simulated broker that fills every order immediately at the latest quote. It keeps its own cash
+// and positions, seeded from the real account the first time it is used, so allocation configs can
+// be exercised without placing real orders.
+#[derive(Default)]
+pub struct PaperBroker {
+    path: Option<String>,
+    account: PaperAccount,
+}
+
+impl PaperBroker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Loads the simulated account from the path if it exists; the state is saved back to the same
+    // path after every change.
+    pub fn load(path: String) -> Result<Self, Error> {
+        let account = match fs::File::open(&path) {
+            Ok(fd) => PaperAccount::deserialize(&mut jsonDe::from_reader(fd))?,
+            Err(e) => match e.kind() {
+                std::io::ErrorKind::NotFound => PaperAccount::default(),
+                _ => return Err(e.into()),
+            },
+        };
+
+        Ok(Self {
+            path: Some(path),
+            account,
+        })
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        if let Some(path) = self.path.as_ref() {
+            fs::File::create(path)?.write_all(&serde_json::to_vec(&self.account)?)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_seeded(&self) -> bool {
+        self.account.seeded
+    }
+
+    pub fn seed(
+        &mut self,
+        cash: f64,
+        positions: HashMap<String, PaperPosition>,
+    ) -> Result<(), Error> {
+        log::info!(
+            "Seeding paper trading account with {} cash and {} positions",
+            cash,
+            positions.len()
+        );
+        self.account.seeded = true;
+        self.account.cash = cash;
+        self.account.positions = positions;
+        self.save()
+    }
+
+    pub fn cash(&self) -> f64 {
+        self.account.cash
+    }
+
+    pub fn positions(&self) -> &HashMap<String, PaperPosition> {
+        &self.account.positions
+    }
+
+    // Fills every leg of the order at the given prices and returns the simulated order id. The
+    // order is rejected as a whole if any leg can't be filled.
+    pub fn place_order(
+        &mut self,
+        order: &OrderRequest,
+        prices: &HashMap<String, f64>,
+    ) -> Result<i64, Error> {
+        let mut cash = self.account.cash;
+        let mut positions = self.account.positions.clone();
+
+        for leg in order.order_leg_collection.iter() {
+            let symbol = leg
+                .instrument
+                .symbol()
+                .ok_or("Paper order leg has no symbol")?;
+            let price = *prices
+                .get(&symbol)
+                .ok_or(format!("No quote to fill paper order for '{}'", symbol))?;
+            let value = two_decimals(leg.quantity * price);
+
+            match leg.instruction {
+                OrderInstruction::Buy => {
+                    if value > cash {
+                        return Err(format!(
+                            "Insufficient paper cash to buy {} of '{}': {} > {}",
+                            leg.quantity, symbol, value, cash
+                        )
+                        .into());
+                    }
+                    cash = two_decimals(cash - value);
+                    let position = positions.entry(symbol).or_default();
+                    position.quantity += leg.quantity;
+                    position.cost = two_decimals(position.cost + value);
+                }
+                OrderInstruction::Sell => {
+                    let position = positions
+                        .get_mut(&symbol)
+                        .filter(|p| p.quantity >= leg.quantity)
+                        .ok_or(format!(
+                            "Insufficient paper position to sell {} of '{}'",
+                            leg.quantity, symbol
+                        ))?;
+                    position.cost =
+                        two_decimals(position.cost * (1.0 - leg.quantity / position.quantity));
+                    position.quantity -= leg.quantity;
+                    if position.quantity == 0.0 {
+                        positions.remove(&symbol);
+                    }
+                    cash = two_decimals(cash + value);
+                }
+                _ => {
+                    return Err(format!(
+                        "Unsupported paper order instruction: {:?}",
+                        leg.instruction
+                    )
+                    .into());
+                }
+            }
+        }
+
+        self.account.cash = cash;
+        self.account.positions = positions;
+        self.account.next_order_id += 1;
+        self.save()?;
+
+        Ok(self.account.next_order_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PaperBroker, PaperPosition};
+    use crate::schwab::schemas::accounts_and_trading::accounts::{OrderInstruction, OrderRequest};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_buy_and_sell_fill_at_quote() {
+        let mut pb = PaperBroker::new();
+        pb.seed(1000.0, HashMap::new()).unwrap();
+        let prices = HashMap::from([("VTI".to_string(), 250.5)]);
+
+        let id = pb
+            .place_order(
+                &OrderRequest::new_fill_or_kill_etf(3.0, "VTI".to_string()),
+                &prices,
+            )
+            .unwrap();
+        assert_eq!(id, 1);
+        assert_eq!(pb.cash(), 248.5);
+        assert_eq!(pb.positions()["VTI"].quantity, 3.0);
+        assert_eq!(pb.positions()["VTI"].cost, 751.5);
+
+        let mut sell = OrderRequest::new_fill_or_kill_etf(3.0, "VTI".to_string());
+        sell.order_leg_collection[0].instruction = OrderInstruction::Sell;
+        pb.place_order(&sell, &prices).unwrap();
+        assert_eq!(pb.cash(), 1000.0);
+        assert!(pb.positions().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_order_without_enough_cash() {
+        let mut pb = PaperBroker::new();
+        pb.seed(
+            100.0,
+            HashMap::from([(
+                "VTI".to_string(),
+                PaperPosition {
+                    quantity: 1.0,
+                    cost: 200.0,
+                },
+            )]),
+        )
+        .unwrap();
+        let prices = HashMap::from([("VTI".to_string(), 250.0)]);
+
+        assert!(
+            pb.place_order(
+                &OrderRequest::new_fill_or_kill_etf(1.0, "VTI".to_string()),
+                &prices
+            )
+            .is_err()
+        );
+        assert_eq!(pb.cash(), 100.0);
+        assert_eq!(pb.positions()["VTI"].quantity, 1.0);
+    }
+}
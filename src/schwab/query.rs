@@ -0,0 +1,170 @@
+use crate::schwab::schemas::accounts_and_trading::accounts::{OrderStatus, TransactionType};
+use chrono::{DateTime, Utc, format::SecondsFormat};
+
+fn encode_time(time: &DateTime<Utc>) -> String {
+    urlencoding::encode(&time.to_rfc3339_opts(SecondsFormat::Secs, true)).into_owned()
+}
+
+// Filters for the orders endpoints, e.g.
+// OrderQuery::new(from, to).status(OrderStatus::Working).max_results(100)
+#[derive(Debug, Clone)]
+pub struct OrderQuery {
+    pub from_entered_time: DateTime<Utc>,
+    pub to_entered_time: DateTime<Utc>,
+    pub status: Option<OrderStatus>,
+    pub max_results: Option<usize>,
+}
+
+impl OrderQuery {
+    pub fn new(from_entered_time: DateTime<Utc>, to_entered_time: DateTime<Utc>) -> Self {
+        Self {
+            from_entered_time,
+            to_entered_time,
+            status: None,
+            max_results: None,
+        }
+    }
+
+    pub fn status(mut self, status: OrderStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    // The same filters over another range.
+    pub fn with_range(
+        &self,
+        from_entered_time: DateTime<Utc>,
+        to_entered_time: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            from_entered_time,
+            to_entered_time,
+            ..self.clone()
+        }
+    }
+
+    pub fn query_string(&self) -> String {
+        let mut query = format!(
+            "fromEnteredTime={}&toEnteredTime={}",
+            encode_time(&self.from_entered_time),
+            encode_time(&self.to_entered_time),
+        );
+        if let Some(status) = self.status.as_ref() {
+            query.push_str(&format!("&status={}", status));
+        }
+        if let Some(max_results) = self.max_results {
+            query.push_str(&format!("&maxResults={}", max_results));
+        }
+        query
+    }
+}
+
+// Filters for the transactions endpoint. Several types can be asked for in one request, e.g.
+// TransactionQuery::new(start, end, &[TransactionType::Trade, TransactionType::DividendOrInterest])
+//     .symbol("VTI")
+#[derive(Debug, Clone)]
+pub struct TransactionQuery {
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    pub types: Vec<TransactionType>,
+    pub symbol: Option<String>,
+}
+
+impl TransactionQuery {
+    pub fn new(
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        types: &[TransactionType],
+    ) -> Self {
+        Self {
+            start_date,
+            end_date,
+            types: Vec::new(),
+            symbol: None,
+        }
+        .types(types)
+    }
+
+    // Adds the types, skipping any already asked for.
+    pub fn types(mut self, types: &[TransactionType]) -> Self {
+        for transaction_type in types.iter() {
+            if !self.types.contains(transaction_type) {
+                self.types.push(transaction_type.clone());
+            }
+        }
+        self
+    }
+
+    pub fn symbol(mut self, symbol: &str) -> Self {
+        self.symbol = Some(symbol.to_string());
+        self
+    }
+
+    // The same filters over another range.
+    pub fn with_range(&self, start_date: DateTime<Utc>, end_date: DateTime<Utc>) -> Self {
+        Self {
+            start_date,
+            end_date,
+            ..self.clone()
+        }
+    }
+
+    pub fn query_string(&self) -> String {
+        let mut query = format!(
+            "startDate={}&endDate={}&types={}",
+            encode_time(&self.start_date),
+            encode_time(&self.end_date),
+            urlencoding::encode(
+                &self
+                    .types
+                    .iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+        );
+        if let Some(symbol) = self.symbol.as_ref() {
+            query.push_str(&format!("&symbol={}", urlencoding::encode(symbol)));
+        }
+        query
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OrderQuery, TransactionQuery};
+    use crate::schwab::schemas::accounts_and_trading::accounts::{OrderStatus, TransactionType};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_query_strings() {
+        let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(
+            TransactionQuery::new(
+                start,
+                end,
+                &[TransactionType::Trade, TransactionType::DividendOrInterest]
+            )
+            .types(&[TransactionType::Trade, TransactionType::ReceiveAndDeliver])
+            .symbol("BRK/B")
+            .query_string(),
+            "startDate=2025-01-01T00%3A00%3A00Z&endDate=2025-02-01T00%3A00%3A00Z\
+             &types=TRADE%2CDIVIDEND_OR_INTEREST%2CRECEIVE_AND_DELIVER&symbol=BRK%2FB"
+        );
+        assert_eq!(
+            OrderQuery::new(start, end)
+                .status(OrderStatus::Working)
+                .max_results(50)
+                .query_string(),
+            "fromEnteredTime=2025-01-01T00%3A00%3A00Z&toEnteredTime=2025-02-01T00%3A00%3A00Z\
+             &status=WORKING&maxResults=50"
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::Instant;
+
+// Schwab allows about 120 requests a minute per app on the trader API.
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 120;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub trader_requests_per_minute: u32,
+    pub market_data_requests_per_minute: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            trader_requests_per_minute: DEFAULT_REQUESTS_PER_MINUTE,
+            market_data_requests_per_minute: DEFAULT_REQUESTS_PER_MINUTE,
+        }
+    }
+}
+
+// The Schwab APIs with their own request limits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Api {
+    Trader,
+    MarketData,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketUsage {
+    // Requests made in the current window, i.e. tokens taken that haven't been refilled yet.
+    pub used: u32,
+    pub limit: u32,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+// Holds up to `capacity` tokens and refills them evenly over `period`. Callers wait for a token
+// in the order they arrived instead of failing.
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: u32,
+    period: Duration,
+    state: std::sync::Mutex<BucketState>,
+    queue: tokio::sync::Mutex<()>,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u32, period: Duration) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            period,
+            state: std::sync::Mutex::new(BucketState {
+                tokens: capacity as f64,
+                refilled_at: Instant::now(),
+            }),
+            queue: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn refill(&self, state: &mut BucketState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.capacity as f64 / self.period.as_secs_f64())
+            .min(self.capacity as f64);
+        state.refilled_at = now;
+    }
+
+    // Takes a token, or returns how long until the next one is available.
+    fn try_take(&self) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state);
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(self
+                .period
+                .mul_f64((1.0 - state.tokens) / self.capacity as f64))
+        }
+    }
+
+    pub async fn acquire(&self) {
+        // tokio's mutex is fair, so holding it while waiting keeps callers queued in order.
+        let _turn = self.queue.lock().await;
+        while let Err(wait) = self.try_take() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    pub fn usage(&self) -> BucketUsage {
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state);
+        BucketUsage {
+            used: (self.capacity as f64 - state.tokens).ceil() as u32,
+            limit: self.capacity,
+        }
+    }
+}
+
+// The buckets shared by every client talking to the same Schwab API.
+#[derive(Debug)]
+pub struct RateLimiter {
+    trader: TokenBucket,
+    market_data: TokenBucket,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(&RateLimitConfig::default())
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let minute = Duration::from_secs(60);
+        Self {
+            trader: TokenBucket::new(config.trader_requests_per_minute, minute),
+            market_data: TokenBucket::new(config.market_data_requests_per_minute, minute),
+        }
+    }
+
+    fn bucket(&self, api: Api) -> &TokenBucket {
+        match api {
+            Api::Trader => &self.trader,
+            Api::MarketData => &self.market_data,
+        }
+    }
+
+    pub async fn acquire(&self, api: Api) {
+        self.bucket(api).acquire().await
+    }
+
+    pub fn usage(&self, api: Api) -> BucketUsage {
+        self.bucket(api).usage()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BucketUsage, TokenBucket};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[tokio::test]
+    async fn test_callers_wait_for_tokens() {
+        let bucket = TokenBucket::new(2, Duration::from_millis(400));
+        let start = Instant::now();
+        bucket.acquire().await;
+        bucket.acquire().await;
+        assert!(start.elapsed() < Duration::from_millis(100));
+        assert_eq!(bucket.usage(), BucketUsage { used: 2, limit: 2 });
+
+        // The third token is refilled 200ms after the first two were taken.
+        bucket.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(190));
+    }
+}
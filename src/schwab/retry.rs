@@ -0,0 +1,154 @@
+use crate::{Error, schwab::error::SchwabApiError};
+use oauth2::reqwest;
+use serde::Deserialize;
+use std::{
+    hash::{BuildHasher, RandomState},
+    time::Duration,
+};
+
+// How idempotent requests are retried after transient failures, e.g. a 502 from Schwab or a
+// connection reset. The backoff doubles on every attempt up to the maximum, and is jittered so
+// several callers don't retry in lockstep.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    // How long the orders list is polled for an order whose placement may or may not have gone
+    // through, and how often.
+    pub order_confirmation_ms: u64,
+    pub order_poll_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 10_000,
+            order_confirmation_ms: 10_000,
+            order_poll_ms: 1_000,
+        }
+    }
+}
+
+// A random fraction in [0, 1), which is all the randomness the jitter needs.
+fn random_fraction() -> f64 {
+    (RandomState::new().hash_one(std::time::SystemTime::now()) % 1_000_000) as f64 / 1_000_000.0
+}
+
+impl RetryPolicy {
+    // Whether the error could go away by sending the same request again.
+    pub fn is_transient(e: &Error) -> bool {
+        if let Some(e) = e.downcast_ref::<SchwabApiError>() {
+            return matches!(
+                e,
+                SchwabApiError::RateLimited { .. } | SchwabApiError::ServerError { .. }
+            );
+        }
+        e.downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| e.is_connect() || e.is_timeout() || e.is_request() || e.is_body())
+    }
+
+    // Whether the error proves Schwab never handled the request, so even a request that isn't
+    // idempotent can be sent again: the connection couldn't be made, or Schwab rate limited the
+    // request. A 503 isn't proof, a gateway may return it after passing the request on.
+    pub fn is_unhandled(e: &Error) -> bool {
+        if let Some(e) = e.downcast_ref::<SchwabApiError>() {
+            return matches!(e, SchwabApiError::RateLimited { .. });
+        }
+        e.downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| e.is_connect())
+    }
+
+    // The exponential backoff before the given retry (0 based), with equal jitter: half the
+    // backoff is fixed and the other half random.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff_ms
+            .saturating_mul(2u64.saturating_pow(retry))
+            .min(self.max_backoff_ms);
+        Duration::from_millis(backoff / 2)
+            + Duration::from_millis(backoff / 2).mul_f64(random_fraction())
+    }
+
+    // How long to wait before retrying after the error, or None if it shouldn't be retried. A
+    // rate limited response is retried once Schwab says it may be.
+    pub fn delay(&self, retry: u32, e: &Error) -> Option<Duration> {
+        if retry >= self.max_retries || !Self::is_transient(e) {
+            return None;
+        }
+        match e.downcast_ref::<SchwabApiError>() {
+            Some(SchwabApiError::RateLimited {
+                retry_after: Some(retry_after),
+            }) => Some(*retry_after),
+            _ => Some(self.backoff(retry)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+    use crate::{
+        Error,
+        schwab::{error::SchwabApiError, schemas::accounts_and_trading::accounts::ApiError},
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1000,
+            ..Default::default()
+        };
+        for (retry, backoff) in [(0, 100), (1, 200), (2, 400), (3, 800), (4, 1000), (9, 1000)] {
+            let delay = policy.backoff(retry);
+            assert!(delay >= Duration::from_millis(backoff / 2), "{:?}", delay);
+            assert!(delay <= Duration::from_millis(backoff), "{:?}", delay);
+        }
+    }
+
+    #[test]
+    fn test_only_transient_errors_are_retried() {
+        let policy = RetryPolicy::default();
+        let server_error: Error = SchwabApiError::ServerError {
+            status: 502,
+            error: ApiError::default(),
+        }
+        .into();
+        let rate_limited: Error = SchwabApiError::RateLimited {
+            retry_after: Some(Duration::from_secs(7)),
+        }
+        .into();
+        let invalid: Error = SchwabApiError::Validation(ApiError::default()).into();
+
+        assert!(policy.delay(0, &server_error).is_some());
+        assert!(policy.delay(3, &server_error).is_none());
+        assert_eq!(policy.delay(1, &rate_limited), Some(Duration::from_secs(7)));
+        assert!(policy.delay(0, &invalid).is_none());
+        assert!(policy.delay(0, &"parse error".into()).is_none());
+    }
+
+    #[test]
+    fn test_only_unhandled_errors_can_be_sent_again() {
+        let error = |status| -> Error {
+            SchwabApiError::ServerError {
+                status,
+                error: ApiError::default(),
+            }
+            .into()
+        };
+        let rate_limited: Error = SchwabApiError::RateLimited { retry_after: None }.into();
+
+        assert!(RetryPolicy::is_unhandled(&rate_limited));
+        assert!(!RetryPolicy::is_unhandled(&error(500)));
+        assert!(!RetryPolicy::is_unhandled(&error(502)));
+        assert!(!RetryPolicy::is_unhandled(&error(503)));
+        assert!(!RetryPolicy::is_unhandled(&error(504)));
+        assert!(!RetryPolicy::is_unhandled(&"timed out".into()));
+    }
+}
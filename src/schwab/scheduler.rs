@@ -0,0 +1,218 @@
+use crate::{
+    config::ScheduleConfig,
+    schwab::{
+        calendar::{self, Sessions},
+        client::SchwabClient,
+        schemas::market_data::market_hours::MarketType,
+    },
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::America::New_York;
+use tokio::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarketPhase {
+    PreMarket,
+    Regular,
+    AfterHours,
+    Closed,
+}
+
+// Decides how long the update/trade loop sleeps and whether it may trade, from the exchange hours
+// of the day.
+pub struct Scheduler {
+    config: ScheduleConfig,
+    regular_poll: Duration,
+    sessions: Option<(NaiveDate, Sessions)>,
+}
+
+impl Scheduler {
+    pub fn new(config: ScheduleConfig, regular_poll: Duration) -> Self {
+        Self {
+            config,
+            regular_poll,
+            sessions: None,
+        }
+    }
+
+    // The sessions of the exchange day at the given time. Schwab is asked once a day; the built in
+    // calendar is used until it answers.
+    pub async fn sessions(
+        &mut self,
+        client: Option<&SchwabClient>,
+        now: DateTime<Utc>,
+    ) -> Sessions {
+        let date = calendar::exchange_date(now);
+        if let Some((cached_date, sessions)) = self.sessions.as_ref()
+            && *cached_date == date
+        {
+            return sessions.clone();
+        }
+
+        let hours = match client {
+            Some(client) => match client.get_market_hours(&[MarketType::Equity], date).await {
+                Ok(response) => response
+                    .get("equity")
+                    .and_then(|products| products.values().next())
+                    .map(|hours| hours.sessions()),
+                Err(e) => {
+                    log::warn!(
+                        "Failed to get market hours, using the built in calendar: '{}'",
+                        e
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+
+        match hours {
+            Some(sessions) => {
+                self.sessions = Some((date, sessions.clone()));
+                sessions
+            }
+            None => calendar::sessions(date),
+        }
+    }
+
+    pub fn phase(sessions: &Sessions, now: DateTime<Utc>) -> MarketPhase {
+        let within = |session: &Option<calendar::Session>| {
+            session.is_some_and(|(start, end)| start <= now && now < end)
+        };
+        if within(&sessions.regular) {
+            MarketPhase::Regular
+        } else if within(&sessions.pre_market) {
+            MarketPhase::PreMarket
+        } else if within(&sessions.post_market) {
+            MarketPhase::AfterHours
+        } else {
+            MarketPhase::Closed
+        }
+    }
+
+    pub fn poll_interval(&self, phase: MarketPhase) -> Duration {
+        match phase {
+            MarketPhase::Regular => self.regular_poll,
+            MarketPhase::PreMarket => Duration::from_secs(self.config.pre_market_poll_seconds),
+            MarketPhase::AfterHours => Duration::from_secs(self.config.after_hours_poll_seconds),
+            MarketPhase::Closed => Duration::from_secs(self.config.closed_poll_seconds),
+        }
+    }
+
+    // How long to sleep: the poll interval of the phase, cut short so the loop wakes up when the
+    // next session starts or ends.
+    pub fn sleep_duration(&self, sessions: &Sessions, now: DateTime<Utc>) -> Duration {
+        let interval = self.poll_interval(Self::phase(sessions, now));
+        [sessions.pre_market, sessions.regular, sessions.post_market]
+            .iter()
+            .flatten()
+            .flat_map(|(start, end)| [*start, *end])
+            .filter(|boundary| *boundary > now)
+            .filter_map(|boundary| (boundary - now).to_std().ok())
+            .fold(interval, |sleep, until_boundary| sleep.min(until_boundary))
+    }
+
+    pub fn can_trade(&self, phase: MarketPhase, now: DateTime<Utc>) -> bool {
+        if phase != MarketPhase::Regular {
+            return false;
+        }
+        if self.config.trading_windows.is_empty() {
+            return true;
+        }
+
+        let time = now.with_timezone(&New_York).time();
+        self.config.trading_windows.iter().any(|window| {
+            window
+                .times()
+                .is_ok_and(|(start, end)| start <= time && time < end)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MarketPhase, Scheduler};
+    use crate::{
+        config::{ScheduleConfig, TradingWindow},
+        schwab::calendar,
+    };
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use tokio::time::Duration;
+
+    fn scheduler(trading_windows: Vec<TradingWindow>) -> Scheduler {
+        Scheduler::new(
+            ScheduleConfig {
+                trading_windows,
+                ..Default::default()
+            },
+            Duration::from_secs(5),
+        )
+    }
+
+    #[test]
+    fn test_phases_and_poll_rates() {
+        let s = scheduler(Vec::new());
+        // New York is UTC-5 on this day, so the regular session is 14:30 to 21:00 UTC.
+        let sessions = calendar::sessions(NaiveDate::from_ymd_opt(2025, 3, 4).unwrap());
+        let at = |hour, minute| Utc.with_ymd_and_hms(2025, 3, 4, hour, minute, 0).unwrap();
+
+        assert_eq!(Scheduler::phase(&sessions, at(11, 0)), MarketPhase::Closed);
+        assert_eq!(
+            s.sleep_duration(&sessions, at(11, 0)),
+            Duration::from_secs(1800)
+        );
+        // wakes up for the start of the pre-market session
+        assert_eq!(
+            s.sleep_duration(&sessions, at(11, 50)),
+            Duration::from_secs(600)
+        );
+
+        assert_eq!(
+            Scheduler::phase(&sessions, at(13, 0)),
+            MarketPhase::PreMarket
+        );
+        assert_eq!(
+            s.sleep_duration(&sessions, at(13, 0)),
+            Duration::from_secs(60)
+        );
+        assert!(!s.can_trade(MarketPhase::PreMarket, at(13, 0)));
+
+        assert_eq!(Scheduler::phase(&sessions, at(15, 0)), MarketPhase::Regular);
+        assert_eq!(
+            s.sleep_duration(&sessions, at(15, 0)),
+            Duration::from_secs(5)
+        );
+        assert!(s.can_trade(MarketPhase::Regular, at(15, 0)));
+
+        assert_eq!(
+            Scheduler::phase(&sessions, at(22, 0)),
+            MarketPhase::AfterHours
+        );
+        assert_eq!(
+            s.sleep_duration(&sessions, at(22, 0)),
+            Duration::from_secs(300)
+        );
+
+        let weekend = calendar::sessions(NaiveDate::from_ymd_opt(2025, 3, 8).unwrap());
+        assert_eq!(
+            Scheduler::phase(
+                &weekend,
+                Utc.with_ymd_and_hms(2025, 3, 8, 15, 0, 0).unwrap()
+            ),
+            MarketPhase::Closed
+        );
+    }
+
+    #[test]
+    fn test_trades_only_inside_windows() {
+        let s = scheduler(vec![TradingWindow {
+            start: "10:00".to_string(),
+            end: "15:30".to_string(),
+        }]);
+        let at = |hour, minute| Utc.with_ymd_and_hms(2025, 3, 4, hour, minute, 0).unwrap();
+
+        assert!(!s.can_trade(MarketPhase::Regular, at(14, 45)));
+        assert!(s.can_trade(MarketPhase::Regular, at(15, 0)));
+        assert!(!s.can_trade(MarketPhase::Regular, at(20, 45)));
+    }
+}
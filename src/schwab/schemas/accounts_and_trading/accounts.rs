@@ -22,9 +22,6 @@ pub struct AccountTypes {
 #[derive(Deserialize, Serialize, Debug, Default)]
 #[serde(tag = "assetType")] // why is this here? 
 pub enum AccountInstrument {
-    #[default]
-    Unknown,
-
     #[serde(rename(serialize = "COLLECTIVE_INVESTMENT"))]
     #[serde(rename(deserialize = "COLLECTIVE_INVESTMENT"))]
     CollectiveInvestment {
@@ -64,6 +61,91 @@ pub enum AccountInstrument {
         #[serde(rename(deserialize = "netChange"))]
         net_change: f64,
     },
+
+    #[serde(rename(serialize = "MUTUAL_FUND"))]
+    #[serde(rename(deserialize = "MUTUAL_FUND"))]
+    MutualFund {
+        cusip: Option<String>,
+        symbol: String,
+        description: Option<String>,
+        #[serde(rename(serialize = "instrumentId"))]
+        #[serde(rename(deserialize = "instrumentId"))]
+        instrument_id: Option<i64>,
+        #[serde(rename(serialize = "netChange"))]
+        #[serde(rename(deserialize = "netChange"))]
+        net_change: Option<f64>,
+    },
+
+    #[serde(rename(serialize = "FIXED_INCOME"))]
+    #[serde(rename(deserialize = "FIXED_INCOME"))]
+    FixedIncome {
+        cusip: Option<String>,
+        symbol: String,
+        description: Option<String>,
+        #[serde(rename(serialize = "instrumentId"))]
+        #[serde(rename(deserialize = "instrumentId"))]
+        instrument_id: Option<i64>,
+        #[serde(rename(serialize = "netChange"))]
+        #[serde(rename(deserialize = "netChange"))]
+        net_change: Option<f64>,
+        #[serde(rename(serialize = "maturityDate"))]
+        #[serde(rename(deserialize = "maturityDate"))]
+        maturity_date: Option<String>,
+        factor: Option<f64>,
+        #[serde(rename(serialize = "variableRate"))]
+        #[serde(rename(deserialize = "variableRate"))]
+        variable_rate: Option<f64>,
+        r#type: Option<String>,
+    },
+
+    #[serde(rename(serialize = "CASH_EQUIVALENT"))]
+    #[serde(rename(deserialize = "CASH_EQUIVALENT"))]
+    CashEquivalent {
+        cusip: Option<String>,
+        symbol: String,
+        description: Option<String>,
+        #[serde(rename(serialize = "instrumentId"))]
+        #[serde(rename(deserialize = "instrumentId"))]
+        instrument_id: Option<i64>,
+        #[serde(rename(serialize = "netChange"))]
+        #[serde(rename(deserialize = "netChange"))]
+        net_change: Option<f64>,
+        r#type: Option<String>,
+    },
+
+    #[serde(rename(serialize = "INDEX"))]
+    #[serde(rename(deserialize = "INDEX"))]
+    Index {
+        cusip: Option<String>,
+        symbol: String,
+        description: Option<String>,
+        #[serde(rename(serialize = "instrumentId"))]
+        #[serde(rename(deserialize = "instrumentId"))]
+        instrument_id: Option<i64>,
+        #[serde(rename(serialize = "netChange"))]
+        #[serde(rename(deserialize = "netChange"))]
+        net_change: Option<f64>,
+        r#type: Option<String>,
+    },
+
+    #[serde(rename(serialize = "CURRENCY"))]
+    #[serde(rename(deserialize = "CURRENCY"))]
+    Currency {
+        cusip: Option<String>,
+        symbol: String,
+        description: Option<String>,
+        #[serde(rename(serialize = "instrumentId"))]
+        #[serde(rename(deserialize = "instrumentId"))]
+        instrument_id: Option<i64>,
+        #[serde(rename(serialize = "netChange"))]
+        #[serde(rename(deserialize = "netChange"))]
+        net_change: Option<f64>,
+    },
+
+    // Any other asset type. Its position still counts towards the account's value.
+    #[default]
+    #[serde(other)]
+    Unknown,
 }
 
 impl AccountInstrument {
@@ -73,6 +155,11 @@ impl AccountInstrument {
             Self::CollectiveInvestment { symbol, .. } => Some(symbol.clone()),
             Self::Option { symbol, .. } => Some(symbol.clone()),
             Self::Equity { symbol, .. } => Some(symbol.clone()),
+            Self::MutualFund { symbol, .. } => Some(symbol.clone()),
+            Self::FixedIncome { symbol, .. } => Some(symbol.clone()),
+            Self::CashEquivalent { symbol, .. } => Some(symbol.clone()),
+            Self::Index { symbol, .. } => Some(symbol.clone()),
+            Self::Currency { symbol, .. } => Some(symbol.clone()),
         }
     }
 }
@@ -140,6 +227,16 @@ pub struct MarginInitialBalance {
     pub account_value: f64,
 }
 
+impl MarginInitialBalance {
+    // The cash in the account. Margin accounts report it as the margin balance with no total cash.
+    pub fn cash(&self) -> f64 {
+        match self.total_cash > 0.0 {
+            true => self.total_cash,
+            false => self.margin_balance,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct MarginBalance {
@@ -237,7 +334,7 @@ pub enum UserDetailsType {
     SystemUser,
 }
 
-#[derive(Deserialize, Serialize, Debug, Default)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 pub enum TransactionType {
     #[default]
     Unknown,
@@ -519,6 +616,18 @@ pub enum AssetType {
     #[serde(rename(serialize = "COLLECTIVE_INVESTMENT"))]
     #[serde(rename(deserialize = "COLLECTIVE_INVESTMENT"))]
     CollectiveInvestment,
+
+    #[serde(rename(serialize = "FOREX"))]
+    #[serde(rename(deserialize = "FOREX"))]
+    Forex,
+
+    #[serde(rename(serialize = "FUTURE"))]
+    #[serde(rename(deserialize = "FUTURE"))]
+    Future,
+
+    #[serde(rename(serialize = "PRODUCT"))]
+    #[serde(rename(deserialize = "PRODUCT"))]
+    Product,
 }
 
 #[derive(Deserialize, Serialize, Debug, Default)]
@@ -825,15 +934,11 @@ pub struct CollectiveInvestment {
 }
 
 #[derive(Deserialize, Serialize, Debug, Default)]
-#[serde(untagged)]
+#[serde(tag = "assetType")]
 pub enum TransactionInstrument {
-    #[default]
-    Unknown,
-
+    #[serde(rename(serialize = "EQUITY"))]
+    #[serde(rename(deserialize = "EQUITY"))]
     TransactionEquity {
-        #[serde(rename(serialize = "assetType"))]
-        #[serde(rename(deserialize = "assetType"))]
-        asset_type: AssetType,
         symbol: String,
         status: String,
         #[serde(rename(serialize = "instrumentId"))]
@@ -847,10 +952,9 @@ pub enum TransactionInstrument {
         te_type: TransactionEquityType,
     },
 
+    #[serde(rename(serialize = "COLLECTIVE_INVESTMENT"))]
+    #[serde(rename(deserialize = "COLLECTIVE_INVESTMENT"))]
     CollectiveInvestment {
-        #[serde(rename(serialize = "assetType"))]
-        #[serde(rename(deserialize = "assetType"))]
-        asset_type: AssetType,
         status: String,
         symbol: String,
         description: String,
@@ -865,10 +969,9 @@ pub enum TransactionInstrument {
         ci_type: CollectiveInvestmentType,
     },
 
+    #[serde(rename(serialize = "OPTION"))]
+    #[serde(rename(deserialize = "OPTION"))]
     TransactionOption {
-        #[serde(rename(serialize = "assetType"))]
-        #[serde(rename(deserialize = "assetType"))]
-        asset_type: AssetType,
         status: String,
         symbol: String,
         #[serde(rename(serialize = "closingPrice"))]
@@ -904,40 +1007,259 @@ pub enum TransactionInstrument {
         underlying_cusip: String,
     },
 
-    Currency {
-        #[serde(rename(serialize = "assetType"))]
-        #[serde(rename(deserialize = "assetType"))]
-        asset_type: AssetType,
+    #[serde(rename(serialize = "CASH_EQUIVALENT"))]
+    #[serde(rename(deserialize = "CASH_EQUIVALENT"))]
+    TransactionCashEquivalent {
+        cusip: Option<String>,
         symbol: String,
-        description: String,
+        description: Option<String>,
         #[serde(rename(serialize = "instrumentId"))]
         #[serde(rename(deserialize = "instrumentId"))]
         instrument_id: i64,
         #[serde(rename(serialize = "netChange"))]
         #[serde(rename(deserialize = "netChange"))]
         net_change: Option<f64>,
-        status: String,
+        status: Option<String>,
         #[serde(rename(serialize = "closingPrice"))]
         #[serde(rename(deserialize = "closingPrice"))]
-        closing_price: f64,
+        closing_price: Option<f64>,
+        #[serde(rename(serialize = "type"))]
+        #[serde(rename(deserialize = "type"))]
+        tce_type: TransactionCashEquivalentType,
     },
-    /*
-    Other types I may implement in the future
 
-    TransactionCashEquivalent { },
+    #[serde(rename(serialize = "FIXED_INCOME"))]
+    #[serde(rename(deserialize = "FIXED_INCOME"))]
+    TransactionFixedIncome {
+        cusip: Option<String>,
+        symbol: String,
+        description: Option<String>,
+        #[serde(rename(serialize = "instrumentId"))]
+        #[serde(rename(deserialize = "instrumentId"))]
+        instrument_id: i64,
+        #[serde(rename(serialize = "netChange"))]
+        #[serde(rename(deserialize = "netChange"))]
+        net_change: Option<f64>,
+        status: Option<String>,
+        #[serde(rename(serialize = "closingPrice"))]
+        #[serde(rename(deserialize = "closingPrice"))]
+        closing_price: Option<f64>,
+        #[serde(rename(serialize = "type"))]
+        #[serde(rename(deserialize = "type"))]
+        tfi_type: TransactionFixedIncomeType,
+        #[serde(rename(serialize = "maturityDate"))]
+        #[serde(rename(deserialize = "maturityDate"))]
+        maturity_date: Option<String>,
+        factor: Option<f64>,
+        multiplier: Option<f64>,
+        #[serde(rename(serialize = "variableRate"))]
+        #[serde(rename(deserialize = "variableRate"))]
+        variable_rate: Option<f64>,
+    },
 
-    TransactionFixedIncome { },
+    #[serde(rename(serialize = "FOREX"))]
+    #[serde(rename(deserialize = "FOREX"))]
+    Forex {
+        cusip: Option<String>,
+        symbol: String,
+        description: Option<String>,
+        #[serde(rename(serialize = "instrumentId"))]
+        #[serde(rename(deserialize = "instrumentId"))]
+        instrument_id: i64,
+        #[serde(rename(serialize = "netChange"))]
+        #[serde(rename(deserialize = "netChange"))]
+        net_change: Option<f64>,
+        status: Option<String>,
+        #[serde(rename(serialize = "closingPrice"))]
+        #[serde(rename(deserialize = "closingPrice"))]
+        closing_price: Option<f64>,
+        #[serde(rename(serialize = "type"))]
+        #[serde(rename(deserialize = "type"))]
+        forex_type: ForexType,
+        #[serde(rename(serialize = "baseCurrency"))]
+        #[serde(rename(deserialize = "baseCurrency"))]
+        base_currency: Currency,
+        #[serde(rename(serialize = "counterCurrency"))]
+        #[serde(rename(deserialize = "counterCurrency"))]
+        counter_currency: Currency,
+    },
 
-    Forex { },
+    #[serde(rename(serialize = "FUTURE"))]
+    #[serde(rename(deserialize = "FUTURE"))]
+    Future {
+        cusip: Option<String>,
+        symbol: String,
+        description: Option<String>,
+        #[serde(rename(serialize = "instrumentId"))]
+        #[serde(rename(deserialize = "instrumentId"))]
+        instrument_id: i64,
+        #[serde(rename(serialize = "netChange"))]
+        #[serde(rename(deserialize = "netChange"))]
+        net_change: Option<f64>,
+        status: Option<String>,
+        #[serde(rename(serialize = "closingPrice"))]
+        #[serde(rename(deserialize = "closingPrice"))]
+        closing_price: Option<f64>,
+        #[serde(rename(serialize = "type"))]
+        #[serde(rename(deserialize = "type"))]
+        future_type: FutureType,
+        #[serde(rename(serialize = "activeContract"))]
+        #[serde(rename(deserialize = "activeContract"))]
+        active_contract: Option<bool>,
+        #[serde(rename(serialize = "expirationDate"))]
+        #[serde(rename(deserialize = "expirationDate"))]
+        expiration_date: Option<String>,
+        #[serde(rename(serialize = "lastTradingDate"))]
+        #[serde(rename(deserialize = "lastTradingDate"))]
+        last_trading_date: Option<String>,
+        #[serde(rename(serialize = "firstNoticeDate"))]
+        #[serde(rename(deserialize = "firstNoticeDate"))]
+        first_notice_date: Option<String>,
+        multiplier: Option<f64>,
+    },
 
-    Future { },
+    #[serde(rename(serialize = "INDEX"))]
+    #[serde(rename(deserialize = "INDEX"))]
+    Index {
+        cusip: Option<String>,
+        symbol: String,
+        description: Option<String>,
+        #[serde(rename(serialize = "instrumentId"))]
+        #[serde(rename(deserialize = "instrumentId"))]
+        instrument_id: i64,
+        #[serde(rename(serialize = "netChange"))]
+        #[serde(rename(deserialize = "netChange"))]
+        net_change: Option<f64>,
+        status: Option<String>,
+        #[serde(rename(serialize = "closingPrice"))]
+        #[serde(rename(deserialize = "closingPrice"))]
+        closing_price: Option<f64>,
+        #[serde(rename(serialize = "type"))]
+        #[serde(rename(deserialize = "type"))]
+        index_type: IndexType,
+        #[serde(rename(serialize = "activeContract"))]
+        #[serde(rename(deserialize = "activeContract"))]
+        active_contract: Option<bool>,
+    },
+
+    #[serde(rename(serialize = "MUTUAL_FUND"))]
+    #[serde(rename(deserialize = "MUTUAL_FUND"))]
+    TransactionMutualFund {
+        cusip: Option<String>,
+        symbol: String,
+        description: Option<String>,
+        #[serde(rename(serialize = "instrumentId"))]
+        #[serde(rename(deserialize = "instrumentId"))]
+        instrument_id: i64,
+        #[serde(rename(serialize = "netChange"))]
+        #[serde(rename(deserialize = "netChange"))]
+        net_change: Option<f64>,
+        status: Option<String>,
+        #[serde(rename(serialize = "closingPrice"))]
+        #[serde(rename(deserialize = "closingPrice"))]
+        closing_price: Option<f64>,
+        #[serde(rename(serialize = "type"))]
+        #[serde(rename(deserialize = "type"))]
+        tmf_type: TransactionMutualFundType,
+        #[serde(rename(serialize = "fundFamilyName"))]
+        #[serde(rename(deserialize = "fundFamilyName"))]
+        fund_family_name: Option<String>,
+        #[serde(rename(serialize = "fundFamilySymbol"))]
+        #[serde(rename(deserialize = "fundFamilySymbol"))]
+        fund_family_symbol: Option<String>,
+        #[serde(rename(serialize = "fundGroup"))]
+        #[serde(rename(deserialize = "fundGroup"))]
+        fund_group: Option<String>,
+        #[serde(rename(serialize = "exchangeCutoffTime"))]
+        #[serde(rename(deserialize = "exchangeCutoffTime"))]
+        exchange_cutoff_time: Option<String>,
+        #[serde(rename(serialize = "purchaseCutoffTime"))]
+        #[serde(rename(deserialize = "purchaseCutoffTime"))]
+        purchase_cutoff_time: Option<String>,
+        #[serde(rename(serialize = "redemptionCutoffTime"))]
+        #[serde(rename(deserialize = "redemptionCutoffTime"))]
+        redemption_cutoff_time: Option<String>,
+    },
+
+    #[serde(rename(serialize = "PRODUCT"))]
+    #[serde(rename(deserialize = "PRODUCT"))]
+    Product {
+        cusip: Option<String>,
+        symbol: String,
+        description: Option<String>,
+        #[serde(rename(serialize = "instrumentId"))]
+        #[serde(rename(deserialize = "instrumentId"))]
+        instrument_id: i64,
+        #[serde(rename(serialize = "netChange"))]
+        #[serde(rename(deserialize = "netChange"))]
+        net_change: Option<f64>,
+        status: Option<String>,
+        #[serde(rename(serialize = "closingPrice"))]
+        #[serde(rename(deserialize = "closingPrice"))]
+        closing_price: Option<f64>,
+        #[serde(rename(serialize = "type"))]
+        #[serde(rename(deserialize = "type"))]
+        product_type: ProductType,
+    },
+
+    #[serde(rename(serialize = "CURRENCY"))]
+    #[serde(rename(deserialize = "CURRENCY"))]
+    Currency {
+        symbol: String,
+        description: String,
+        #[serde(rename(serialize = "instrumentId"))]
+        #[serde(rename(deserialize = "instrumentId"))]
+        instrument_id: i64,
+        #[serde(rename(serialize = "netChange"))]
+        #[serde(rename(deserialize = "netChange"))]
+        net_change: Option<f64>,
+        status: String,
+        #[serde(rename(serialize = "closingPrice"))]
+        #[serde(rename(deserialize = "closingPrice"))]
+        closing_price: f64,
+    },
 
-    Index { },
+    // Any other asset type.
+    #[default]
+    #[serde(rename(serialize = "UNKNOWN"))]
+    #[serde(other)]
+    Unknown,
+}
 
-    TransactionMutualFund { },
+impl TransactionInstrument {
+    pub fn asset_type(&self) -> AssetType {
+        match self {
+            Self::Unknown => AssetType::Unknown,
+            Self::TransactionEquity { .. } => AssetType::Equity,
+            Self::CollectiveInvestment { .. } => AssetType::CollectiveInvestment,
+            Self::TransactionOption { .. } => AssetType::Option,
+            Self::TransactionCashEquivalent { .. } => AssetType::CashEquivalent,
+            Self::TransactionFixedIncome { .. } => AssetType::FixedIncome,
+            Self::Forex { .. } => AssetType::Forex,
+            Self::Future { .. } => AssetType::Future,
+            Self::Index { .. } => AssetType::Index,
+            Self::TransactionMutualFund { .. } => AssetType::MutualFund,
+            Self::Product { .. } => AssetType::Product,
+            Self::Currency { .. } => AssetType::Currency,
+        }
+    }
 
-    Product { },
-    */
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            Self::Unknown => None,
+            Self::TransactionEquity { symbol, .. }
+            | Self::CollectiveInvestment { symbol, .. }
+            | Self::TransactionOption { symbol, .. }
+            | Self::TransactionCashEquivalent { symbol, .. }
+            | Self::TransactionFixedIncome { symbol, .. }
+            | Self::Forex { symbol, .. }
+            | Self::Future { symbol, .. }
+            | Self::Index { symbol, .. }
+            | Self::TransactionMutualFund { symbol, .. }
+            | Self::Product { symbol, .. }
+            | Self::Currency { symbol, .. } => Some(symbol),
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Default)]
@@ -1350,7 +1672,7 @@ pub enum OrderStrategyType {
     Trigger,
 }
 
-#[derive(Deserialize, Serialize, Debug, Default)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 pub enum OrderStatus {
     #[default]
     #[serde(rename(serialize = "UNKNOWN"))]
@@ -1438,7 +1760,49 @@ pub enum OrderStatus {
     PendingRecall,
 }
 
-#[derive(Deserialize, Serialize, Debug, Default)]
+impl OrderStatus {
+    // Terminal orders will never change state again.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Self::Filled | Self::Canceled | Self::Replaced | Self::Rejected | Self::Expired
+        )
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                OrderStatus::AwaitingParentOrder => "AWAITING_PARENT_ORDER",
+                OrderStatus::AwaitingCondition => "AWAITING_CONDITION",
+                OrderStatus::AwaitingStopCondition => "AWAITING_STOP_CONDITION",
+                OrderStatus::AwaitingManualReview => "AWAITING_MANUAL_REVIEW",
+                OrderStatus::Accepted => "ACCEPTED",
+                OrderStatus::AwaitingUrOut => "AWAITING_UR_OUT",
+                OrderStatus::PendingActivation => "PENDING_ACTIVATION",
+                OrderStatus::Queued => "QUEUED",
+                OrderStatus::Working => "WORKING",
+                OrderStatus::Rejected => "REJECTED",
+                OrderStatus::PendingCancel => "PENDING_CANCEL",
+                OrderStatus::Canceled => "CANCELED",
+                OrderStatus::PendingReplace => "PENDING_REPLACE",
+                OrderStatus::Replaced => "REPLACED",
+                OrderStatus::Filled => "FILLED",
+                OrderStatus::Expired => "EXPIRED",
+                OrderStatus::New => "NEW",
+                OrderStatus::AwaitingReleaseTime => "AWAITING_RELEASE_TIME",
+                OrderStatus::PendingAcknowledgement => "PENDING_ACKNOWLEDGEMENT",
+                OrderStatus::PendingRecall => "PENDING_RECALL",
+                OrderStatus::Unknown => "UNKNOWN",
+            }
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 pub enum OrderInstruction {
     #[default]
     Unknown,
@@ -1633,6 +1997,20 @@ pub enum AccountsInstrument {
     },
 }
 
+impl AccountsInstrument {
+    pub fn symbol(&self) -> Option<String> {
+        match self {
+            Self::Unknown => None,
+            Self::CashEquivalent { symbol, .. } => Some(symbol.clone()),
+            Self::Equity { symbol, .. } => Some(symbol.clone()),
+            Self::CollectiveInvestment { symbol, .. } => Some(symbol.clone()),
+            Self::FixedIncome { symbol, .. } => Some(symbol.clone()),
+            Self::MutualFund { symbol, .. } => Some(symbol.clone()),
+            Self::Option { symbol, .. } => Some(symbol.clone()),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Default)]
 pub enum ApiCurrencyType {
     #[default]
@@ -1906,22 +2284,503 @@ pub struct Order {
     pub editable: bool,
     pub status: OrderStatus,
     pub entered_time: String,
-    pub close_time: String,
+    // Only set once the order is no longer working.
+    pub close_time: Option<String>,
     pub account_number: i64,
     pub order_activity_collection: Vec<OrderActivity>,
+    // The order that took this one's place, once it is replaced.
+    #[serde(default)]
+    pub replacing_order_collection: Vec<Order>,
     pub tag: Option<String>,
     pub status_description: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Default)]
+// The body of a non 2xx response. The trader API returns a message with plain string errors, while
+// the market data API returns a list of detailed errors without a message.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ApiError {
-    /*
-        {
-      "message": "string",
-      "errors": [
-        "string"
-      ]
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub errors: Vec<ApiErrorDetail>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum ApiErrorDetail {
+    Message(String),
+    Detail {
+        id: Option<String>,
+        status: Option<String>,
+        title: Option<String>,
+        detail: Option<String>,
+    },
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<String> = self
+            .errors
+            .iter()
+            .filter_map(|e| match e {
+                ApiErrorDetail::Message(m) => Some(m.clone()),
+                ApiErrorDetail::Detail { title, detail, .. } => match (title, detail) {
+                    (Some(t), Some(d)) => Some(format!("{}: {}", t, d)),
+                    (t, d) => t.clone().or(d.clone()),
+                },
+            })
+            .collect();
+
+        match (self.message.as_ref(), errors.is_empty()) {
+            (Some(m), true) => write!(f, "{}", m),
+            (Some(m), false) => write!(f, "{} ({})", m, errors.join("; ")),
+            (None, _) => write!(f, "{}", errors.join("; ")),
         }
-    */
+    }
+}
+
+// The response of previewOrder: what the order would cost and whether Schwab would accept it.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewOrder {
+    pub order_id: Option<i64>,
+    pub order_strategy: Option<PreviewOrderStrategy>,
+    #[serde(default)]
+    pub order_validation_result: OrderValidationResult,
+    pub commission_and_fee: Option<CommissionAndFee>,
+}
+
+impl PreviewOrder {
+    // An order with any reject would be refused if it were placed.
+    pub fn is_rejected(&self) -> bool {
+        !self.order_validation_result.rejects.is_empty()
+    }
+
+    pub fn commission(&self) -> f64 {
+        self.commission_and_fee
+            .as_ref()
+            .and_then(|c| c.commission.as_ref())
+            .map(|c| {
+                c.commission_legs
+                    .iter()
+                    .flat_map(|l| l.commission_values.iter())
+                    .map(|v| v.value)
+                    .sum()
+            })
+            .unwrap_or(0.0)
+    }
+
+    pub fn fees(&self) -> f64 {
+        self.commission_and_fee
+            .as_ref()
+            .and_then(|c| c.fee.as_ref())
+            .map(|f| {
+                f.fee_legs
+                    .iter()
+                    .flat_map(|l| l.fee_values.iter())
+                    .map(|v| v.value)
+                    .sum()
+            })
+            .unwrap_or(0.0)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewOrderStrategy {
+    pub account_number: Option<String>,
+    pub status: Option<OrderStatus>,
+    pub order_balance: Option<OrderBalance>,
+    pub order_value: Option<f64>,
+    pub price: Option<f64>,
+    pub quantity: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderBalance {
+    pub order_value: Option<f64>,
+    pub projected_available_fund: Option<f64>,
+    pub projected_buying_power: Option<f64>,
+    pub projected_commission: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderValidationResult {
+    #[serde(default)]
+    pub alerts: Vec<OrderValidationDetail>,
+    #[serde(default)]
+    pub accepts: Vec<OrderValidationDetail>,
+    #[serde(default)]
+    pub rejects: Vec<OrderValidationDetail>,
+    #[serde(default)]
+    pub reviews: Vec<OrderValidationDetail>,
+    #[serde(default)]
+    pub warns: Vec<OrderValidationDetail>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderValidationDetail {
+    pub validation_rule_name: Option<String>,
+    pub message: Option<String>,
+    pub activity_message: Option<String>,
+    pub original_severity: Option<ApiRuleAction>,
+    pub override_name: Option<String>,
+    pub override_severity: Option<ApiRuleAction>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+pub enum ApiRuleAction {
+    #[default]
+    #[serde(rename(serialize = "UNKNOWN"))]
+    #[serde(rename(deserialize = "UNKNOWN"))]
+    Unknown,
+
+    #[serde(rename(serialize = "ACCEPT"))]
+    #[serde(rename(deserialize = "ACCEPT"))]
+    Accept,
+
+    #[serde(rename(serialize = "ALERT"))]
+    #[serde(rename(deserialize = "ALERT"))]
+    Alert,
+
+    #[serde(rename(serialize = "REJECT"))]
+    #[serde(rename(deserialize = "REJECT"))]
+    Reject,
+
+    #[serde(rename(serialize = "REVIEW"))]
+    #[serde(rename(deserialize = "REVIEW"))]
+    Review,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommissionAndFee {
+    pub commission: Option<Commission>,
+    pub fee: Option<Fees>,
+    pub true_commission: Option<Commission>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Commission {
+    #[serde(default)]
+    pub commission_legs: Vec<CommissionLeg>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommissionLeg {
+    #[serde(default)]
+    pub commission_values: Vec<FeeValue>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Fees {
+    #[serde(default)]
+    pub fee_legs: Vec<FeeLeg>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeLeg {
+    #[serde(default)]
+    pub fee_values: Vec<FeeValue>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeValue {
+    pub value: f64,
+    pub r#type: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AccountInstrument, AssetType, MarginInitialBalance, Position,
+        TransactionCashEquivalentType, TransactionFixedIncomeType, TransactionInstrument,
+        TransactionMutualFundType, Transactions,
+    };
+    use serde_json::{Value, json};
+
+    #[test]
+    fn test_margin_account_cash_falls_back_to_margin_balance() {
+        let cash_account = MarginInitialBalance {
+            total_cash: 1000.0,
+            margin_balance: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(cash_account.cash(), 1000.0);
+
+        let margin_account = MarginInitialBalance {
+            total_cash: 0.0,
+            margin_balance: 2500.0,
+            ..Default::default()
+        };
+        assert_eq!(margin_account.cash(), 2500.0);
+    }
+
+    fn transaction(activity_id: i64, instrument: Value) -> Value {
+        json!({
+            "activityId": activity_id,
+            "time": "2025-04-01T13:00:00+0000",
+            "accountNumber": "12345678",
+            "type": "RECEIVE_AND_DELIVER",
+            "status": "VALID",
+            "subAccount": "CASH",
+            "tradeDate": "2025-04-01T13:00:00+0000",
+            "positionId": activity_id,
+            "netAmount": 0.0,
+            "transferItems": [{"instrument": instrument, "amount": 10.0, "cost": 0.0}]
+        })
+    }
+
+    fn position(market_value: f64, instrument: Value) -> Value {
+        json!({
+            "shortQuantity": 0.0,
+            "averagePrice": 1.0,
+            "currentDayProfitLoss": 0.5,
+            "currentDayProfitLossPercentage": 0.1,
+            "longQuantity": 10.0,
+            "settledLongQuantity": 10.0,
+            "settledShortQuantity": 0.0,
+            "instrument": instrument,
+            "marketValue": market_value,
+            "longOpenProfitLoss": 1.0,
+            "currentDayCost": 0.0
+        })
+    }
+
+    #[test]
+    fn test_positions_with_every_instrument_kind() {
+        let positions: Vec<Position> = serde_json::from_value(json!([
+            position(100.0, json!({
+                "assetType": "MUTUAL_FUND", "cusip": "922908728", "symbol": "VTSAX",
+                "description": "VANGUARD TOTAL STOCK MKT IDX ADM", "instrumentId": 200,
+                "netChange": 0.42
+            })),
+            position(200.0, json!({
+                "assetType": "FIXED_INCOME", "cusip": "912797KX4", "symbol": "912797KX4",
+                "description": "US TREASURY BILL 07/01/25",
+                "maturityDate": "2025-07-01T00:00:00.000+00:00", "factor": 1.0, "variableRate": 0.0,
+                "type": "US_TREASURY_BILL"
+            })),
+            position(300.0, json!({
+                "assetType": "CASH_EQUIVALENT", "symbol": "SWVXX",
+                "description": "SCHWAB VALUE ADVANTAGE MONEY INV", "type": "MONEY_MARKET_FUND"
+            })),
+            position(400.0, json!({"assetType": "INDEX", "symbol": "$SPX", "type": "BROAD_BASED"})),
+            position(500.0, json!({"assetType": "CURRENCY", "symbol": "CURRENCY_USD"})),
+            position(600.0, json!({"assetType": "SOMETHING_NEW", "symbol": "XYZ"}))
+        ]))
+        .unwrap();
+
+        assert!(matches!(
+            positions[0].instrument,
+            AccountInstrument::MutualFund {
+                instrument_id: Some(200),
+                ..
+            }
+        ));
+        assert!(matches!(
+            positions[1].instrument,
+            AccountInstrument::FixedIncome {
+                factor: Some(1.0),
+                ..
+            }
+        ));
+        assert!(matches!(
+            positions[2].instrument,
+            AccountInstrument::CashEquivalent { .. }
+        ));
+        assert!(matches!(
+            positions[3].instrument,
+            AccountInstrument::Index { .. }
+        ));
+        assert!(matches!(
+            positions[4].instrument,
+            AccountInstrument::Currency { .. }
+        ));
+        assert!(matches!(
+            positions[5].instrument,
+            AccountInstrument::Unknown
+        ));
+        assert_eq!(
+            positions
+                .iter()
+                .map(|p| p.instrument.symbol())
+                .collect::<Vec<_>>(),
+            [
+                Some("VTSAX".to_string()),
+                Some("912797KX4".to_string()),
+                Some("SWVXX".to_string()),
+                Some("$SPX".to_string()),
+                Some("CURRENCY_USD".to_string()),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transactions_with_every_instrument_kind() {
+        let transactions: Transactions = serde_json::from_value(json!([
+            transaction(1, json!({
+                "assetType": "CASH_EQUIVALENT", "status": "ACTIVE", "symbol": "SWVXX",
+                "description": "SCHWAB VALUE ADVANTAGE MONEY INV", "instrumentId": 100,
+                "closingPrice": 1.0, "type": "MONEY_MARKET_FUND"
+            })),
+            transaction(2, json!({
+                "assetType": "FIXED_INCOME", "status": "ACTIVE", "symbol": "912797KX4",
+                "cusip": "912797KX4", "description": "US TREASURY BILL 07/01/25",
+                "instrumentId": 101, "closingPrice": 99.2, "type": "US_TREASURY_BILL",
+                "maturityDate": "2025-07-01T00:00:00+0000", "factor": 1.0, "multiplier": 0.01
+            })),
+            transaction(3, json!({
+                "assetType": "MUTUAL_FUND", "status": "ACTIVE", "symbol": "VTSAX",
+                "cusip": "922908728", "description": "VANGUARD TOTAL STOCK MARKET INDEX ADMIRAL",
+                "instrumentId": 102, "closingPrice": 140.1, "type": "NO_LOAD_TAXABLE",
+                "fundFamilyName": "Vanguard"
+            })),
+            transaction(4, json!({
+                "assetType": "INDEX", "symbol": "$SPX", "description": "S&P 500 INDEX",
+                "instrumentId": 103, "type": "BROAD_BASED", "activeContract": false
+            })),
+            transaction(5, json!({
+                "assetType": "FOREX", "symbol": "EUR/USD", "instrumentId": 104, "type": "STANDARD",
+                "baseCurrency": {"assetType": "CURRENCY", "cusip": "", "symbol": "EUR",
+                                 "description": "Euro", "instrumentId": 5, "netChange": 0.0},
+                "counterCurrency": {"assetType": "CURRENCY", "cusip": "", "symbol": "USD",
+                                    "description": "US dollar", "instrumentId": 1, "netChange": 0.0}
+            })),
+            transaction(6, json!({
+                "assetType": "FUTURE", "symbol": "/ESM25", "instrumentId": 105, "type": "STANDARD",
+                "activeContract": true, "expirationDate": "2025-06-20T00:00:00+0000",
+                "multiplier": 50.0
+            })),
+            transaction(7, json!({
+                "assetType": "PRODUCT", "symbol": "TBD", "instrumentId": 106, "type": "TBD"
+            })),
+            transaction(8, json!({
+                "assetType": "CURRENCY", "status": "ACTIVE", "symbol": "CURRENCY_USD",
+                "description": "USD currency", "instrumentId": 1, "closingPrice": 0.0
+            })),
+        ]))
+        .unwrap();
+
+        let instruments: Vec<&TransactionInstrument> = transactions
+            .iter()
+            .map(|t| &t.transfer_items[0].instrument)
+            .collect();
+        assert!(matches!(
+            instruments[0],
+            TransactionInstrument::TransactionCashEquivalent {
+                tce_type: TransactionCashEquivalentType::MoneyMarketFund,
+                ..
+            }
+        ));
+        assert!(matches!(
+            instruments[1],
+            TransactionInstrument::TransactionFixedIncome {
+                tfi_type: TransactionFixedIncomeType::UsTreasuryBill,
+                ..
+            }
+        ));
+        assert!(matches!(
+            instruments[1].asset_type(),
+            AssetType::FixedIncome
+        ));
+        assert!(matches!(
+            instruments[2],
+            TransactionInstrument::TransactionMutualFund {
+                tmf_type: TransactionMutualFundType::NoLoadTaxable,
+                ..
+            }
+        ));
+        assert!(matches!(
+            instruments[3],
+            TransactionInstrument::Index { .. }
+        ));
+        assert!(matches!(
+            instruments[4],
+            TransactionInstrument::Forex { .. }
+        ));
+        assert!(matches!(
+            instruments[5],
+            TransactionInstrument::Future { .. }
+        ));
+        assert!(matches!(
+            instruments[6],
+            TransactionInstrument::Product { .. }
+        ));
+        assert!(matches!(
+            instruments[7],
+            TransactionInstrument::Currency { .. }
+        ));
+        assert_eq!(
+            instruments.iter().map(|i| i.symbol()).collect::<Vec<_>>(),
+            [
+                "SWVXX",
+                "912797KX4",
+                "VTSAX",
+                "$SPX",
+                "EUR/USD",
+                "/ESM25",
+                "TBD",
+                "CURRENCY_USD"
+            ]
+            .map(Some)
+        );
+    }
+
+    #[test]
+    fn test_transaction_instruments_are_picked_by_asset_type() {
+        let transactions: Transactions = serde_json::from_value(json!([
+            transaction(
+                1,
+                json!({
+                    "assetType": "MUTUAL_FUND", "status": "ACTIVE", "symbol": "VTSAX",
+                    "instrumentId": 102, "closingPrice": 140.1, "type": "UNKNOWN"
+                })
+            ),
+            transaction(
+                2,
+                json!({
+                    "assetType": "FIXED_INCOME", "status": "ACTIVE", "symbol": "912797KX4",
+                    "instrumentId": 101, "closingPrice": 99.2, "type": "UNKNOWN"
+                })
+            ),
+            transaction(
+                3,
+                json!({
+                    "assetType": "SWAP", "symbol": "XYZ", "instrumentId": 107
+                })
+            ),
+        ]))
+        .unwrap();
+
+        let instruments: Vec<&TransactionInstrument> = transactions
+            .iter()
+            .map(|t| &t.transfer_items[0].instrument)
+            .collect();
+        assert!(matches!(
+            instruments[0],
+            TransactionInstrument::TransactionMutualFund {
+                tmf_type: TransactionMutualFundType::Unknown,
+                ..
+            }
+        ));
+        assert!(matches!(
+            instruments[1],
+            TransactionInstrument::TransactionFixedIncome {
+                tfi_type: TransactionFixedIncomeType::Unknown,
+                ..
+            }
+        ));
+        assert!(matches!(instruments[2], TransactionInstrument::Unknown));
+    }
 }
@@ -1 +1,2 @@
 pub mod accounts;
+pub mod user_preference;
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPreference {
+    #[serde(default)]
+    pub accounts: Vec<UserPreferenceAccount>,
+    #[serde(default)]
+    pub streamer_info: Vec<StreamerInfo>,
+    #[serde(default)]
+    pub offers: Vec<Offer>,
+}
+
+impl UserPreference {
+    pub fn account(&self, account_number: &str) -> Option<&UserPreferenceAccount> {
+        self.accounts
+            .iter()
+            .find(|a| a.account_number == account_number)
+    }
+
+    // The nickname given to the account in the Schwab app, if there is one.
+    pub fn nickname(&self, account_number: &str) -> Option<String> {
+        self.account(account_number)?
+            .nick_name
+            .clone()
+            .filter(|n| !n.is_empty())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPreferenceAccount {
+    pub account_number: String,
+    #[serde(default)]
+    pub primary_account: bool,
+    pub r#type: Option<String>,
+    pub nick_name: Option<String>,
+    pub account_color: Option<String>,
+    // The masked account number Schwab shows, e.g. "...678".
+    pub display_acct_id: Option<String>,
+    #[serde(default)]
+    pub auto_position_effect: bool,
+}
+
+// What a streaming client needs to log in to the streamer.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamerInfo {
+    pub streamer_socket_url: String,
+    pub schwab_client_customer_id: String,
+    pub schwab_client_correl_id: String,
+    pub schwab_client_channel: String,
+    pub schwab_client_function_id: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Offer {
+    #[serde(default)]
+    pub level2_permissions: bool,
+    pub mkt_data_permission: Option<String>,
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// What the instruments endpoint searches by. The symbol and description searches accept several
+// comma separated symbols or a regular expression, and the fundamental projection returns the
+// fundamentals of the exact symbols given.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    #[serde(rename(serialize = "symbol-search"))]
+    #[serde(rename(deserialize = "symbol-search"))]
+    SymbolSearch,
+
+    #[serde(rename(serialize = "symbol-regex"))]
+    #[serde(rename(deserialize = "symbol-regex"))]
+    SymbolRegex,
+
+    #[serde(rename(serialize = "desc-search"))]
+    #[serde(rename(deserialize = "desc-search"))]
+    DescSearch,
+
+    #[serde(rename(serialize = "desc-regex"))]
+    #[serde(rename(deserialize = "desc-regex"))]
+    DescRegex,
+
+    #[serde(rename(serialize = "search"))]
+    #[serde(rename(deserialize = "search"))]
+    Search,
+
+    #[serde(rename(serialize = "fundamental"))]
+    #[serde(rename(deserialize = "fundamental"))]
+    Fundamental,
+}
+
+impl fmt::Display for Projection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Projection::SymbolSearch => "symbol-search",
+                Projection::SymbolRegex => "symbol-regex",
+                Projection::DescSearch => "desc-search",
+                Projection::DescRegex => "desc-regex",
+                Projection::Search => "search",
+                Projection::Fundamental => "fundamental",
+            }
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub enum InstrumentAssetType {
+    #[serde(rename(serialize = "BOND"))]
+    #[serde(rename(deserialize = "BOND"))]
+    Bond,
+
+    #[serde(rename(serialize = "EQUITY"))]
+    #[serde(rename(deserialize = "EQUITY"))]
+    Equity,
+
+    #[serde(rename(serialize = "ETF"))]
+    #[serde(rename(deserialize = "ETF"))]
+    Etf,
+
+    #[serde(rename(serialize = "EXTENDED"))]
+    #[serde(rename(deserialize = "EXTENDED"))]
+    Extended,
+
+    #[serde(rename(serialize = "FOREX"))]
+    #[serde(rename(deserialize = "FOREX"))]
+    Forex,
+
+    #[serde(rename(serialize = "FUTURE"))]
+    #[serde(rename(deserialize = "FUTURE"))]
+    Future,
+
+    #[serde(rename(serialize = "FUTURE_OPTION"))]
+    #[serde(rename(deserialize = "FUTURE_OPTION"))]
+    FutureOption,
+
+    #[serde(rename(serialize = "FUNDAMENTAL"))]
+    #[serde(rename(deserialize = "FUNDAMENTAL"))]
+    Fundamental,
+
+    #[serde(rename(serialize = "INDEX"))]
+    #[serde(rename(deserialize = "INDEX"))]
+    Index,
+
+    #[serde(rename(serialize = "INDICATOR"))]
+    #[serde(rename(deserialize = "INDICATOR"))]
+    Indicator,
+
+    #[serde(rename(serialize = "MUTUAL_FUND"))]
+    #[serde(rename(deserialize = "MUTUAL_FUND"))]
+    MutualFund,
+
+    #[serde(rename(serialize = "OPTION"))]
+    #[serde(rename(deserialize = "OPTION"))]
+    Option,
+
+    #[serde(rename(serialize = "UNKNOWN"))]
+    #[serde(rename(deserialize = "UNKNOWN"))]
+    #[serde(other)]
+    Unknown,
+}
+
+impl fmt::Display for InstrumentAssetType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                InstrumentAssetType::Bond => "BOND",
+                InstrumentAssetType::Equity => "EQUITY",
+                InstrumentAssetType::Etf => "ETF",
+                InstrumentAssetType::Extended => "EXTENDED",
+                InstrumentAssetType::Forex => "FOREX",
+                InstrumentAssetType::Future => "FUTURE",
+                InstrumentAssetType::FutureOption => "FUTURE_OPTION",
+                InstrumentAssetType::Fundamental => "FUNDAMENTAL",
+                InstrumentAssetType::Index => "INDEX",
+                InstrumentAssetType::Indicator => "INDICATOR",
+                InstrumentAssetType::MutualFund => "MUTUAL_FUND",
+                InstrumentAssetType::Option => "OPTION",
+                InstrumentAssetType::Unknown => "UNKNOWN",
+            }
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct InstrumentsResponse {
+    #[serde(default)]
+    pub instruments: Vec<Instrument>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Instrument {
+    pub cusip: Option<String>,
+    pub symbol: String,
+    pub description: Option<String>,
+    pub exchange: Option<String>,
+    pub asset_type: InstrumentAssetType,
+    // Only returned with the fundamental projection.
+    pub fundamental: Option<InstrumentFundamental>,
+}
+
+impl Instrument {
+    // Whether the trader can buy it like the symbols in the trading collections.
+    pub fn is_equity_or_etf(&self) -> bool {
+        matches!(
+            self.asset_type,
+            InstrumentAssetType::Equity | InstrumentAssetType::Etf
+        )
+    }
+
+    // The description, e.g. "Vanguard Total Stock Market ETF", falling back to the symbol.
+    pub fn name(&self) -> &str {
+        self.description
+            .as_deref()
+            .filter(|d| !d.is_empty())
+            .unwrap_or(&self.symbol)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InstrumentFundamental {
+    pub symbol: Option<String>,
+    pub high52: Option<f64>,
+    pub low52: Option<f64>,
+    pub dividend_amount: Option<f64>,
+    pub dividend_yield: Option<f64>,
+    pub dividend_date: Option<String>,
+    pub dividend_freq: Option<i64>,
+    pub dividend_pay_date: Option<String>,
+    pub next_dividend_date: Option<String>,
+    pub next_dividend_pay_date: Option<String>,
+    pub pe_ratio: Option<f64>,
+    pub peg_ratio: Option<f64>,
+    pub pb_ratio: Option<f64>,
+    #[serde(rename(serialize = "epsTTM"))]
+    #[serde(rename(deserialize = "epsTTM"))]
+    pub eps_ttm: Option<f64>,
+    pub shares_outstanding: Option<f64>,
+    pub market_cap: Option<f64>,
+    pub beta: Option<f64>,
+    pub avg10_days_volume: Option<f64>,
+    pub fund_leverage_factor: Option<f64>,
+    pub fund_strategy: Option<String>,
+}
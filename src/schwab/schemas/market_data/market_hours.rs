@@ -0,0 +1,98 @@
+use crate::schwab::calendar::{Session, Sessions};
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt};
+
+// Hours keyed by market and then by product, e.g. "equity" -> "EQ".
+pub type MarketHoursResponse = HashMap<String, HashMap<String, Hours>>;
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Hours {
+    pub date: String,
+    pub market_type: Option<String>,
+    pub exchange: Option<String>,
+    pub category: Option<String>,
+    pub product: Option<String>,
+    pub product_name: Option<String>,
+    pub is_open: bool,
+    pub session_hours: Option<SessionHours>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionHours {
+    #[serde(default)]
+    pub pre_market: Vec<Interval>,
+    #[serde(default)]
+    pub regular_market: Vec<Interval>,
+    #[serde(default)]
+    pub post_market: Vec<Interval>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct Interval {
+    pub start: String,
+    pub end: String,
+}
+
+impl Interval {
+    fn session(&self) -> Option<Session> {
+        Some((
+            DateTime::parse_from_rfc3339(&self.start).ok()?.to_utc(),
+            DateTime::parse_from_rfc3339(&self.end).ok()?.to_utc(),
+        ))
+    }
+}
+
+impl Hours {
+    pub fn sessions(&self) -> Sessions {
+        match (self.is_open, self.session_hours.as_ref()) {
+            (true, Some(hours)) => Sessions {
+                pre_market: hours.pre_market.first().and_then(|i| i.session()),
+                regular: hours.regular_market.first().and_then(|i| i.session()),
+                post_market: hours.post_market.first().and_then(|i| i.session()),
+            },
+            _ => Sessions::closed(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub enum MarketType {
+    #[serde(rename(serialize = "equity"))]
+    #[serde(rename(deserialize = "equity"))]
+    Equity,
+
+    #[serde(rename(serialize = "option"))]
+    #[serde(rename(deserialize = "option"))]
+    Option,
+
+    #[serde(rename(serialize = "bond"))]
+    #[serde(rename(deserialize = "bond"))]
+    Bond,
+
+    #[serde(rename(serialize = "future"))]
+    #[serde(rename(deserialize = "future"))]
+    Future,
+
+    #[serde(rename(serialize = "forex"))]
+    #[serde(rename(deserialize = "forex"))]
+    Forex,
+}
+
+impl fmt::Display for MarketType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                MarketType::Equity => "equity",
+                MarketType::Option => "option",
+                MarketType::Bond => "bond",
+                MarketType::Future => "future",
+                MarketType::Forex => "forex",
+            }
+        )
+    }
+}
@@ -0,0 +1,6 @@
+pub mod instruments;
+pub mod market_hours;
+pub mod movers;
+pub mod option_chains;
+pub mod price_history;
+pub mod quotes;
@@ -0,0 +1,154 @@
+use crate::schwab::math::two_decimals;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// The index or market whose top movers are listed.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum MoverIndex {
+    #[serde(rename(serialize = "$DJI"))]
+    #[serde(rename(deserialize = "$DJI"))]
+    Dji,
+
+    #[serde(rename(serialize = "$COMPX"))]
+    #[serde(rename(deserialize = "$COMPX"))]
+    Compx,
+
+    #[serde(rename(serialize = "$SPX"))]
+    #[serde(rename(deserialize = "$SPX"))]
+    Spx,
+
+    #[serde(rename(serialize = "NYSE"))]
+    #[serde(rename(deserialize = "NYSE"))]
+    Nyse,
+
+    #[serde(rename(serialize = "NASDAQ"))]
+    #[serde(rename(deserialize = "NASDAQ"))]
+    Nasdaq,
+
+    #[serde(rename(serialize = "OTCBB"))]
+    #[serde(rename(deserialize = "OTCBB"))]
+    Otcbb,
+
+    #[serde(rename(serialize = "INDEX_ALL"))]
+    #[serde(rename(deserialize = "INDEX_ALL"))]
+    IndexAll,
+
+    #[serde(rename(serialize = "EQUITY_ALL"))]
+    #[serde(rename(deserialize = "EQUITY_ALL"))]
+    EquityAll,
+
+    #[serde(rename(serialize = "OPTION_ALL"))]
+    #[serde(rename(deserialize = "OPTION_ALL"))]
+    OptionAll,
+
+    #[serde(rename(serialize = "OPTION_PUT"))]
+    #[serde(rename(deserialize = "OPTION_PUT"))]
+    OptionPut,
+
+    #[serde(rename(serialize = "OPTION_CALL"))]
+    #[serde(rename(deserialize = "OPTION_CALL"))]
+    OptionCall,
+}
+
+impl fmt::Display for MoverIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                MoverIndex::Dji => "$DJI",
+                MoverIndex::Compx => "$COMPX",
+                MoverIndex::Spx => "$SPX",
+                MoverIndex::Nyse => "NYSE",
+                MoverIndex::Nasdaq => "NASDAQ",
+                MoverIndex::Otcbb => "OTCBB",
+                MoverIndex::IndexAll => "INDEX_ALL",
+                MoverIndex::EquityAll => "EQUITY_ALL",
+                MoverIndex::OptionAll => "OPTION_ALL",
+                MoverIndex::OptionPut => "OPTION_PUT",
+                MoverIndex::OptionCall => "OPTION_CALL",
+            }
+        )
+    }
+}
+
+impl MoverIndex {
+    // How the index is labelled on the dashboard.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MoverIndex::Dji => "Dow Jones",
+            MoverIndex::Compx => "Nasdaq Composite",
+            MoverIndex::Spx => "S&P 500",
+            MoverIndex::Nyse => "NYSE",
+            MoverIndex::Nasdaq => "Nasdaq",
+            MoverIndex::Otcbb => "OTC",
+            MoverIndex::IndexAll => "All indices",
+            MoverIndex::EquityAll => "All equities",
+            MoverIndex::OptionAll => "All options",
+            MoverIndex::OptionPut => "Puts",
+            MoverIndex::OptionCall => "Calls",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum MoverSort {
+    #[serde(rename(serialize = "VOLUME"))]
+    #[serde(rename(deserialize = "VOLUME"))]
+    Volume,
+
+    #[serde(rename(serialize = "TRADES"))]
+    #[serde(rename(deserialize = "TRADES"))]
+    Trades,
+
+    #[serde(rename(serialize = "PERCENT_CHANGE_UP"))]
+    #[serde(rename(deserialize = "PERCENT_CHANGE_UP"))]
+    PercentChangeUp,
+
+    #[serde(rename(serialize = "PERCENT_CHANGE_DOWN"))]
+    #[serde(rename(deserialize = "PERCENT_CHANGE_DOWN"))]
+    PercentChangeDown,
+}
+
+impl fmt::Display for MoverSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                MoverSort::Volume => "VOLUME",
+                MoverSort::Trades => "TRADES",
+                MoverSort::PercentChangeUp => "PERCENT_CHANGE_UP",
+                MoverSort::PercentChangeDown => "PERCENT_CHANGE_DOWN",
+            }
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct MoversResponse {
+    #[serde(default)]
+    pub screeners: Vec<Screener>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Screener {
+    pub symbol: String,
+    pub description: Option<String>,
+    pub last_price: Option<f64>,
+    pub net_change: Option<f64>,
+    // A fraction, e.g. 0.0123 for 1.23%.
+    pub net_percent_change: Option<f64>,
+    pub volume: Option<i64>,
+    pub total_volume: Option<i64>,
+    pub trades: Option<i64>,
+    pub market_share: Option<f64>,
+}
+
+impl Screener {
+    // The change in percent, rounded to two decimals.
+    pub fn percent_change(&self) -> Option<f64> {
+        self.net_percent_change.map(|p| two_decimals(p * 100.0))
+    }
+}
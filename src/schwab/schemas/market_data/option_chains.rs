@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt};
+
+// Contracts keyed by "<expiration date>:<days to expiration>" and then by strike price.
+pub type ExpirationDateMap = HashMap<String, HashMap<String, Vec<OptionContract>>>;
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionChain {
+    pub symbol: String,
+    pub status: Option<String>,
+    pub underlying: Option<Underlying>,
+    pub strategy: Option<String>,
+    pub interval: Option<f64>,
+    pub is_delayed: Option<bool>,
+    pub is_index: Option<bool>,
+    pub days_to_expiration: Option<f64>,
+    pub interest_rate: Option<f64>,
+    pub underlying_price: Option<f64>,
+    pub volatility: Option<f64>,
+    #[serde(default)]
+    pub call_exp_date_map: ExpirationDateMap,
+    #[serde(default)]
+    pub put_exp_date_map: ExpirationDateMap,
+}
+
+// Which contracts to pick from a chain, e.g. a put around -0.30 delta one to two months out.
+#[derive(Debug, Clone)]
+pub struct ContractSelection {
+    pub put_call: PutCall,
+    pub target_delta: f64,
+    pub min_days_to_expiration: i64,
+    pub max_days_to_expiration: i64,
+}
+
+impl OptionChain {
+    pub fn contracts(&self, put_call: &PutCall) -> impl Iterator<Item = &OptionContract> {
+        match put_call {
+            PutCall::Call => &self.call_exp_date_map,
+            PutCall::Put => &self.put_exp_date_map,
+        }
+        .values()
+        .flat_map(|strikes| strikes.values())
+        .flatten()
+    }
+
+    // The contract expiring inside the window whose delta is closest to the target, preferring the
+    // earlier expiration and then the lower strike when two are equally close.
+    pub fn select_contract(&self, selection: &ContractSelection) -> Option<&OptionContract> {
+        self.contracts(&selection.put_call)
+            .filter(|c| {
+                c.days_to_expiration >= selection.min_days_to_expiration
+                    && c.days_to_expiration <= selection.max_days_to_expiration
+            })
+            .filter_map(|c| {
+                c.delta()
+                    .map(|delta| (c, (delta - selection.target_delta).abs()))
+            })
+            .min_by(|(a, a_distance), (b, b_distance)| {
+                a_distance
+                    .total_cmp(b_distance)
+                    .then(a.days_to_expiration.cmp(&b.days_to_expiration))
+                    .then(a.strike_price.total_cmp(&b.strike_price))
+            })
+            .map(|(c, _)| c)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Underlying {
+    pub symbol: Option<String>,
+    pub description: Option<String>,
+    pub ask: Option<f64>,
+    pub bid: Option<f64>,
+    pub last: Option<f64>,
+    pub mark: Option<f64>,
+    pub change: Option<f64>,
+    pub percent_change: Option<f64>,
+    pub close: Option<f64>,
+    pub total_volume: Option<i64>,
+    pub fifty_two_week_high: Option<f64>,
+    pub fifty_two_week_low: Option<f64>,
+    pub delayed: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionContract {
+    pub put_call: PutCall,
+    pub symbol: String,
+    pub description: Option<String>,
+    pub exchange_name: Option<String>,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub last: Option<f64>,
+    pub mark: Option<f64>,
+    pub bid_size: Option<i64>,
+    pub ask_size: Option<i64>,
+    pub last_size: Option<i64>,
+    pub high_price: Option<f64>,
+    pub low_price: Option<f64>,
+    pub open_price: Option<f64>,
+    pub close_price: Option<f64>,
+    pub total_volume: Option<i64>,
+    pub net_change: Option<f64>,
+    pub volatility: Option<f64>,
+    // Greeks Schwab can't calculate come back as -999.
+    pub delta: Option<f64>,
+    pub gamma: Option<f64>,
+    pub theta: Option<f64>,
+    pub vega: Option<f64>,
+    pub rho: Option<f64>,
+    pub time_value: Option<f64>,
+    pub open_interest: Option<i64>,
+    pub is_in_the_money: Option<bool>,
+    pub theoretical_option_value: Option<f64>,
+    pub theoretical_volatility: Option<f64>,
+    pub is_mini: Option<bool>,
+    pub is_non_standard: Option<bool>,
+    pub strike_price: f64,
+    pub expiration_date: String,
+    pub days_to_expiration: i64,
+    pub expiration_type: Option<ExpirationType>,
+    pub last_trading_day: Option<i64>,
+    pub multiplier: Option<f64>,
+    pub settlement_type: Option<String>,
+    pub deliverable_note: Option<String>,
+    pub percent_change: Option<f64>,
+    pub mark_change: Option<f64>,
+    pub mark_percent_change: Option<f64>,
+    pub intrinsic_value: Option<f64>,
+    pub is_penny_pilot: Option<bool>,
+    pub option_root: Option<String>,
+}
+
+impl OptionContract {
+    pub fn delta(&self) -> Option<f64> {
+        self.delta.filter(|d| d.abs() <= 1.0)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+pub enum PutCall {
+    #[default]
+    #[serde(rename(serialize = "PUT"))]
+    #[serde(rename(deserialize = "PUT"))]
+    Put,
+
+    #[serde(rename(serialize = "CALL"))]
+    #[serde(rename(deserialize = "CALL"))]
+    Call,
+}
+
+impl fmt::Display for PutCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PutCall::Put => "PUT",
+                PutCall::Call => "CALL",
+            }
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub enum ExpirationType {
+    // End of month
+    #[serde(rename(serialize = "M"))]
+    #[serde(rename(deserialize = "M"))]
+    EndOfMonth,
+
+    // Quarterly
+    #[serde(rename(serialize = "Q"))]
+    #[serde(rename(deserialize = "Q"))]
+    Quarterly,
+
+    // Third friday of the month
+    #[serde(rename(serialize = "S"))]
+    #[serde(rename(deserialize = "S"))]
+    Standard,
+
+    // Weekly
+    #[serde(rename(serialize = "W"))]
+    #[serde(rename(deserialize = "W"))]
+    Weekly,
+
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpirationChain {
+    pub status: Option<String>,
+    #[serde(default)]
+    pub expiration_list: Vec<Expiration>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Expiration {
+    pub expiration_date: String,
+    pub days_to_expiration: i64,
+    pub expiration_type: Option<ExpirationType>,
+    pub standard: Option<bool>,
+    pub settlement_type: Option<String>,
+    pub option_roots: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContractSelection, OptionChain, OptionContract, PutCall};
+    use std::collections::HashMap;
+
+    fn put(strike_price: f64, days_to_expiration: i64, delta: f64) -> OptionContract {
+        OptionContract {
+            put_call: PutCall::Put,
+            symbol: format!("VTI   P{}-{}", strike_price, days_to_expiration),
+            strike_price,
+            expiration_date: format!("+{} days", days_to_expiration),
+            days_to_expiration,
+            delta: Some(delta),
+            ..Default::default()
+        }
+    }
+
+    fn chain(contracts: Vec<OptionContract>) -> OptionChain {
+        let mut chain = OptionChain::default();
+        for c in contracts {
+            chain
+                .put_exp_date_map
+                .entry(format!("{}:{}", c.expiration_date, c.days_to_expiration))
+                .or_insert_with(HashMap::new)
+                .entry(c.strike_price.to_string())
+                .or_default()
+                .push(c);
+        }
+        chain
+    }
+
+    #[test]
+    fn test_select_contract_by_delta_inside_window() {
+        let chain = chain(vec![
+            put(230.0, 10, -0.30),
+            put(240.0, 40, -0.32),
+            put(235.0, 40, -0.26),
+            put(245.0, 40, -0.45),
+            put(240.0, 120, -0.30),
+            put(250.0, 40, -999.0),
+        ]);
+        let selection = ContractSelection {
+            put_call: PutCall::Put,
+            target_delta: -0.30,
+            min_days_to_expiration: 30,
+            max_days_to_expiration: 60,
+        };
+
+        let contract = chain.select_contract(&selection).unwrap();
+        assert_eq!(contract.strike_price, 240.0);
+        assert_eq!(contract.days_to_expiration, 40);
+
+        assert!(
+            chain
+                .select_contract(&ContractSelection {
+                    put_call: PutCall::Call,
+                    ..selection
+                })
+                .is_none()
+        );
+    }
+}
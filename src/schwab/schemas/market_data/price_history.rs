@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CandleList {
+    #[serde(default)]
+    pub candles: Vec<Candle>,
+    pub symbol: String,
+    #[serde(default)]
+    pub empty: bool,
+    pub previous_close: Option<f64>,
+    pub previous_close_date: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+pub struct Candle {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: i64,
+    // Milliseconds since the epoch of the start of the candle.
+    pub datetime: i64,
+}
+
+impl Candle {
+    pub fn date_time(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_millis(self.datetime)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+pub enum PeriodType {
+    #[serde(rename(serialize = "day"))]
+    #[serde(rename(deserialize = "day"))]
+    Day,
+
+    #[serde(rename(serialize = "month"))]
+    #[serde(rename(deserialize = "month"))]
+    Month,
+
+    #[default]
+    #[serde(rename(serialize = "year"))]
+    #[serde(rename(deserialize = "year"))]
+    Year,
+
+    #[serde(rename(serialize = "ytd"))]
+    #[serde(rename(deserialize = "ytd"))]
+    Ytd,
+}
+
+impl fmt::Display for PeriodType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PeriodType::Day => "day",
+                PeriodType::Month => "month",
+                PeriodType::Year => "year",
+                PeriodType::Ytd => "ytd",
+            }
+        )
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+pub enum FrequencyType {
+    #[serde(rename(serialize = "minute"))]
+    #[serde(rename(deserialize = "minute"))]
+    Minute,
+
+    #[default]
+    #[serde(rename(serialize = "daily"))]
+    #[serde(rename(deserialize = "daily"))]
+    Daily,
+
+    #[serde(rename(serialize = "weekly"))]
+    #[serde(rename(deserialize = "weekly"))]
+    Weekly,
+
+    #[serde(rename(serialize = "monthly"))]
+    #[serde(rename(deserialize = "monthly"))]
+    Monthly,
+}
+
+impl fmt::Display for FrequencyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FrequencyType::Minute => "minute",
+                FrequencyType::Daily => "daily",
+                FrequencyType::Weekly => "weekly",
+                FrequencyType::Monthly => "monthly",
+            }
+        )
+    }
+}
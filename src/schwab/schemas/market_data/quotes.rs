@@ -0,0 +1,489 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// The response of the quotes endpoints, keyed by the requested symbol. Symbols Schwab doesn't know
+// are listed under errors instead.
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct QuoteResponse {
+    pub errors: Option<QuoteError>,
+    #[serde(flatten)]
+    pub quotes: HashMap<String, QuoteResponseObject>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteError {
+    #[serde(default)]
+    pub invalid_cusips: Vec<String>,
+    #[serde(default)]
+    pub invalid_ssids: Vec<i64>,
+    #[serde(default)]
+    pub invalid_symbols: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "assetMainType")]
+pub enum QuoteResponseObject {
+    #[serde(rename(serialize = "EQUITY"))]
+    #[serde(rename(deserialize = "EQUITY"))]
+    Equity(Box<EquityResponse>),
+
+    #[serde(rename(serialize = "OPTION"))]
+    #[serde(rename(deserialize = "OPTION"))]
+    Option(Box<OptionResponse>),
+
+    #[serde(rename(serialize = "FOREX"))]
+    #[serde(rename(deserialize = "FOREX"))]
+    Forex(ForexResponse),
+
+    #[serde(rename(serialize = "FUTURE"))]
+    #[serde(rename(deserialize = "FUTURE"))]
+    Future(FutureResponse),
+
+    #[serde(rename(serialize = "FUTURE_OPTION"))]
+    #[serde(rename(deserialize = "FUTURE_OPTION"))]
+    FutureOption(FutureOptionResponse),
+
+    #[serde(rename(serialize = "INDEX"))]
+    #[serde(rename(deserialize = "INDEX"))]
+    Index(IndexResponse),
+
+    #[serde(rename(serialize = "MUTUAL_FUND"))]
+    #[serde(rename(deserialize = "MUTUAL_FUND"))]
+    MutualFund(MutualFundResponse),
+
+    #[serde(other)]
+    Unknown,
+}
+
+impl QuoteResponseObject {
+    // The price fields shared by every asset type, or None for an unknown asset type.
+    pub fn prices(&self) -> Option<&QuotePrices> {
+        match self {
+            Self::Equity(r) => r.quote.as_ref().map(|q| &q.prices),
+            Self::Option(r) => r.quote.as_ref().map(|q| &q.prices),
+            Self::Forex(r) => r.quote.as_ref().map(|q| &q.prices),
+            Self::Future(r) => r.quote.as_ref().map(|q| &q.prices),
+            Self::FutureOption(r) => r.quote.as_ref().map(|q| &q.prices),
+            Self::Index(r) => r.quote.as_ref().map(|q| &q.prices),
+            Self::MutualFund(r) => r.quote.as_ref().map(|q| &q.prices),
+            Self::Unknown => None,
+        }
+    }
+
+    pub fn ask_price(&self) -> Option<f64> {
+        self.prices()?.ask_price.filter(|p| *p > 0.0)
+    }
+
+    pub fn bid_price(&self) -> Option<f64> {
+        self.prices()?.bid_price.filter(|p| *p > 0.0)
+    }
+
+    pub fn last_price(&self) -> Option<f64> {
+        self.prices()?.last_price
+    }
+
+    pub fn mark(&self) -> Option<f64> {
+        self.prices()?.mark
+    }
+
+    pub fn net_change(&self) -> Option<f64> {
+        self.prices()?.net_change
+    }
+}
+
+// Price fields that appear in the quote block of every asset type. Not every type fills in all
+// of them, e.g. an index has no bid or ask.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotePrices {
+    #[serde(rename(serialize = "52WeekHigh"))]
+    #[serde(rename(deserialize = "52WeekHigh"))]
+    pub week_52_high: Option<f64>,
+    #[serde(rename(serialize = "52WeekLow"))]
+    #[serde(rename(deserialize = "52WeekLow"))]
+    pub week_52_low: Option<f64>,
+    pub ask_price: Option<f64>,
+    pub ask_size: Option<i64>,
+    pub bid_price: Option<f64>,
+    pub bid_size: Option<i64>,
+    pub close_price: Option<f64>,
+    pub high_price: Option<f64>,
+    pub last_price: Option<f64>,
+    pub last_size: Option<i64>,
+    pub low_price: Option<f64>,
+    pub mark: Option<f64>,
+    pub net_change: Option<f64>,
+    pub net_percent_change: Option<f64>,
+    pub open_price: Option<f64>,
+    pub quote_time: Option<i64>,
+    pub security_status: Option<String>,
+    pub total_volume: Option<i64>,
+    pub trade_time: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EquityResponse {
+    pub asset_sub_type: Option<String>,
+    pub ssid: Option<i64>,
+    pub symbol: String,
+    pub realtime: Option<bool>,
+    pub quote_type: Option<String>,
+    pub extended: Option<ExtendedMarket>,
+    pub fundamental: Option<Fundamental>,
+    pub quote: Option<QuoteEquity>,
+    pub reference: Option<ReferenceEquity>,
+    pub regular: Option<RegularMarket>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteEquity {
+    #[serde(flatten)]
+    pub prices: QuotePrices,
+    #[serde(rename(serialize = "askMICId"))]
+    #[serde(rename(deserialize = "askMICId"))]
+    pub ask_mic_id: Option<String>,
+    pub ask_time: Option<i64>,
+    #[serde(rename(serialize = "bidMICId"))]
+    #[serde(rename(deserialize = "bidMICId"))]
+    pub bid_mic_id: Option<String>,
+    pub bid_time: Option<i64>,
+    #[serde(rename(serialize = "lastMICId"))]
+    #[serde(rename(deserialize = "lastMICId"))]
+    pub last_mic_id: Option<String>,
+    pub mark_change: Option<f64>,
+    pub mark_percent_change: Option<f64>,
+    pub post_market_change: Option<f64>,
+    pub post_market_percent_change: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtendedMarket {
+    pub ask_price: Option<f64>,
+    pub ask_size: Option<i64>,
+    pub bid_price: Option<f64>,
+    pub bid_size: Option<i64>,
+    pub last_price: Option<f64>,
+    pub last_size: Option<i64>,
+    pub mark: Option<f64>,
+    pub quote_time: Option<i64>,
+    pub total_volume: Option<i64>,
+    pub trade_time: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RegularMarket {
+    pub regular_market_last_price: Option<f64>,
+    pub regular_market_last_size: Option<i64>,
+    pub regular_market_net_change: Option<f64>,
+    pub regular_market_percent_change: Option<f64>,
+    pub regular_market_trade_time: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Fundamental {
+    pub avg10_days_volume: Option<f64>,
+    pub avg1_year_volume: Option<f64>,
+    pub declaration_date: Option<String>,
+    pub div_amount: Option<f64>,
+    pub div_ex_date: Option<String>,
+    pub div_freq: Option<i64>,
+    pub div_pay_amount: Option<f64>,
+    pub div_pay_date: Option<String>,
+    pub div_yield: Option<f64>,
+    pub eps: Option<f64>,
+    pub fund_leverage_factor: Option<f64>,
+    pub fund_strategy: Option<String>,
+    pub last_earnings_date: Option<String>,
+    pub next_div_ex_date: Option<String>,
+    pub next_div_pay_date: Option<String>,
+    pub pe_ratio: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceEquity {
+    pub cusip: Option<String>,
+    pub description: Option<String>,
+    pub exchange: Option<String>,
+    pub exchange_name: Option<String>,
+    pub fsi_desc: Option<String>,
+    pub htb_quantity: Option<i64>,
+    pub htb_rate: Option<f64>,
+    pub is_hard_to_borrow: Option<bool>,
+    pub is_shortable: Option<bool>,
+    pub otc_market_tier: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionResponse {
+    pub ssid: Option<i64>,
+    pub symbol: String,
+    pub realtime: Option<bool>,
+    pub quote: Option<QuoteOption>,
+    pub reference: Option<ReferenceOption>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteOption {
+    #[serde(flatten)]
+    pub prices: QuotePrices,
+    pub delta: Option<f64>,
+    pub gamma: Option<f64>,
+    pub theta: Option<f64>,
+    pub vega: Option<f64>,
+    pub rho: Option<f64>,
+    pub volatility: Option<f64>,
+    pub implied_yield: Option<f64>,
+    pub money_intrinsic_value: Option<f64>,
+    pub open_interest: Option<f64>,
+    pub theoretical_option_value: Option<f64>,
+    pub time_value: Option<f64>,
+    pub underlying_price: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceOption {
+    pub contract_type: Option<String>,
+    pub cusip: Option<String>,
+    pub days_to_expiration: Option<i64>,
+    pub deliverables: Option<String>,
+    pub description: Option<String>,
+    pub exchange: Option<String>,
+    pub exchange_name: Option<String>,
+    pub exercise_type: Option<String>,
+    pub expiration_day: Option<i64>,
+    pub expiration_month: Option<i64>,
+    pub expiration_year: Option<i64>,
+    pub is_penny_pilot: Option<bool>,
+    pub last_trading_day: Option<i64>,
+    pub multiplier: Option<f64>,
+    pub settlement_type: Option<String>,
+    pub strike_price: Option<f64>,
+    pub underlying: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ForexResponse {
+    pub ssid: Option<i64>,
+    pub symbol: String,
+    pub realtime: Option<bool>,
+    pub quote: Option<QuoteForex>,
+    pub reference: Option<ReferenceForex>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteForex {
+    #[serde(flatten)]
+    pub prices: QuotePrices,
+    pub tick: Option<f64>,
+    pub tick_amount: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceForex {
+    pub description: Option<String>,
+    pub exchange: Option<String>,
+    pub exchange_name: Option<String>,
+    pub is_tradable: Option<bool>,
+    pub market_maker: Option<String>,
+    pub product: Option<String>,
+    pub trading_hours: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FutureResponse {
+    pub ssid: Option<i64>,
+    pub symbol: String,
+    pub realtime: Option<bool>,
+    pub quote: Option<QuoteFuture>,
+    pub reference: Option<ReferenceFuture>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteFuture {
+    #[serde(flatten)]
+    pub prices: QuotePrices,
+    pub future_percent_change: Option<f64>,
+    pub open_interest: Option<i64>,
+    pub settle_time: Option<i64>,
+    pub tick: Option<f64>,
+    pub tick_amount: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceFuture {
+    pub description: Option<String>,
+    pub exchange: Option<String>,
+    pub exchange_name: Option<String>,
+    pub future_active_symbol: Option<String>,
+    pub future_expiration_date: Option<i64>,
+    pub future_is_active: Option<bool>,
+    pub future_multiplier: Option<f64>,
+    pub future_price_format: Option<String>,
+    pub future_settlement_price: Option<f64>,
+    pub future_trading_hours: Option<String>,
+    pub product: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FutureOptionResponse {
+    pub ssid: Option<i64>,
+    pub symbol: String,
+    pub realtime: Option<bool>,
+    pub quote: Option<QuoteFutureOption>,
+    pub reference: Option<ReferenceFutureOption>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteFutureOption {
+    #[serde(flatten)]
+    pub prices: QuotePrices,
+    pub open_interest: Option<i64>,
+    // Misspelled in the Schwab API.
+    pub settlemet_price: Option<f64>,
+    pub tick: Option<f64>,
+    pub tick_amount: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceFutureOption {
+    pub contract_type: Option<String>,
+    pub description: Option<String>,
+    pub exchange: Option<String>,
+    pub exchange_name: Option<String>,
+    pub multiplier: Option<f64>,
+    pub expiration_date: Option<i64>,
+    pub expiration_style: Option<String>,
+    pub strike_price: Option<f64>,
+    pub underlying: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexResponse {
+    pub ssid: Option<i64>,
+    pub symbol: String,
+    pub realtime: Option<bool>,
+    pub quote: Option<QuoteIndex>,
+    pub reference: Option<ReferenceIndex>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteIndex {
+    #[serde(flatten)]
+    pub prices: QuotePrices,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceIndex {
+    pub description: Option<String>,
+    pub exchange: Option<String>,
+    pub exchange_name: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MutualFundResponse {
+    pub asset_sub_type: Option<String>,
+    pub ssid: Option<i64>,
+    pub symbol: String,
+    pub realtime: Option<bool>,
+    pub fundamental: Option<Fundamental>,
+    pub quote: Option<QuoteMutualFund>,
+    pub reference: Option<ReferenceMutualFund>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuoteMutualFund {
+    #[serde(flatten)]
+    pub prices: QuotePrices,
+    #[serde(rename(serialize = "nAV"))]
+    #[serde(rename(deserialize = "nAV"))]
+    pub nav: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceMutualFund {
+    pub cusip: Option<String>,
+    pub description: Option<String>,
+    pub exchange: Option<String>,
+    pub exchange_name: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QuoteResponse, QuoteResponseObject};
+
+    #[test]
+    fn test_quotes_deserialize_by_asset_type() {
+        let response: QuoteResponse = serde_json::from_str(
+            r#"{
+                "VTI": {
+                    "assetMainType": "EQUITY",
+                    "symbol": "VTI",
+                    "quote": { "52WeekHigh": 260.0, "askPrice": 250.1, "lastPrice": 250.0 }
+                },
+                "$SPX": {
+                    "assetMainType": "INDEX",
+                    "symbol": "$SPX",
+                    "quote": { "lastPrice": 5900.5, "netChange": -12.25 }
+                },
+                "VFIAX": {
+                    "assetMainType": "MUTUAL_FUND",
+                    "symbol": "VFIAX",
+                    "quote": { "nAV": 540.12, "lastPrice": 540.12 }
+                },
+                "/ES": { "assetMainType": "SOMETHING_NEW", "symbol": "/ES" },
+                "errors": { "invalidSymbols": ["NOPE"] }
+            }"#,
+        )
+        .unwrap();
+
+        let vti = &response.quotes["VTI"];
+        assert_eq!(vti.ask_price(), Some(250.1));
+        match vti {
+            QuoteResponseObject::Equity(e) => {
+                assert_eq!(e.quote.as_ref().unwrap().prices.week_52_high, Some(260.0))
+            }
+            _ => panic!("expected an equity quote"),
+        }
+        assert_eq!(response.quotes["$SPX"].ask_price(), None);
+        assert_eq!(response.quotes["$SPX"].net_change(), Some(-12.25));
+        match &response.quotes["VFIAX"] {
+            QuoteResponseObject::MutualFund(m) => {
+                assert_eq!(m.quote.as_ref().unwrap().nav, Some(540.12))
+            }
+            _ => panic!("expected a mutual fund quote"),
+        }
+        assert!(matches!(
+            response.quotes["/ES"],
+            QuoteResponseObject::Unknown
+        ));
+        assert_eq!(
+            response.errors.unwrap().invalid_symbols,
+            vec!["NOPE".to_string()]
+        );
+    }
+}
@@ -1 +1,3 @@
 pub mod accounts_and_trading;
+pub mod market_data;
+pub mod streamer;
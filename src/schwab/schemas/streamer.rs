@@ -0,0 +1,475 @@
+use crate::schwab::schemas::{
+    accounts_and_trading::accounts::OrderStatus, market_data::price_history::Candle,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fmt};
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Service {
+    #[serde(rename(serialize = "ADMIN"))]
+    #[serde(rename(deserialize = "ADMIN"))]
+    Admin,
+    #[serde(rename(serialize = "LEVELONE_EQUITIES"))]
+    #[serde(rename(deserialize = "LEVELONE_EQUITIES"))]
+    LevelOneEquities,
+    #[serde(rename(serialize = "CHART_EQUITY"))]
+    #[serde(rename(deserialize = "CHART_EQUITY"))]
+    ChartEquity,
+    #[serde(rename(serialize = "ACCT_ACTIVITY"))]
+    #[serde(rename(deserialize = "ACCT_ACTIVITY"))]
+    AcctActivity,
+    // Any other service, so one Schwab adds doesn't end the session.
+    #[default]
+    #[serde(rename(serialize = "UNKNOWN"))]
+    #[serde(other)]
+    Unknown,
+}
+
+impl fmt::Display for Service {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Unknown => "UNKNOWN",
+                Self::Admin => "ADMIN",
+                Self::LevelOneEquities => "LEVELONE_EQUITIES",
+                Self::ChartEquity => "CHART_EQUITY",
+                Self::AcctActivity => "ACCT_ACTIVITY",
+            }
+        )
+    }
+}
+
+impl Service {
+    // The fields asked for when subscribing, as a comma separated list of field numbers.
+    pub fn fields(&self) -> &'static str {
+        match self {
+            Self::LevelOneEquities => "0,1,2,3,4,5,8,10,11,12,17,18,34,35",
+            Self::ChartEquity => "0,1,2,3,4,5,6,7,8",
+            Self::AcctActivity => "0,1,2,3",
+            Self::Unknown | Self::Admin => "",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    #[serde(rename(serialize = "LOGIN"))]
+    #[serde(rename(deserialize = "LOGIN"))]
+    Login,
+    #[serde(rename(serialize = "LOGOUT"))]
+    #[serde(rename(deserialize = "LOGOUT"))]
+    Logout,
+    // Replaces every subscription to the service with the given keys.
+    #[serde(rename(serialize = "SUBS"))]
+    #[serde(rename(deserialize = "SUBS"))]
+    Subs,
+    // Adds keys to the existing subscription.
+    #[serde(rename(serialize = "ADD"))]
+    #[serde(rename(deserialize = "ADD"))]
+    Add,
+    #[serde(rename(serialize = "UNSUBS"))]
+    #[serde(rename(deserialize = "UNSUBS"))]
+    Unsubs,
+    // Any other command, so one Schwab adds doesn't end the session.
+    #[default]
+    #[serde(rename(serialize = "UNKNOWN"))]
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct StreamerRequests {
+    pub requests: Vec<StreamerRequest>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct StreamerRequest {
+    pub service: Service,
+    pub requestid: String,
+    pub command: Command,
+    #[serde(rename(serialize = "SchwabClientCustomerId"))]
+    #[serde(rename(deserialize = "SchwabClientCustomerId"))]
+    pub customer_id: String,
+    #[serde(rename(serialize = "SchwabClientCorrelId"))]
+    #[serde(rename(deserialize = "SchwabClientCorrelId"))]
+    pub correl_id: String,
+    #[serde(default)]
+    pub parameters: HashMap<String, String>,
+}
+
+// Everything the streamer sends is one of these, with only some of the lists present.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct StreamerMessage {
+    #[serde(default)]
+    pub response: Vec<StreamerResponse>,
+    #[serde(default)]
+    pub data: Vec<StreamerData>,
+    #[serde(default)]
+    pub notify: Vec<Notify>,
+}
+
+// The answer to a request.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct StreamerResponse {
+    pub service: Service,
+    pub command: Command,
+    pub requestid: String,
+    #[serde(default)]
+    pub timestamp: i64,
+    pub content: ResponseContent,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct ResponseContent {
+    // 0 on success.
+    pub code: i64,
+    #[serde(default)]
+    pub msg: String,
+}
+
+// Updates for subscribed keys. Each item holds the key and the fields that changed, keyed by
+// field number.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct StreamerData {
+    pub service: Service,
+    #[serde(default)]
+    pub timestamp: i64,
+    #[serde(default)]
+    pub command: Command,
+    #[serde(default)]
+    pub content: Vec<Value>,
+}
+
+// Heartbeats, or a notice from the streamer such as the connection being closed.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct Notify {
+    pub heartbeat: Option<String>,
+    pub service: Option<Service>,
+    pub content: Option<ResponseContent>,
+}
+
+fn field_f64(content: &Value, field: usize) -> Option<f64> {
+    content[field.to_string()].as_f64()
+}
+
+fn field_i64(content: &Value, field: usize) -> Option<i64> {
+    content[field.to_string()].as_i64()
+}
+
+// The latest level one quote for an equity. Updates only carry the fields that changed, so they
+// are merged into the quote seen so far.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+pub struct LevelOneEquity {
+    pub symbol: String,
+    pub bid_price: Option<f64>,
+    pub ask_price: Option<f64>,
+    pub last_price: Option<f64>,
+    pub bid_size: Option<i64>,
+    pub ask_size: Option<i64>,
+    pub total_volume: Option<i64>,
+    pub high_price: Option<f64>,
+    pub low_price: Option<f64>,
+    pub close_price: Option<f64>,
+    pub open_price: Option<f64>,
+    pub net_change: Option<f64>,
+    // Milliseconds since the epoch.
+    pub quote_time: Option<i64>,
+    pub trade_time: Option<i64>,
+}
+
+impl LevelOneEquity {
+    pub fn new(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn merge(&mut self, content: &Value) {
+        let f64_fields = [
+            (1, &mut self.bid_price),
+            (2, &mut self.ask_price),
+            (3, &mut self.last_price),
+            (10, &mut self.high_price),
+            (11, &mut self.low_price),
+            (12, &mut self.close_price),
+            (17, &mut self.open_price),
+            (18, &mut self.net_change),
+        ];
+        for (field, value) in f64_fields {
+            if let Some(v) = field_f64(content, field) {
+                *value = Some(v);
+            }
+        }
+        let i64_fields = [
+            (4, &mut self.bid_size),
+            (5, &mut self.ask_size),
+            (8, &mut self.total_volume),
+            (34, &mut self.quote_time),
+            (35, &mut self.trade_time),
+        ];
+        for (field, value) in i64_fields {
+            if let Some(v) = field_i64(content, field) {
+                *value = Some(v);
+            }
+        }
+    }
+
+    // The price to value the equity at: the mid point of the spread when both sides are quoted,
+    // otherwise the last trade.
+    pub fn price(&self) -> Option<f64> {
+        match (self.bid_price, self.ask_price) {
+            (Some(bid), Some(ask)) if bid > 0.0 && ask > 0.0 => Some((bid + ask) / 2.0),
+            _ => self.last_price,
+        }
+    }
+}
+
+// A one minute candle. Unlike quotes, every update carries all of the fields.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+pub struct ChartEquity {
+    pub symbol: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: i64,
+    pub sequence: i64,
+    // Milliseconds since the epoch of the start of the minute.
+    pub chart_time: i64,
+    pub chart_day: i64,
+}
+
+impl ChartEquity {
+    pub fn from_content(content: &Value) -> Option<Self> {
+        Some(Self {
+            symbol: content["key"].as_str()?.to_string(),
+            open: field_f64(content, 1)?,
+            high: field_f64(content, 2)?,
+            low: field_f64(content, 3)?,
+            close: field_f64(content, 4)?,
+            volume: field_f64(content, 5)? as i64,
+            sequence: field_i64(content, 6).unwrap_or_default(),
+            chart_time: field_i64(content, 7)?,
+            chart_day: field_i64(content, 8).unwrap_or_default(),
+        })
+    }
+
+    pub fn candle(&self) -> Candle {
+        Candle {
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            volume: self.volume,
+            datetime: self.chart_time,
+        }
+    }
+}
+
+// Account activity is subscribed to with this key rather than with account numbers; every account
+// the user has streams under it.
+pub const ACCOUNT_ACTIVITY_KEY: &str = "Account Activity";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActivityType {
+    // Sent once when the subscription starts.
+    Subscribed,
+    OrderEntryRequest,
+    OrderActivation,
+    OrderPartialFill,
+    OrderFill,
+    OrderCancelRequest,
+    OrderCancelReplaceRequest,
+    // The order was canceled, also called UROUT.
+    OrderCancel,
+    OrderRejection,
+    TooLateToCancel,
+    BrokenTrade,
+    ManualExecution,
+    Unknown(String),
+}
+
+impl From<&str> for ActivityType {
+    fn from(message_type: &str) -> Self {
+        match message_type {
+            "SUBSCRIBED" => Self::Subscribed,
+            "OrderEntryRequest" => Self::OrderEntryRequest,
+            "OrderActivation" => Self::OrderActivation,
+            "OrderPartialFill" => Self::OrderPartialFill,
+            "OrderFill" => Self::OrderFill,
+            "OrderCancelRequest" => Self::OrderCancelRequest,
+            "OrderCancelReplaceRequest" => Self::OrderCancelReplaceRequest,
+            "OrderCancel" | "UROUT" => Self::OrderCancel,
+            "OrderRejection" => Self::OrderRejection,
+            "TooLateToCancel" => Self::TooLateToCancel,
+            "BrokenTrade" => Self::BrokenTrade,
+            "ManualExecution" => Self::ManualExecution,
+            other => Self::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ActivityType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Subscribed => "SUBSCRIBED",
+                Self::OrderEntryRequest => "OrderEntryRequest",
+                Self::OrderActivation => "OrderActivation",
+                Self::OrderPartialFill => "OrderPartialFill",
+                Self::OrderFill => "OrderFill",
+                Self::OrderCancelRequest => "OrderCancelRequest",
+                Self::OrderCancelReplaceRequest => "OrderCancelReplaceRequest",
+                Self::OrderCancel => "OrderCancel",
+                Self::OrderRejection => "OrderRejection",
+                Self::TooLateToCancel => "TooLateToCancel",
+                Self::BrokenTrade => "BrokenTrade",
+                Self::ManualExecution => "ManualExecution",
+                Self::Unknown(message_type) => message_type,
+            }
+        )
+    }
+}
+
+// Something that happened to an order in one of the user's accounts. Field 3 of the message holds
+// the details as json; the ones the trader uses are pulled out and the rest kept in `data`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountActivity {
+    pub account_number: String,
+    pub activity_type: ActivityType,
+    pub order_id: Option<i64>,
+    pub symbol: Option<String>,
+    // For fills, the quantity filled by this execution.
+    pub quantity: Option<f64>,
+    pub price: Option<f64>,
+    pub data: Value,
+}
+
+impl AccountActivity {
+    pub fn from_content(content: &Value) -> Option<Self> {
+        let data = content["3"]
+            .as_str()
+            .and_then(|d| serde_json::from_str(d).ok())
+            .unwrap_or(Value::Null);
+        let find = |keys: &[&str]| {
+            keys.iter()
+                .map(|k| &data[*k])
+                .find(|v| !v.is_null())
+                .cloned()
+        };
+        Some(Self {
+            account_number: content["1"].as_str()?.to_string(),
+            activity_type: ActivityType::from(content["2"].as_str()?),
+            order_id: find(&["orderId", "SchwabOrderID"]).and_then(|v| match v {
+                Value::String(s) => s.parse().ok(),
+                v => v.as_i64(),
+            }),
+            symbol: find(&["symbol", "Symbol"]).and_then(|v| v.as_str().map(|s| s.to_string())),
+            quantity: find(&["quantity", "filledQuantity", "ExecutionQuantity"])
+                .and_then(|v| v.as_f64()),
+            price: find(&["price", "executionPrice", "ExecutionPrice"]).and_then(|v| v.as_f64()),
+            data,
+        })
+    }
+
+    // The state the order is in after this activity, for the activities that change it.
+    pub fn order_status(&self) -> Option<OrderStatus> {
+        match self.activity_type {
+            ActivityType::OrderEntryRequest => Some(OrderStatus::Accepted),
+            ActivityType::OrderActivation | ActivityType::OrderPartialFill => {
+                Some(OrderStatus::Working)
+            }
+            ActivityType::OrderFill => Some(OrderStatus::Filled),
+            ActivityType::OrderCancel => Some(OrderStatus::Canceled),
+            ActivityType::OrderRejection => Some(OrderStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AccountActivity, ActivityType, ChartEquity, Command, LevelOneEquity, Service,
+        StreamerMessage,
+    };
+    use crate::schwab::schemas::accounts_and_trading::accounts::OrderStatus;
+    use serde_json::json;
+
+    #[test]
+    fn test_level_one_updates_are_merged() {
+        let message: StreamerMessage = serde_json::from_value(json!({"data": [{
+            "service": "LEVELONE_EQUITIES",
+            "timestamp": 1736173800000i64,
+            "command": "SUBS",
+            "content": [
+                {"key": "VTI", "1": 229.5, "2": 230.1, "3": 230.0, "8": 1200},
+                {"key": "VTI", "2": 230.5}
+            ]
+        }]}))
+        .unwrap();
+
+        let mut quote = LevelOneEquity::new("VTI");
+        message.data[0].content.iter().for_each(|c| quote.merge(c));
+        assert_eq!(quote.bid_price, Some(229.5));
+        assert_eq!(quote.ask_price, Some(230.5));
+        assert_eq!(quote.total_volume, Some(1200));
+        assert_eq!(quote.price(), Some(230.0));
+    }
+
+    #[test]
+    fn test_chart_content_is_a_candle() {
+        let chart = ChartEquity::from_content(&json!({
+            "key": "VTI", "seq": 1, "1": 230.0, "2": 230.5, "3": 229.5, "4": 230.2,
+            "5": 1500.0, "6": 12, "7": 1736173800000i64, "8": 20094
+        }))
+        .unwrap();
+        assert_eq!(chart.candle().close, 230.2);
+        assert_eq!(chart.candle().volume, 1500);
+        assert!(ChartEquity::from_content(&json!({"key": "VTI"})).is_none());
+    }
+
+    #[test]
+    fn test_account_activity_is_typed() {
+        let fill = AccountActivity::from_content(&json!({
+            "seq": 3, "key": "Account Activity", "1": "12345678", "2": "OrderFill",
+            "3": r#"{"orderId": "2000000001", "symbol": "VTI", "quantity": 8, "price": 250.1}"#
+        }))
+        .unwrap();
+        assert_eq!(fill.activity_type, ActivityType::OrderFill);
+        assert_eq!(fill.order_id, Some(2000000001));
+        assert_eq!(fill.symbol.as_deref(), Some("VTI"));
+        assert_eq!(fill.quantity, Some(8.0));
+        assert_eq!(fill.order_status(), Some(OrderStatus::Filled));
+
+        let other = AccountActivity::from_content(&json!({
+            "key": "Account Activity", "1": "12345678", "2": "OrderAmended", "3": ""
+        }))
+        .unwrap();
+        assert_eq!(
+            other.activity_type,
+            ActivityType::Unknown("OrderAmended".to_string())
+        );
+        assert_eq!(other.order_id, None);
+        assert_eq!(other.order_status(), None);
+    }
+
+    #[test]
+    fn test_unknown_services_and_commands_are_read() {
+        let message: StreamerMessage = serde_json::from_value(json!({
+            "response": [{
+                "service": "LEVELONE_FUTURES", "command": "VIEW", "requestid": "7",
+                "content": {"code": 0, "msg": "ok"}
+            }]
+        }))
+        .unwrap();
+        assert_eq!(message.response[0].service, Service::Unknown);
+        assert_eq!(message.response[0].command, Command::Unknown);
+    }
+}
@@ -0,0 +1,397 @@
+use crate::{
+    Error,
+    schwab::{
+        client::SchwabClient,
+        retry::RetryPolicy,
+        schemas::{
+            accounts_and_trading::user_preference::StreamerInfo,
+            streamer::{
+                AccountActivity, ActivityType, ChartEquity, Command, LevelOneEquity, Service,
+                StreamerMessage, StreamerRequest, StreamerRequests,
+            },
+        },
+    },
+};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream, connect_async, tungstenite::protocol::Message,
+};
+use tokio_util::sync::CancellationToken;
+
+type Socket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+const EVENT_CAPACITY: usize = 1024;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct StreamerConfig {
+    // Schwab sends a heartbeat every few seconds, so a connection that stays silent for longer
+    // than this is considered dead and replaced.
+    pub heartbeat_timeout_secs: u64,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for StreamerConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_timeout_secs: 30,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 60_000,
+        }
+    }
+}
+
+impl StreamerConfig {
+    fn reconnect_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: u32::MAX,
+            initial_backoff_ms: self.initial_backoff_ms,
+            max_backoff_ms: self.max_backoff_ms,
+            ..RetryPolicy::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    // Logged in, with every subscription sent again.
+    Connected,
+    Disconnected(String),
+    Quote(LevelOneEquity),
+    Chart(ChartEquity),
+    AccountActivity(AccountActivity),
+}
+
+#[derive(Debug)]
+struct Subscription {
+    service: Service,
+    symbols: Vec<String>,
+}
+
+// A connection to the Schwab streamer that is kept open in the background. It logs in with the
+// client's access token, replays the subscriptions whenever it has to reconnect, and publishes
+// what it receives as events. The latest quote for each symbol is also kept in a watch channel.
+pub struct Streamer {
+    subscriptions: mpsc::UnboundedSender<Subscription>,
+    events: broadcast::Sender<StreamEvent>,
+    quotes: watch::Receiver<HashMap<String, LevelOneEquity>>,
+    cancel_token: CancellationToken,
+}
+
+impl Streamer {
+    pub fn start(client: SchwabClient, config: StreamerConfig) -> Self {
+        let (subscriptions, subscription_receiver) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let (quotes_sender, quotes) = watch::channel(HashMap::new());
+        let cancel_token = CancellationToken::new();
+
+        tokio::spawn({
+            let connection = Connection {
+                client,
+                config,
+                subscription_receiver,
+                subscriptions: HashMap::new(),
+                events: events.clone(),
+                quotes: quotes_sender,
+            };
+            let cancel_token = cancel_token.clone();
+            async move { connection.run(cancel_token).await }
+        });
+
+        Self {
+            subscriptions,
+            events,
+            quotes,
+            cancel_token,
+        }
+    }
+
+    pub fn events(&self) -> broadcast::Receiver<StreamEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn quotes(&self) -> watch::Receiver<HashMap<String, LevelOneEquity>> {
+        self.quotes.clone()
+    }
+
+    // Adds the symbols to the service's subscription. They stay subscribed across reconnects.
+    pub fn subscribe(&self, service: Service, symbols: &[String]) -> Result<(), Error> {
+        self.subscriptions
+            .send(Subscription {
+                service,
+                symbols: symbols.to_vec(),
+            })
+            .map_err(|_| "The streamer is no longer running".into())
+    }
+
+    pub fn stop(&self) {
+        self.cancel_token.cancel();
+    }
+}
+
+impl Drop for Streamer {
+    fn drop(&mut self) {
+        self.cancel_token.cancel();
+    }
+}
+
+// Numbers the requests of one login session.
+struct Requests {
+    info: StreamerInfo,
+    next_id: u64,
+}
+
+impl Requests {
+    fn request(
+        &mut self,
+        service: Service,
+        command: Command,
+        parameters: &[(&str, &str)],
+    ) -> Result<Message, Error> {
+        let request = StreamerRequest {
+            service,
+            requestid: self.next_id.to_string(),
+            command,
+            customer_id: self.info.schwab_client_customer_id.clone(),
+            correl_id: self.info.schwab_client_correl_id.clone(),
+            parameters: parameters
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        };
+        self.next_id += 1;
+        Ok(Message::text(serde_json::to_string(&StreamerRequests {
+            requests: vec![request],
+        })?))
+    }
+
+    fn login(&mut self, access_token: &str) -> Result<Message, Error> {
+        let channel = self.info.schwab_client_channel.clone();
+        let function_id = self.info.schwab_client_function_id.clone();
+        self.request(
+            Service::Admin,
+            Command::Login,
+            &[
+                ("Authorization", access_token),
+                ("SchwabClientChannel", &channel),
+                ("SchwabClientFunctionId", &function_id),
+            ],
+        )
+    }
+
+    fn subscribe(
+        &mut self,
+        service: Service,
+        command: Command,
+        symbols: &[&String],
+    ) -> Result<Message, Error> {
+        let keys = symbols
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<&str>>()
+            .join(",");
+        self.request(
+            service,
+            command,
+            &[("keys", &keys), ("fields", service.fields())],
+        )
+    }
+}
+
+struct Connection {
+    client: SchwabClient,
+    config: StreamerConfig,
+    subscription_receiver: mpsc::UnboundedReceiver<Subscription>,
+    subscriptions: HashMap<Service, BTreeSet<String>>,
+    events: broadcast::Sender<StreamEvent>,
+    quotes: watch::Sender<HashMap<String, LevelOneEquity>>,
+}
+
+impl Connection {
+    async fn run(mut self, cancel_token: CancellationToken) {
+        let policy = self.config.reconnect_policy();
+        let mut attempt = 0;
+        loop {
+            let result = tokio::select! {
+                _ = cancel_token.cancelled() => return,
+                result = self.session(&mut attempt) => result,
+            };
+            let e = match result {
+                // The streamer was dropped.
+                Ok(()) => return,
+                Err(e) => e,
+            };
+
+            let delay = policy.backoff(attempt);
+            attempt = attempt.saturating_add(1);
+            log::warn!(
+                "Streamer disconnected: '{}', reconnecting in {:?}",
+                e,
+                delay
+            );
+            let _ = self.events.send(StreamEvent::Disconnected(e.to_string()));
+            tokio::select! {
+                _ = cancel_token.cancelled() => return,
+                _ = tokio::time::sleep(delay) => (),
+            }
+        }
+    }
+
+    async fn next_message(
+        socket: &mut Socket,
+        heartbeat_timeout: std::time::Duration,
+    ) -> Result<StreamerMessage, Error> {
+        loop {
+            match tokio::time::timeout(heartbeat_timeout, socket.next()).await {
+                Err(_) => return Err("No heartbeat from the streamer".into()),
+                Ok(None) | Ok(Some(Ok(Message::Close(_)))) => {
+                    return Err("The streamer closed the connection".into());
+                }
+                Ok(Some(Err(e))) => return Err(e.into()),
+                Ok(Some(Ok(Message::Text(text)))) => return Ok(serde_json::from_str(&text)?),
+                // Pings are answered by the socket itself.
+                Ok(Some(Ok(_))) => (),
+            }
+        }
+    }
+
+    // One login session. Returns Ok only when the streamer was dropped and the session logged out.
+    async fn session(&mut self, attempt: &mut u32) -> Result<(), Error> {
+        let info = self
+            .client
+            .get_user_preference()
+            .await?
+            .streamer_info
+            .into_iter()
+            .next()
+            .ok_or("No streamer info in the user preferences")?;
+        let (mut socket, _) = connect_async(info.streamer_socket_url.as_str()).await?;
+        let mut requests = Requests { info, next_id: 0 };
+        let heartbeat_timeout = std::time::Duration::from_secs(self.config.heartbeat_timeout_secs);
+
+        socket
+            .send(requests.login(&self.client.access_token().await?)?)
+            .await?;
+        loop {
+            let message = Self::next_message(&mut socket, heartbeat_timeout).await?;
+            if let Some(login) = message
+                .response
+                .iter()
+                .find(|r| r.service == Service::Admin && r.command == Command::Login)
+            {
+                if login.content.code != 0 {
+                    return Err(format!("Streamer login failed: {}", login.content.msg).into());
+                }
+                break;
+            }
+        }
+        log::info!("Logged in to the streamer");
+        *attempt = 0;
+        let _ = self.events.send(StreamEvent::Connected);
+
+        for (service, symbols) in self.subscriptions.iter() {
+            if !symbols.is_empty() {
+                let symbols: Vec<&String> = symbols.iter().collect();
+                socket
+                    .send(requests.subscribe(*service, Command::Subs, &symbols)?)
+                    .await?;
+            }
+        }
+
+        loop {
+            tokio::select! {
+                subscription = self.subscription_receiver.recv() => match subscription {
+                    Some(subscription) => {
+                        let subscribed = self.subscriptions.entry(subscription.service).or_default();
+                        // The first keys for a service are a new subscription, later ones are
+                        // added to it.
+                        let command = match subscribed.is_empty() {
+                            true => Command::Subs,
+                            false => Command::Add,
+                        };
+                        let added: Vec<&String> = subscription
+                            .symbols
+                            .iter()
+                            .filter(|s| subscribed.insert(s.to_string()))
+                            .collect();
+                        if !added.is_empty() {
+                            socket
+                                .send(requests.subscribe(subscription.service, command, &added)?)
+                                .await?;
+                        }
+                    }
+                    None => {
+                        socket
+                            .send(requests.request(Service::Admin, Command::Logout, &[])?)
+                            .await?;
+                        let _ = socket.close(None).await;
+                        return Ok(());
+                    }
+                },
+                message = Self::next_message(&mut socket, heartbeat_timeout) => self.publish(message?)?,
+            }
+        }
+    }
+
+    fn publish(&self, message: StreamerMessage) -> Result<(), Error> {
+        for response in message.response.iter() {
+            if response.content.code != 0 {
+                log::warn!(
+                    "Streamer rejected {:?} for {}: {}",
+                    response.command,
+                    response.service,
+                    response.content.msg
+                );
+            }
+        }
+        for notify in message.notify.iter() {
+            // Anything other than a heartbeat is the streamer ending the session.
+            if let Some(content) = notify.content.as_ref()
+                && notify.heartbeat.is_none()
+            {
+                return Err(format!("The streamer ended the session: {}", content.msg).into());
+            }
+        }
+
+        for data in message.data.iter() {
+            for content in data.content.iter() {
+                match data.service {
+                    Service::LevelOneEquities => {
+                        let Some(symbol) = content["key"].as_str() else {
+                            continue;
+                        };
+                        let mut quote = None;
+                        self.quotes.send_modify(|quotes| {
+                            let q = quotes
+                                .entry(symbol.to_string())
+                                .or_insert_with(|| LevelOneEquity::new(symbol));
+                            q.merge(content);
+                            quote = Some(q.clone());
+                        });
+                        if let Some(quote) = quote {
+                            let _ = self.events.send(StreamEvent::Quote(quote));
+                        }
+                    }
+                    Service::ChartEquity => {
+                        if let Some(chart) = ChartEquity::from_content(content) {
+                            let _ = self.events.send(StreamEvent::Chart(chart));
+                        }
+                    }
+                    Service::AcctActivity => {
+                        if let Some(activity) = AccountActivity::from_content(content)
+                            && activity.activity_type != ActivityType::Subscribed
+                        {
+                            let _ = self.events.send(StreamEvent::AccountActivity(activity));
+                        }
+                    }
+                    Service::Admin | Service::Unknown => (),
+                }
+            }
+        }
+        Ok(())
+    }
+}
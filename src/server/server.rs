@@ -1,6 +1,9 @@
 use crate::{
     oauth::token::OauthManager,
-    schwab::account_manager::{AccountData, AccountManager},
+    schwab::{
+        account_manager::{AccountData, AccountManager},
+        rate_limiter::{Api, RateLimiter},
+    },
     server::web_resources::files::{css, html},
 };
 use http_body_util::Full;
@@ -71,6 +74,7 @@ pub async fn run_server(
     let tls_acceptor = TlsAcceptor::from(Arc::new(server_config));
     let renderer = html::Renderer::new()?;
     let account_data_watcher = account_manager.lock().await.account_data_watcher();
+    let rate_limiter = account_manager.lock().await.rate_limiter();
     loop {
         tokio::select! {
             _ = cancel_token.cancelled() => return Ok(()),
@@ -84,10 +88,11 @@ pub async fn run_server(
                                     let om = oauth_manager.clone();
                                     let renderer = renderer.clone();
                                     let account_data_watcher = account_data_watcher.clone();
+                                    let rate_limiter = rate_limiter.clone();
 
                                     async move {
                                         if let Err(err) = hyper::server::conn::http2::Builder::new(TokioExecutor)
-                                            .serve_connection(io, Svc::new(om, renderer, account_data_watcher))
+                                            .serve_connection(io, Svc::new(om, renderer, account_data_watcher, rate_limiter))
                                             .await {
                                                 log::warn!("Error serving connection: {}", err);
                                         }
@@ -109,6 +114,7 @@ struct Svc {
     om: std::sync::Arc<tokio::sync::Mutex<OauthManager>>,
     renderer: html::Renderer,
     account_data_watcher: watch::Receiver<AccountData>,
+    rate_limiter: std::sync::Arc<RateLimiter>,
 }
 
 impl Svc {
@@ -116,11 +122,13 @@ impl Svc {
         om: std::sync::Arc<tokio::sync::Mutex<OauthManager>>,
         renderer: html::Renderer,
         account_data_watcher: watch::Receiver<AccountData>,
+        rate_limiter: std::sync::Arc<RateLimiter>,
     ) -> Self {
         Self {
             om,
             renderer,
             account_data_watcher,
+            rate_limiter,
         }
     }
 }
@@ -150,6 +158,23 @@ impl hyper::service::Service<Request<Incoming>> for Svc {
                                 percentage_investments: account_data
                                     .investment_account_state_percent
                                     .clone(),
+                                paper_trading: account_data.paper_trading,
+                                account_nickname: account_data.account_nickname.clone(),
+                                market_overview: account_data.market_overview.clone(),
+                                api_usage: [
+                                    ("Trader API", Api::Trader),
+                                    ("Market Data API", Api::MarketData),
+                                ]
+                                .into_iter()
+                                .map(|(name, api)| {
+                                    let usage = svc.rate_limiter.usage(api);
+                                    html::ApiUsage {
+                                        name: name.to_string(),
+                                        used: usage.used,
+                                        limit: usage.limit,
+                                    }
+                                })
+                                .collect(),
                             }
                         })?)));
                     } else {
@@ -12,13 +12,42 @@ pub mod html {
         pub oauth_return_message: String,
     }
 
+    #[derive(Clone, Serialize)]
+    pub struct SecurityName {
+        pub symbol: String,
+        pub name: Option<String>,
+    }
+
     #[derive(Clone, Serialize)]
     pub struct InvestmentCollectionPercent {
         pub name: String,
+        pub securities: Vec<SecurityName>,
         pub target_investment: f64,
         pub actual_investment: f64,
     }
 
+    #[derive(Clone, Serialize)]
+    pub struct Mover {
+        pub symbol: String,
+        pub description: Option<String>,
+        pub last_price: Option<f64>,
+        pub percent_change: Option<f64>,
+        pub volume: Option<i64>,
+    }
+
+    #[derive(Clone, Serialize)]
+    pub struct IndexMovers {
+        pub name: String,
+        pub movers: Vec<Mover>,
+    }
+
+    #[derive(Clone, Serialize)]
+    pub struct ApiUsage {
+        pub name: String,
+        pub used: u32,
+        pub limit: u32,
+    }
+
     #[derive(Serialize)]
     pub struct Root {
         pub account_value: f64,
@@ -28,6 +57,10 @@ pub mod html {
         pub total_day_change: f64,
         pub total_profit_loss: f64,
         pub percentage_investments: Vec<InvestmentCollectionPercent>,
+        pub paper_trading: bool,
+        pub account_nickname: Option<String>,
+        pub market_overview: Vec<IndexMovers>,
+        pub api_usage: Vec<ApiUsage>,
     }
 
     #[derive(Clone)]
@@ -0,0 +1,294 @@
+mod common;
+
+use schwab_auto_trader::{
+    mock::streamer::MockStreamer,
+    schwab::{
+        account_manager::AccountManager,
+        endpoints::Endpoints,
+        schemas::{
+            accounts_and_trading::accounts::OrderStatus,
+            streamer::{ACCOUNT_ACTIVITY_KEY, Service},
+        },
+        streamer::StreamerConfig,
+    },
+};
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_account_data_from_mock_server() {
+    let server = common::mock_server().await;
+    let dir = tempfile::tempdir().unwrap();
+    let om = common::authorized_oauth_manager(&server, dir.path(), 1800);
+
+    let mut am = AccountManager::new(
+        common::trading_config(),
+        Endpoints::new(&server.base_url()),
+        om,
+    )
+    .unwrap();
+    let mut watcher = am.account_data_watcher();
+    am.init(Duration::from_millis(50)).await.unwrap();
+
+    let account_data = tokio::time::timeout(
+        Duration::from_secs(10),
+        watcher.wait_for(|ad| ad.total_account_value > 0.0),
+    )
+    .await
+    .unwrap()
+    .unwrap()
+    .clone();
+
+    assert_eq!(account_data.total_account_value, 8700.0);
+    assert_eq!(account_data.total_cash_balance, 5000.0);
+    assert_eq!(account_data.target_cash_balance, 1000.0);
+    assert_eq!(account_data.total_market_value, 3700.0);
+    assert_eq!(account_data.total_day_change, 8.5);
+    assert_eq!(account_data.total_profit_loss, 300.0);
+    assert!(!account_data.paper_trading);
+    assert_eq!(account_data.account_nickname.as_deref(), Some("Retirement"));
+
+    let us = account_data
+        .investment_account_state_percent
+        .iter()
+        .find(|i| i.name == "us")
+        .unwrap();
+    assert_eq!(us.target_investment, 60.0);
+    assert_eq!(us.actual_investment, 67.57);
+    assert_eq!(us.securities[0].symbol, "VTI");
+    assert_eq!(
+        us.securities[0].name.as_deref(),
+        Some("Vanguard Total Stock Market ETF")
+    );
+
+    assert!(
+        server
+            .requests()
+            .iter()
+            .any(|r| r.starts_with("GET /trader/v1/accounts/accountNumbers"))
+    );
+}
+
+#[tokio::test]
+async fn test_untradable_collection_fails_startup() {
+    let server = common::mock_server().await;
+    let dir = tempfile::tempdir().unwrap();
+    let om = common::authorized_oauth_manager(&server, dir.path(), 1800);
+    let mut trading_config = common::trading_config();
+    trading_config.trading_collections[1].collection = vec!["NOTASYMBOL".to_string()];
+
+    let mut am =
+        AccountManager::new(trading_config, Endpoints::new(&server.base_url()), om).unwrap();
+    let e = am.init(Duration::from_millis(50)).await.unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "'NOTASYMBOL' in collection 'intl' is not a known symbol"
+    );
+}
+
+#[tokio::test]
+async fn test_trades_wait_for_placed_orders_to_settle() {
+    let server = common::mock_server().await;
+    let dir = tempfile::tempdir().unwrap();
+    let om = common::authorized_oauth_manager(&server, dir.path(), 1800);
+
+    let mut am = AccountManager::new(
+        common::trading_config(),
+        Endpoints::new(&server.base_url()),
+        om,
+    )
+    .unwrap();
+    am.init(Duration::from_millis(20)).await.unwrap();
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while server.placed_orders().len() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    // The mock account never reflects the fills, so no further rounds may trade.
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let placed_orders = server.placed_orders();
+    assert_eq!(placed_orders.len(), 2);
+
+    let legs: Vec<(String, f64)> = placed_orders
+        .iter()
+        .map(|o| {
+            let leg = &o["orderLegCollection"][0];
+            (
+                leg["instrument"]["symbol"].as_str().unwrap().to_string(),
+                leg["quantity"].as_f64().unwrap(),
+            )
+        })
+        .collect();
+    // 4000 to invest: 2120 into VTI at an ask of 250.1 and 1880 into VXUS at 60.05
+    assert!(legs.contains(&("VTI".to_string(), 8.0)));
+    assert!(legs.contains(&("VXUS".to_string(), 31.0)));
+}
+
+#[tokio::test]
+async fn test_orders_rejected_in_preview_are_not_placed() {
+    let server = common::mock_server().await;
+    server.set_rejected_symbols(&["VXUS"]);
+    let dir = tempfile::tempdir().unwrap();
+    let om = common::authorized_oauth_manager(&server, dir.path(), 1800);
+
+    let mut am = AccountManager::new(
+        common::trading_config(),
+        Endpoints::new(&server.base_url()),
+        om,
+    )
+    .unwrap();
+    am.init(Duration::from_millis(20)).await.unwrap();
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while server.placed_orders().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let placed_orders = server.placed_orders();
+    assert_eq!(placed_orders.len(), 1);
+    assert_eq!(
+        placed_orders[0]["orderLegCollection"][0]["instrument"]["symbol"],
+        "VTI"
+    );
+    assert!(
+        server
+            .requests()
+            .iter()
+            .any(|r| r.starts_with("POST") && r.ends_with("/previewOrder"))
+    );
+}
+
+#[tokio::test]
+async fn test_no_trades_while_market_is_closed() {
+    let server = common::mock_server().await;
+    server.set_market_closed(true);
+    let dir = tempfile::tempdir().unwrap();
+    let om = common::authorized_oauth_manager(&server, dir.path(), 1800);
+
+    let mut am = AccountManager::new(
+        common::trading_config(),
+        Endpoints::new(&server.base_url()),
+        om,
+    )
+    .unwrap();
+    let mut watcher = am.account_data_watcher();
+    am.init(Duration::from_millis(20)).await.unwrap();
+
+    // The account is still refreshed for the dashboard.
+    tokio::time::timeout(Duration::from_secs(10), watcher.changed())
+        .await
+        .unwrap()
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    assert!(server.placed_orders().is_empty());
+    assert!(
+        server
+            .requests()
+            .iter()
+            .any(|r| r.starts_with("GET /marketdata/v1/markets?markets=equity"))
+    );
+}
+
+#[tokio::test]
+async fn test_streamed_fill_refreshes_account_immediately() {
+    let server = common::mock_server().await;
+    server.set_hold_orders(true);
+    let mock_streamer =
+        MockStreamer::start("127.0.0.1:0".parse().unwrap(), Duration::from_millis(100))
+            .await
+            .unwrap();
+    server.set_streamer_url(&mock_streamer.url());
+    let dir = tempfile::tempdir().unwrap();
+    let om = common::authorized_oauth_manager(&server, dir.path(), 1800);
+
+    let mut am = AccountManager::new(
+        common::trading_config(),
+        Endpoints::new(&server.base_url()),
+        om,
+    )
+    .unwrap()
+    .with_streamer(StreamerConfig::default());
+    // Long enough that any refresh within the test comes from the streamer.
+    am.init(Duration::from_secs(60)).await.unwrap();
+
+    let tracked = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let tracked = am.tracked_orders().await;
+            if tracked.len() == 2 && tracked.iter().all(|o| o.order_id.is_some()) {
+                return tracked;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while mock_streamer.subscriptions(Service::AcctActivity) != [ACCOUNT_ACTIVITY_KEY] {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let order_id = |symbol: &str| {
+        tracked
+            .iter()
+            .find(|o| o.symbol == symbol)
+            .and_then(|o| o.order_id)
+            .unwrap()
+    };
+    let account_refreshes = || {
+        server
+            .requests()
+            .iter()
+            .filter(|r| {
+                r.starts_with("GET /trader/v1/accounts/") && r.ends_with("?fields=positions")
+            })
+            .count()
+    };
+    let refreshes = account_refreshes();
+
+    let activity = |account_number: &str, message_type: &str, order_id: i64| {
+        json!({
+            "key": ACCOUNT_ACTIVITY_KEY,
+            "1": account_number,
+            "2": message_type,
+            "3": json!({"orderId": order_id.to_string(), "quantity": 8}).to_string()
+        })
+    };
+    // Activity on another account is ignored.
+    mock_streamer.push(
+        Service::AcctActivity,
+        activity("87654321", "OrderFill", order_id("VXUS")),
+    );
+    mock_streamer.push(
+        Service::AcctActivity,
+        activity(common::ACCOUNT_NUMBER, "OrderFill", order_id("VTI")),
+    );
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while account_refreshes() == refreshes {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let tracked = am.tracked_orders().await;
+    let status = |symbol: &str| {
+        tracked
+            .iter()
+            .find(|o| o.symbol == symbol)
+            .map(|o| o.status.clone())
+            .unwrap()
+    };
+    assert_eq!(status("VTI"), OrderStatus::Filled);
+    assert_ne!(status("VXUS"), OrderStatus::Filled);
+}
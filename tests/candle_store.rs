@@ -0,0 +1,33 @@
+mod common;
+
+use schwab_auto_trader::schwab::{
+    candle_store::CandleStore, client::SchwabClient, endpoints::Endpoints,
+};
+
+#[tokio::test]
+async fn test_sync_only_adds_new_candles() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+    let dir = tempfile::tempdir().unwrap();
+    let store = CandleStore::new(dir.path().to_str().unwrap()).unwrap();
+    let symbols = vec!["VTI".to_string(), "VXUS".to_string(), "NOPE".to_string()];
+
+    assert_eq!(store.sync(&client, &symbols).await, 10);
+    let candles = store.candles("VTI").unwrap();
+    assert_eq!(candles.len(), 5);
+    assert_eq!(candles.last().unwrap().close, 250.0);
+    assert!(candles.windows(2).all(|w| w[0].datetime < w[1].datetime));
+
+    // The second sync starts from the newest stored candle instead of the full history.
+    assert_eq!(store.sync(&client, &symbols).await, 0);
+    assert_eq!(store.candles("VTI").unwrap().len(), 5);
+    let history_requests: Vec<String> = server
+        .requests()
+        .into_iter()
+        .filter(|r| r.contains("/pricehistory?symbol=VTI&"))
+        .collect();
+    assert_eq!(history_requests.len(), 2);
+    assert!(
+        history_requests[1].contains(&format!("startDate={}", candles.last().unwrap().datetime))
+    );
+}
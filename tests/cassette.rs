@@ -0,0 +1,170 @@
+mod common;
+
+use schwab_auto_trader::schwab::{
+    cassette::Cassette,
+    client::SchwabClient,
+    endpoints::Endpoints,
+    schemas::accounts_and_trading::accounts::{OrderRequest, OrderStatus},
+};
+
+// Nothing listens here, so a replaying client that reached the network would fail.
+const UNREACHABLE_BASE_URL: &str = "http://127.0.0.1:9";
+
+#[tokio::test]
+async fn test_recorded_session_is_redacted_and_replays() {
+    let server = common::mock_server().await;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir
+        .path()
+        .join("session.json")
+        .to_str()
+        .unwrap()
+        .to_string();
+    let fixtures = common::fixtures();
+    let account_hash = fixtures.account_numbers[0]["hashValue"].as_str().unwrap();
+
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()))
+        .with_cassette(Cassette::record(&path));
+    let recorded_hash = client.get_account_numbers().await.unwrap()[0]
+        .hash_value
+        .clone();
+    let account = client.get_account(&recorded_hash).await.unwrap();
+    let placed = client
+        .post_order(
+            &recorded_hash,
+            &OrderRequest::new_fill_or_kill_etf(2.0, "VTI".to_string()),
+            &[],
+        )
+        .await
+        .unwrap();
+    drop(server);
+
+    let recording = std::fs::read_to_string(&path).unwrap();
+    // One line per interaction: the account numbers, the account and the order.
+    assert_eq!(recording.lines().count(), 3);
+    for secret in [common::ACCOUNT_NUMBER, account_hash, "mock-access-token"] {
+        assert!(!recording.contains(secret), "'{}' was recorded", secret);
+    }
+
+    let client = SchwabClient::new(common::token(), Endpoints::new(UNREACHABLE_BASE_URL))
+        .with_cassette(Cassette::replay(&path).unwrap());
+    let replayed_hash = client.get_account_numbers().await.unwrap()[0]
+        .hash_value
+        .clone();
+    assert_ne!(replayed_hash, recorded_hash);
+    let replayed_account = client.get_account(&replayed_hash).await.unwrap();
+    assert_eq!(
+        replayed_account.securities_account.unwrap().positions.len(),
+        account.securities_account.unwrap().positions.len()
+    );
+    let replayed_order = client
+        .post_order(
+            &replayed_hash,
+            &OrderRequest::new_fill_or_kill_etf(2.0, "VTI".to_string()),
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(replayed_order.order_id, placed.order_id);
+    assert!(client.get_quotes(&["VTI".to_string()]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_secrets_learned_later_are_redacted_in_earlier_interactions() {
+    let server = common::mock_server().await;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir
+        .path()
+        .join("session.json")
+        .to_str()
+        .unwrap()
+        .to_string();
+    let fixtures = common::fixtures();
+    let account_hash = fixtures.account_numbers[0]["hashValue"].as_str().unwrap();
+
+    // The account is fetched with a hash known from an earlier session, before the account
+    // numbers response that reveals it.
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()))
+        .with_cassette(Cassette::record(&path));
+    client.get_account(account_hash).await.unwrap();
+    client.get_account_numbers().await.unwrap();
+    client.get_account(account_hash).await.unwrap();
+    drop(server);
+
+    let recording = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(recording.lines().count(), 3);
+    for line in recording.lines() {
+        assert!(line.contains("REDACTEDHASH"), "unredacted hash in {}", line);
+        assert!(
+            !line.contains(account_hash),
+            "'{}' was recorded",
+            account_hash
+        );
+    }
+}
+
+// An order placed, replaced by a larger one and then canceled, recorded from the mock server.
+#[tokio::test]
+async fn test_order_lifecycle_cassette() {
+    let client = SchwabClient::new(common::token(), Endpoints::new(UNREACHABLE_BASE_URL))
+        .with_cassette(
+            Cassette::replay(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/cassettes/order_lifecycle.jsonl"
+            ))
+            .unwrap(),
+        );
+    let account_hash = client.get_account_numbers().await.unwrap()[0]
+        .hash_value
+        .clone();
+
+    let placed = client
+        .post_order(
+            &account_hash,
+            &OrderRequest::new_fill_or_kill_etf(2.0, "VTI".to_string()),
+            &[],
+        )
+        .await
+        .unwrap();
+    let order = client
+        .get_order(&account_hash, placed.order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Working);
+
+    let replacement = client
+        .replace_order(
+            &account_hash,
+            placed.order_id,
+            &OrderRequest::new_fill_or_kill_etf(3.0, "VTI".to_string()),
+        )
+        .await
+        .unwrap();
+    let order = client
+        .get_order(&account_hash, placed.order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Replaced);
+    client
+        .cancel_order(&account_hash, replacement.order_id)
+        .await
+        .unwrap();
+
+    // Completed orders are listed as filled, replaced or canceled.
+    let now = chrono::Utc::now();
+    let statuses: Vec<OrderStatus> = client
+        .get_all_orders(now - chrono::Duration::days(1), now, None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|o| o.status)
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            OrderStatus::Filled,
+            OrderStatus::Replaced,
+            OrderStatus::Canceled
+        ]
+    );
+}
@@ -0,0 +1,753 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use schwab_auto_trader::{
+    mock::schwab_api::MockSchwabServer,
+    schwab::{
+        client::{SchwabClient, is_order_not_created},
+        endpoints::Endpoints,
+        error::SchwabApiError,
+        instrument_cache::InstrumentCache,
+        query::{OrderQuery, TransactionQuery},
+        rate_limiter::{Api, BucketUsage, RateLimitConfig},
+        retry::RetryPolicy,
+        schemas::{
+            accounts_and_trading::accounts::{OrderRequest, OrderStatus, TransactionType},
+            market_data::{
+                instruments::{Instrument, InstrumentAssetType, Projection},
+                movers::{MoverIndex, MoverSort},
+            },
+        },
+    },
+};
+
+#[tokio::test]
+async fn test_error_responses_are_typed() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+
+    let e = client.get_account("not-an-account-hash").await.unwrap_err();
+    match e.downcast_ref::<SchwabApiError>() {
+        Some(SchwabApiError::NotFound(api_error)) => {
+            assert_eq!(api_error.message.as_deref(), Some("Account not found"))
+        }
+        _ => panic!("expected a not found error, got {:?}", e),
+    }
+}
+
+#[tokio::test]
+async fn test_post_order_returns_order_id() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+    let account_hash = common::fixtures().account_numbers[0]["hashValue"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let placed = client
+        .post_order(
+            &account_hash,
+            &OrderRequest::new_fill_or_kill_etf(2.0, "VTI".to_string()),
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(placed.order_id, 2000000001);
+    assert_eq!(server.placed_orders().len(), 1);
+}
+
+#[tokio::test]
+async fn test_replace_and_cancel_working_orders() {
+    let server = common::mock_server().await;
+    server.set_hold_orders(true);
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+    let account_hash = common::fixtures().account_numbers[0]["hashValue"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let placed = client
+        .post_order(
+            &account_hash,
+            &OrderRequest::new_fill_or_kill_etf(2.0, "VTI".to_string()),
+            &[],
+        )
+        .await
+        .unwrap();
+    let order = client
+        .get_order(&account_hash, placed.order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Working);
+
+    let replacement = client
+        .replace_order(
+            &account_hash,
+            placed.order_id,
+            &OrderRequest::new_fill_or_kill_etf(3.0, "VTI".to_string()),
+        )
+        .await
+        .unwrap();
+    assert_ne!(replacement.order_id, placed.order_id);
+    assert_eq!(
+        client
+            .get_order(&account_hash, placed.order_id)
+            .await
+            .unwrap()
+            .status,
+        OrderStatus::Replaced
+    );
+
+    let now = chrono::Utc::now();
+    let working = client
+        .get_all_orders(
+            now - chrono::Duration::days(1),
+            now,
+            Some(&OrderStatus::Working),
+        )
+        .await
+        .unwrap();
+    assert_eq!(working.len(), 1);
+    assert_eq!(working[0].order_id, replacement.order_id);
+
+    client
+        .cancel_order(&account_hash, replacement.order_id)
+        .await
+        .unwrap();
+    let e = client
+        .cancel_order(&account_hash, replacement.order_id)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        e.downcast_ref::<SchwabApiError>(),
+        Some(SchwabApiError::Validation(_))
+    ));
+    assert!(
+        client
+            .get_all_orders(
+                now - chrono::Duration::days(1),
+                now,
+                Some(&OrderStatus::Working)
+            )
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_quotes_for_several_symbols_in_one_request() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+
+    let response = client
+        .get_quotes(&["VTI".to_string(), "VXUS".to_string(), "NOPE".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(response.quotes.len(), 2);
+    assert_eq!(response.quotes["VTI"].ask_price(), Some(250.1));
+    assert_eq!(response.quotes["VXUS"].last_price(), Some(60.0));
+    assert_eq!(
+        response.errors.unwrap().invalid_symbols,
+        vec!["NOPE".to_string()]
+    );
+    assert_eq!(
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.contains("/marketdata/"))
+            .count(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_user_preference_has_nicknames_and_streamer_info() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+
+    let preference = client.get_user_preference().await.unwrap();
+    assert_eq!(
+        preference.nickname("12345678").as_deref(),
+        Some("Retirement")
+    );
+    assert_eq!(preference.nickname("87654321"), None);
+    let streamer = &preference.streamer_info[0];
+    assert_eq!(
+        streamer.streamer_socket_url,
+        "wss://streamer-api.schwab.com/ws"
+    );
+    assert_eq!(streamer.schwab_client_channel, "N9");
+    assert_eq!(streamer.schwab_client_function_id, "APIAPP");
+}
+
+#[tokio::test]
+async fn test_instrument_searches() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+    let symbols = |instruments: Vec<Instrument>| {
+        instruments
+            .into_iter()
+            .map(|i| i.symbol)
+            .collect::<Vec<String>>()
+    };
+
+    let instruments = client
+        .get_instruments(&["VTI".to_string(), "NOPE".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(instruments.len(), 1);
+    assert_eq!(instruments[0].name(), "Vanguard Total Stock Market ETF");
+    assert_eq!(instruments[0].asset_type, InstrumentAssetType::Etf);
+    assert_eq!(instruments[0].fundamental, None);
+
+    assert_eq!(
+        symbols(
+            client
+                .search_instruments("VT.*", &Projection::SymbolRegex)
+                .await
+                .unwrap()
+        ),
+        vec!["VTI", "VTSAX"]
+    );
+    assert_eq!(
+        symbols(
+            client
+                .search_instruments("dividend", &Projection::DescSearch)
+                .await
+                .unwrap()
+        ),
+        vec!["SCHD"]
+    );
+    assert_eq!(
+        symbols(
+            client
+                .search_instruments(
+                    "^Vanguard Total (International|Stock Market ETF)",
+                    &Projection::DescRegex
+                )
+                .await
+                .unwrap()
+        ),
+        vec!["VTI", "VXUS"]
+    );
+
+    let fundamentals = client
+        .get_fundamentals(&["SCHD".to_string(), "AAPL".to_string()])
+        .await
+        .unwrap();
+    let fundamental = fundamentals[1].fundamental.as_ref().unwrap();
+    assert_eq!(fundamental.eps_ttm, Some(6.3));
+    assert_eq!(
+        fundamentals[0].fundamental.as_ref().unwrap().dividend_yield,
+        Some(3.59)
+    );
+
+    let apple = client.get_instrument_by_cusip("037833100").await.unwrap();
+    assert_eq!(apple.symbol, "AAPL");
+    assert!(apple.is_equity_or_etf());
+    assert!(client.get_instrument_by_cusip("000000000").await.is_err());
+}
+
+#[tokio::test]
+async fn test_instruments_are_cached_and_validate_collections() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache").join("instruments.json");
+    let symbols = ["VTI", "VXUS", "VTSAX", "NOPE"].map(|s| s.to_string());
+    let instrument_requests = || {
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.contains("/instruments"))
+            .count()
+    };
+
+    let instruments = InstrumentCache::new(path.to_str().unwrap())
+        .unwrap()
+        .instruments(&client, &symbols)
+        .await
+        .unwrap();
+    assert_eq!(instruments.len(), 3);
+    assert_eq!(instrument_requests(), 1);
+
+    // Only the symbol Schwab didn't know is asked for again.
+    let cache = InstrumentCache::new(path.to_str().unwrap()).unwrap();
+    let cached = cache.instruments(&client, &symbols[..3]).await.unwrap();
+    assert_eq!(cached, instruments);
+    assert_eq!(instrument_requests(), 1);
+    cache.instruments(&client, &symbols).await.unwrap();
+    assert_eq!(instrument_requests(), 2);
+    assert!(server.requests().last().unwrap().contains("symbol=NOPE&"));
+
+    let mut trading_config = common::trading_config();
+    trading_config.validate_instruments(&instruments).unwrap();
+
+    trading_config.trading_collections[1]
+        .collection
+        .push("VTSAX".to_string());
+    let e = trading_config
+        .validate_instruments(&instruments)
+        .unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "'VTSAX' in collection 'intl' is a MUTUAL_FUND, not an equity or ETF"
+    );
+
+    trading_config.trading_collections[1].collection = vec!["NOPE".to_string()];
+    let e = trading_config
+        .validate_instruments(&instruments)
+        .unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "'NOPE' in collection 'intl' is not a known symbol"
+    );
+}
+
+#[tokio::test]
+async fn test_movers_by_index_and_sort() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+    let symbols = |index, sort| {
+        let client = client.clone();
+        async move {
+            client
+                .get_movers(&index, &sort, 0)
+                .await
+                .unwrap()
+                .screeners
+                .into_iter()
+                .map(|s| s.symbol)
+                .collect::<Vec<String>>()
+        }
+    };
+
+    assert_eq!(
+        symbols(MoverIndex::Dji, MoverSort::Volume).await,
+        vec!["NVDA", "AAPL", "KO", "JNJ"]
+    );
+    assert_eq!(
+        symbols(MoverIndex::Dji, MoverSort::PercentChangeDown).await,
+        vec!["KO", "JNJ"]
+    );
+    assert_eq!(
+        symbols(MoverIndex::Spx, MoverSort::PercentChangeUp).await,
+        vec!["SMCI"]
+    );
+    assert!(
+        server
+            .requests()
+            .last()
+            .unwrap()
+            .contains("/marketdata/v1/movers/%24SPX?sort=PERCENT_CHANGE_UP&frequency=0")
+    );
+
+    let movers = client
+        .get_movers(&MoverIndex::Compx, &MoverSort::Trades, 5)
+        .await
+        .unwrap();
+    assert_eq!(
+        movers.screeners[0].description.as_deref(),
+        Some("Tesla Inc")
+    );
+    assert_eq!(movers.screeners[0].percent_change(), Some(5.69));
+
+    assert!(
+        client
+            .get_movers(&MoverIndex::OptionAll, &MoverSort::Volume, 0)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_clients_share_rate_limits_per_api() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()))
+        .with_rate_limits(&RateLimitConfig {
+            trader_requests_per_minute: 2,
+            market_data_requests_per_minute: 10,
+        });
+
+    client.clone().get_account_numbers().await.unwrap();
+    client.get_account_numbers().await.unwrap();
+    client.get_quotes(&["VTI".to_string()]).await.unwrap();
+
+    let rate_limiter = client.rate_limiter();
+    assert_eq!(
+        rate_limiter.usage(Api::Trader),
+        BucketUsage { used: 2, limit: 2 }
+    );
+    assert_eq!(
+        rate_limiter.usage(Api::MarketData),
+        BucketUsage { used: 1, limit: 10 }
+    );
+
+    // The trader bucket is empty, so the next call queues until a token is refilled.
+    let queued = tokio::time::timeout(
+        std::time::Duration::from_millis(500),
+        client.get_account_numbers(),
+    )
+    .await;
+    assert!(queued.is_err());
+}
+
+fn retrying_client(server: &MockSchwabServer) -> SchwabClient {
+    SchwabClient::new(common::token(), Endpoints::new(&server.base_url())).with_retry_policy(
+        &RetryPolicy {
+            max_retries: 3,
+            initial_backoff_ms: 10,
+            max_backoff_ms: 50,
+            order_confirmation_ms: 300,
+            order_poll_ms: 50,
+        },
+    )
+}
+
+fn account_hash() -> String {
+    common::fixtures().account_numbers[0]["hashValue"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_transient_get_failures_are_retried() {
+    let server = common::mock_server().await;
+    let client = retrying_client(&server);
+    let path = "/trader/v1/accounts/accountNumbers";
+
+    server.fail_requests("GET", path, 502, 2);
+    client.get_account_numbers().await.unwrap();
+    server.fail_requests("GET", path, 503, 4);
+    assert!(client.get_account_numbers().await.is_err());
+    // 2 failures and a success, then the first try and 3 retries
+    assert_eq!(
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.ends_with(path))
+            .count(),
+        7
+    );
+
+    // Invalid requests are not retried.
+    server.fail_requests("GET", path, 400, 1);
+    assert!(client.get_account_numbers().await.is_err());
+    assert_eq!(
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.ends_with(path))
+            .count(),
+        8
+    );
+}
+
+#[tokio::test]
+async fn test_order_is_placed_again_only_when_schwab_never_handled_it() {
+    let server = common::mock_server().await;
+    let client = retrying_client(&server);
+    let orders_path = format!("/trader/v1/accounts/{}/orders", account_hash());
+    let order_posts = || {
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.starts_with(&format!("POST {}", orders_path)))
+            .count()
+    };
+
+    // Schwab rate limited the order, so it is placed again.
+    let order = OrderRequest::new_fill_or_kill_etf(2.0, "VTI".to_string());
+    server.fail_requests("POST", &orders_path, 429, 1);
+    let placed = client
+        .post_order(&account_hash(), &order, &[])
+        .await
+        .unwrap();
+    assert_eq!(placed.order_id, 2000000001);
+    assert_eq!(server.placed_orders().len(), 1);
+    assert_eq!(order_posts(), 2);
+
+    // The order was created but the response was lost, so it is found instead of placed twice.
+    let order = OrderRequest::new_fill_or_kill_etf(3.0, "VTI".to_string());
+    server.lose_responses("POST", &orders_path, 502, 1);
+    let placed = client
+        .post_order(&account_hash(), &order, &[])
+        .await
+        .unwrap();
+    assert_eq!(placed.order_id, 2000000002);
+    assert_eq!(server.placed_orders().len(), 2);
+    assert_eq!(order_posts(), 3);
+
+    // A 502 may come after the order was handled, so it is never placed again even when it
+    // doesn't show up.
+    let order = OrderRequest::new_fill_or_kill_etf(4.0, "VTI".to_string());
+    server.fail_requests("POST", &orders_path, 502, 1);
+    let e = client
+        .post_order(&account_hash(), &order, &[])
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        e.downcast_ref::<SchwabApiError>(),
+        Some(SchwabApiError::ServerError { status: 502, .. })
+    ));
+    assert!(!is_order_not_created(&e));
+    assert_eq!(server.placed_orders().len(), 2);
+    assert_eq!(order_posts(), 4);
+}
+
+#[tokio::test]
+async fn test_order_is_looked_up_instead_of_placed_again_after_a_503() {
+    let server = common::mock_server().await;
+    let client = retrying_client(&server);
+    let orders_path = format!("/trader/v1/accounts/{}/orders", account_hash());
+    let order_lookups = || {
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.starts_with(&format!("GET {}?", orders_path)))
+            .count()
+    };
+
+    // A gateway may return a 503 after passing the order on, so the orders are checked for it.
+    server.lose_responses("POST", &orders_path, 503, 1);
+    let placed = client
+        .post_order(
+            &account_hash(),
+            &OrderRequest::new_fill_or_kill_etf(2.0, "VTI".to_string()),
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(placed.order_id, 2000000001);
+    assert_eq!(server.placed_orders().len(), 1);
+    assert!(order_lookups() > 0);
+
+    // Even when the order doesn't show up it isn't placed again.
+    server.fail_requests("POST", &orders_path, 503, 1);
+    let e = client
+        .post_order(
+            &account_hash(),
+            &OrderRequest::new_fill_or_kill_etf(3.0, "VTI".to_string()),
+            &[],
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        e.downcast_ref::<SchwabApiError>(),
+        Some(SchwabApiError::ServerError { status: 503, .. })
+    ));
+    assert!(!is_order_not_created(&e));
+    assert_eq!(server.placed_orders().len(), 1);
+    assert_eq!(
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.starts_with(&format!("POST {}", orders_path)))
+            .count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_known_orders_are_not_taken_for_a_lost_order() {
+    let server = common::mock_server().await;
+    let client = retrying_client(&server);
+    let orders_path = format!("/trader/v1/accounts/{}/orders", account_hash());
+    let order = OrderRequest::new_fill_or_kill_etf(2.0, "VTI".to_string());
+
+    // An identical order placed moments earlier is already tracked by the caller.
+    let earlier = client
+        .post_order(&account_hash(), &order, &[])
+        .await
+        .unwrap();
+    server.lose_responses("POST", &orders_path, 502, 1);
+    let placed = client
+        .post_order(&account_hash(), &order, &[earlier.order_id])
+        .await
+        .unwrap();
+    assert_eq!(placed.order_id, earlier.order_id + 1);
+}
+
+#[tokio::test]
+async fn test_order_is_not_retried_when_orders_cant_be_listed() {
+    let server = common::mock_server().await;
+    let client = retrying_client(&server);
+    let orders_path = format!("/trader/v1/accounts/{}/orders", account_hash());
+
+    server.fail_requests("POST", &orders_path, 502, 1);
+    server.fail_requests("GET", &orders_path, 500, 10);
+    let e = client
+        .post_order(
+            &account_hash(),
+            &OrderRequest::new_fill_or_kill_etf(2.0, "VTI".to_string()),
+            &[],
+        )
+        .await
+        .unwrap_err();
+    match e.downcast_ref::<SchwabApiError>() {
+        Some(SchwabApiError::ServerError { status: 502, .. }) => (),
+        _ => panic!("expected the order's server error, got {:?}", e),
+    }
+    assert_eq!(
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.starts_with(&format!("POST {}", orders_path)))
+            .count(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_rejected_token_is_refreshed_and_request_resent() {
+    let server = common::mock_server().await;
+    let dir = tempfile::tempdir().unwrap();
+    let om = common::authorized_oauth_manager(&server, dir.path(), 1800);
+    let client = SchwabClient::new(om, Endpoints::new(&server.base_url()));
+    let path = "/trader/v1/accounts/accountNumbers";
+
+    server.fail_requests("GET", path, 401, 1);
+    // Clones share the token provider, so either one can be used.
+    client.clone().get_account_numbers().await.unwrap();
+    let requests = server.requests();
+    assert_eq!(requests.iter().filter(|r| r.ends_with(path)).count(), 2);
+    assert!(requests.iter().any(|r| r == "POST /v1/oauth/token"));
+
+    // A token that is still rejected after refreshing is reported as unauthorized.
+    server.fail_requests("GET", path, 401, 2);
+    let e = client.get_account_numbers().await.unwrap_err();
+    assert!(matches!(
+        e.downcast_ref::<SchwabApiError>(),
+        Some(SchwabApiError::Unauthorized(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_long_ranges_are_fetched_in_windows() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+    let start = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+
+    let e = client
+        .get_transactions(
+            &account_hash(),
+            &TransactionQuery::new(start, end, &[TransactionType::Trade]),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        e.downcast_ref::<SchwabApiError>(),
+        Some(SchwabApiError::Validation(_))
+    ));
+
+    let transactions = client
+        .get_transaction_history(
+            &account_hash(),
+            &TransactionQuery::new(start, end, &[TransactionType::Trade]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].activity_id, 80000001);
+
+    // The fixture order is entered right on the boundary of two windows, so both return it.
+    let entered = Utc.with_ymd_and_hms(2025, 1, 6, 14, 30, 1).unwrap();
+    let orders = client
+        .get_order_history(
+            &account_hash(),
+            &OrderQuery::new(
+                entered - chrono::Duration::days(60),
+                entered + chrono::Duration::days(30),
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].order_id, 1000000001);
+    assert_eq!(
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.starts_with(&format!(
+                "GET /trader/v1/accounts/{}/orders?",
+                account_hash()
+            )))
+            .count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_transactions_of_several_types_for_one_symbol() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+    let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2025, 12, 31, 0, 0, 0).unwrap();
+    let query = TransactionQuery::new(
+        start,
+        end,
+        &[
+            TransactionType::Trade,
+            TransactionType::DividendOrInterest,
+            TransactionType::ReceiveAndDeliver,
+        ],
+    );
+
+    let all = client
+        .get_transactions(&account_hash(), &query)
+        .await
+        .unwrap();
+    assert_eq!(all.len(), 3);
+
+    let vti = client
+        .get_transactions(&account_hash(), &query.clone().symbol("VTI"))
+        .await
+        .unwrap();
+    assert_eq!(
+        vti.iter().map(|t| t.activity_id).collect::<Vec<i64>>(),
+        vec![80000001, 80000002]
+    );
+    assert_eq!(
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.contains("/transactions?"))
+            .count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_order_history_splits_windows_at_max_results() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+    let entered = Utc.with_ymd_and_hms(2025, 1, 6, 14, 30, 1).unwrap();
+    let query = OrderQuery::new(
+        entered - chrono::Duration::days(1),
+        entered + chrono::Duration::days(1),
+    );
+
+    let orders = client
+        .query_orders(&account_hash(), &query.clone().max_results(1))
+        .await
+        .unwrap();
+    assert_eq!(orders.len(), 1);
+
+    // Every window holding the order is full, so it is split down to the smallest window.
+    let orders = client
+        .get_order_history(&account_hash(), &query.max_results(1))
+        .await
+        .unwrap();
+    assert_eq!(orders.len(), 1);
+    assert!(
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.contains("maxResults=1"))
+            .count()
+            > 2
+    );
+}
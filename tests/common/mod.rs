@@ -0,0 +1,92 @@
+#![allow(dead_code)]
+
+use schwab_auto_trader::{
+    config::{AllocationPercent, TradingCollection, TradingConfig},
+    mock::schwab_api::{Fixtures, MockSchwabServer},
+    oauth::{
+        token::{OauthManager, OauthTokenResponse},
+        token_storage::TokenStorage,
+        utils::oauth_utils,
+    },
+};
+use std::{path::Path, sync::Arc};
+use tokio::sync::Mutex;
+
+pub const ACCOUNT_NUMBER: &str = "12345678";
+
+pub fn fixtures() -> Fixtures {
+    Fixtures::load(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/schwab"
+    ))
+    .unwrap()
+}
+
+pub async fn mock_server() -> MockSchwabServer {
+    MockSchwabServer::start("127.0.0.1:0".parse().unwrap(), fixtures())
+        .await
+        .unwrap()
+}
+
+pub fn token() -> OauthTokenResponse {
+    serde_json::from_value(fixtures().token).unwrap()
+}
+
+pub fn oauth_manager(server: &MockSchwabServer, dir: &Path) -> Arc<Mutex<OauthManager>> {
+    Arc::new(Mutex::new(OauthManager::new(
+        oauth_utils::new_oauth_basic_client(
+            "client-id".to_string(),
+            "client-secret".to_string(),
+            "https://127.0.0.1:8080/oauth".to_string(),
+            &server.base_url(),
+        )
+        .unwrap(),
+        TokenStorage::load(dir.join("token.json").to_str().unwrap().to_string()).unwrap(),
+    )))
+}
+
+// An OauthManager that already holds a token which expires in the given number of seconds.
+pub fn authorized_oauth_manager(
+    server: &MockSchwabServer,
+    dir: &Path,
+    expires_in_secs: i64,
+) -> Arc<Mutex<OauthManager>> {
+    let path = dir.join("token.json").to_str().unwrap().to_string();
+    TokenStorage::load(path)
+        .unwrap()
+        .set_token(
+            &token(),
+            chrono::Utc::now() + chrono::Duration::seconds(expires_in_secs),
+        )
+        .unwrap();
+    oauth_manager(server, dir)
+}
+
+pub fn trading_config() -> TradingConfig {
+    TradingConfig {
+        account_number: ACCOUNT_NUMBER.to_string(),
+        trading_collections: vec![
+            TradingCollection {
+                id: "us".to_string(),
+                collection: vec!["VTI".to_string()],
+            },
+            TradingCollection {
+                id: "intl".to_string(),
+                collection: vec!["VXUS".to_string()],
+            },
+        ],
+        allocations_percent: vec![
+            AllocationPercent {
+                id: "us".to_string(),
+                percent: 60.0,
+            },
+            AllocationPercent {
+                id: "intl".to_string(),
+                percent: 40.0,
+            },
+        ],
+        allocations_amount: vec![],
+        target_cash_balance: 1000.0,
+        ..Default::default()
+    }
+}
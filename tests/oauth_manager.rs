@@ -0,0 +1,73 @@
+mod common;
+
+use oauth2::TokenResponse;
+use schwab_auto_trader::oauth::token::OauthManager;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_authorization_code_exchange() {
+    let server = common::mock_server().await;
+    let dir = tempfile::tempdir().unwrap();
+    let om = common::oauth_manager(&server, dir.path());
+
+    let auth_url = om.lock().await.reset_auth_url();
+    assert!(auth_url.starts_with(&format!("{}/v1/oauth/authorize", server.base_url())));
+    let state = url::Url::parse(&auth_url)
+        .unwrap()
+        .query_pairs()
+        .find(|(k, _)| k == "state")
+        .unwrap()
+        .1
+        .to_string();
+
+    OauthManager::spawn_token_receiver(om.clone(), Duration::from_millis(10)).await;
+    om.lock()
+        .await
+        .token_manager()
+        .send_token("auth-code".to_string(), &state)
+        .unwrap();
+
+    let token = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(Ok(token)) = om.lock().await.get_unexpired_token() {
+                break token;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(token.access_token().secret(), "mock-access-token");
+    assert!(
+        server
+            .requests()
+            .iter()
+            .any(|r| r.starts_with("POST /v1/oauth/token"))
+    );
+}
+
+#[tokio::test]
+async fn test_expiring_token_is_refreshed() {
+    let server = common::mock_server().await;
+    let dir = tempfile::tempdir().unwrap();
+    // expires inside the refresher's buffer, so the first check refreshes it.
+    let om = common::authorized_oauth_manager(&server, dir.path(), 60);
+
+    OauthManager::spawn_token_refresher(om.clone(), Duration::from_millis(10)).await;
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while !server
+            .requests()
+            .iter()
+            .any(|r| r.starts_with("POST /v1/oauth/token"))
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(om.lock().await.get_unexpired_token().is_some());
+}
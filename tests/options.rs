@@ -0,0 +1,50 @@
+mod common;
+
+use schwab_auto_trader::{
+    config::TradingCollection,
+    schwab::{
+        client::SchwabClient,
+        endpoints::Endpoints,
+        options::select_collection_contract,
+        schemas::market_data::option_chains::{ContractSelection, PutCall},
+    },
+};
+
+#[tokio::test]
+async fn test_select_put_for_collection() {
+    let server = common::mock_server().await;
+    let client = SchwabClient::new(common::token(), Endpoints::new(&server.base_url()));
+
+    let expirations = client.get_expiration_chain("VTI").await.unwrap();
+    assert_eq!(
+        expirations
+            .expiration_list
+            .iter()
+            .map(|e| e.days_to_expiration)
+            .collect::<Vec<i64>>(),
+        vec![9, 37, 65]
+    );
+
+    // VXUS has no chain in the fixtures, so the contract comes from its alternate.
+    let collection = TradingCollection {
+        id: "us".to_string(),
+        collection: vec!["VXUS".to_string(), "VTI".to_string()],
+    };
+    let contract = select_collection_contract(
+        &client,
+        &collection,
+        &ContractSelection {
+            put_call: PutCall::Put,
+            target_delta: -0.30,
+            min_days_to_expiration: 30,
+            max_days_to_expiration: 60,
+        },
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(contract.put_call, PutCall::Put);
+    assert_eq!(contract.strike_price, 245.0);
+    assert_eq!(contract.days_to_expiration, 37);
+    assert_eq!(contract.delta(), Some(-0.31));
+}
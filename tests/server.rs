@@ -0,0 +1,138 @@
+mod common;
+
+use schwab_auto_trader::{
+    schwab::{
+        account_manager::AccountManager, endpoints::Endpoints,
+        market_overview::MarketOverviewConfig,
+    },
+    server::server,
+};
+use std::{path::Path, sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+
+fn write_certificate(dir: &Path) -> (String, String) {
+    let rcgen::CertifiedKey { cert, key_pair } =
+        rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert_path = dir.join("cert.pem");
+    let key_path = dir.join("key.pem");
+    std::fs::write(&cert_path, cert.pem()).unwrap();
+    std::fs::write(&key_path, key_pair.serialize_pem()).unwrap();
+    (
+        cert_path.to_str().unwrap().to_string(),
+        key_path.to_str().unwrap().to_string(),
+    )
+}
+
+fn free_address() -> std::net::SocketAddr {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+async fn get(url: &str) -> String {
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .http2_prior_knowledge()
+        .build()
+        .unwrap();
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            match client.get(url).send().await {
+                Ok(r) => break r.text().await.unwrap(),
+                Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+            }
+        }
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_root_shows_account_data() {
+    let mock = common::mock_server().await;
+    let dir = tempfile::tempdir().unwrap();
+    let (cert_path, key_path) = write_certificate(dir.path());
+    let om = common::authorized_oauth_manager(&mock, dir.path(), 1800);
+    let am = Arc::new(Mutex::new(
+        AccountManager::new(
+            common::trading_config(),
+            Endpoints::new(&mock.base_url()),
+            om.clone(),
+        )
+        .unwrap()
+        .with_market_overview(MarketOverviewConfig::default()),
+    ));
+    let mut watcher = am.lock().await.account_data_watcher();
+    am.lock()
+        .await
+        .init(Duration::from_millis(50))
+        .await
+        .unwrap();
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        watcher.wait_for(|ad| ad.total_account_value > 0.0 && ad.market_overview.len() == 3),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    let addr = free_address();
+    let cancel_token = tokio_util::sync::CancellationToken::new();
+    let jh = tokio::spawn(server::run_server(
+        addr,
+        om,
+        am,
+        cancel_token.clone(),
+        cert_path,
+        key_path,
+    ));
+
+    let body = get(&format!("https://{}/", addr)).await;
+    assert!(body.contains("8700"));
+    assert!(body.contains("67.57"));
+    assert!(body.contains("Retirement"));
+    assert!(body.contains("Vanguard Total Stock Market ETF (VTI)"));
+    assert!(body.contains("Dow Jones movers"));
+    assert!(body.contains("S&amp;P 500 movers"));
+    assert!(body.contains("NVDA 121.41 (4.23%)"));
+    // Only gainers are listed by default.
+    assert!(!body.contains("INTC"));
+
+    cancel_token.cancel();
+    jh.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_root_without_token_links_to_authorize() {
+    let mock = common::mock_server().await;
+    let dir = tempfile::tempdir().unwrap();
+    let (cert_path, key_path) = write_certificate(dir.path());
+    let om = common::oauth_manager(&mock, dir.path());
+    let am = Arc::new(Mutex::new(
+        AccountManager::new(
+            common::trading_config(),
+            Endpoints::new(&mock.base_url()),
+            om.clone(),
+        )
+        .unwrap(),
+    ));
+
+    let addr = free_address();
+    let cancel_token = tokio_util::sync::CancellationToken::new();
+    let jh = tokio::spawn(server::run_server(
+        addr,
+        om,
+        am,
+        cancel_token.clone(),
+        cert_path,
+        key_path,
+    ));
+
+    let body = get(&format!("https://{}/", addr)).await;
+    assert!(body.contains(&format!("{}/v1/oauth/authorize", mock.base_url())));
+
+    cancel_token.cancel();
+    jh.await.unwrap().unwrap();
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use schwab_auto_trader::{
+    mock::{schwab_api::MockSchwabServer, streamer::MockStreamer},
+    schwab::{
+        client::SchwabClient,
+        endpoints::Endpoints,
+        schemas::streamer::{Command, Service},
+        streamer::{StreamEvent, Streamer, StreamerConfig},
+    },
+};
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+async fn start() -> (MockSchwabServer, MockStreamer, Streamer) {
+    let server = common::mock_server().await;
+    let mock_streamer =
+        MockStreamer::start("127.0.0.1:0".parse().unwrap(), Duration::from_millis(100))
+            .await
+            .unwrap();
+    server.set_streamer_url(&mock_streamer.url());
+    let streamer = Streamer::start(
+        SchwabClient::new(common::token(), Endpoints::new(&server.base_url())),
+        StreamerConfig {
+            heartbeat_timeout_secs: 1,
+            initial_backoff_ms: 10,
+            max_backoff_ms: 100,
+        },
+    );
+    (server, mock_streamer, streamer)
+}
+
+async fn next_event(
+    events: &mut broadcast::Receiver<StreamEvent>,
+    matches: impl Fn(&StreamEvent) -> bool,
+) -> StreamEvent {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let event = events.recv().await.unwrap();
+            if matches(&event) {
+                return event;
+            }
+        }
+    })
+    .await
+    .unwrap()
+}
+
+async fn wait_for_subscription(mock_streamer: &MockStreamer, service: Service, symbols: &[&str]) {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while mock_streamer.subscriptions(service) != symbols {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_quotes_and_charts_are_streamed() {
+    let (_server, mock_streamer, streamer) = start().await;
+    let mut events = streamer.events();
+
+    streamer
+        .subscribe(Service::LevelOneEquities, &["VTI".to_string()])
+        .unwrap();
+    streamer
+        .subscribe(
+            Service::LevelOneEquities,
+            &["VTI".to_string(), "VXUS".to_string()],
+        )
+        .unwrap();
+    streamer
+        .subscribe(Service::ChartEquity, &["VTI".to_string()])
+        .unwrap();
+    wait_for_subscription(&mock_streamer, Service::LevelOneEquities, &["VTI", "VXUS"]).await;
+    wait_for_subscription(&mock_streamer, Service::ChartEquity, &["VTI"]).await;
+
+    mock_streamer.push(
+        Service::LevelOneEquities,
+        json!({"key": "VTI", "1": 229.9, "2": 230.1, "3": 230.0}),
+    );
+    mock_streamer.push(
+        Service::LevelOneEquities,
+        json!({"key": "VTI", "3": 230.05}),
+    );
+    // Not subscribed, so never sent.
+    mock_streamer.push(Service::LevelOneEquities, json!({"key": "SCHD", "3": 27.8}));
+    let StreamEvent::Quote(quote) = next_event(
+        &mut events,
+        |e| matches!(e, StreamEvent::Quote(q) if q.last_price == Some(230.05)),
+    )
+    .await
+    else {
+        unreachable!()
+    };
+    assert_eq!(quote.bid_price, Some(229.9));
+    assert_eq!(quote.price(), Some(230.0));
+    assert_eq!(streamer.quotes().borrow()["VTI"], quote);
+    assert!(!streamer.quotes().borrow().contains_key("SCHD"));
+
+    mock_streamer.push(
+        Service::ChartEquity,
+        json!({"key": "VTI", "1": 230.0, "2": 230.5, "3": 229.5, "4": 230.2, "5": 1500.0,
+               "6": 1, "7": 1736173800000i64, "8": 20094}),
+    );
+    let StreamEvent::Chart(chart) =
+        next_event(&mut events, |e| matches!(e, StreamEvent::Chart(_))).await
+    else {
+        unreachable!()
+    };
+    assert_eq!(chart.candle().close, 230.2);
+
+    let requests = mock_streamer.requests();
+    assert_eq!(requests[0].command, Command::Login);
+    assert_eq!(
+        requests[0].parameters["Authorization"],
+        common::fixtures().token["access_token"]
+    );
+    assert_eq!(requests[0].customer_id, "a1b2c3d4e5f60718293a4b5c6d7e8f90");
+    let commands: Vec<(Service, Command, &str)> = requests[1..]
+        .iter()
+        .map(|r| (r.service, r.command, r.parameters["keys"].as_str()))
+        .collect();
+    assert_eq!(
+        commands,
+        vec![
+            (Service::LevelOneEquities, Command::Subs, "VTI"),
+            (Service::LevelOneEquities, Command::Add, "VXUS"),
+            (Service::ChartEquity, Command::Subs, "VTI"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_streamer_reconnects_and_resubscribes() {
+    let (_server, mock_streamer, streamer) = start().await;
+    let mut events = streamer.events();
+    streamer
+        .subscribe(
+            Service::LevelOneEquities,
+            &["VTI".to_string(), "VXUS".to_string()],
+        )
+        .unwrap();
+    wait_for_subscription(&mock_streamer, Service::LevelOneEquities, &["VTI", "VXUS"]).await;
+
+    // The connection is closed by the streamer.
+    mock_streamer.drop_connections();
+    next_event(&mut events, |e| matches!(e, StreamEvent::Disconnected(_))).await;
+    next_event(&mut events, |e| matches!(e, StreamEvent::Connected)).await;
+    wait_for_subscription(&mock_streamer, Service::LevelOneEquities, &["VTI", "VXUS"]).await;
+    assert_eq!(mock_streamer.logins(), 2);
+
+    // The connection goes quiet.
+    mock_streamer.pause_heartbeats(true);
+    let StreamEvent::Disconnected(reason) =
+        next_event(&mut events, |e| matches!(e, StreamEvent::Disconnected(_))).await
+    else {
+        unreachable!()
+    };
+    assert!(reason.contains("heartbeat"), "{}", reason);
+    mock_streamer.pause_heartbeats(false);
+    next_event(&mut events, |e| matches!(e, StreamEvent::Connected)).await;
+    wait_for_subscription(&mock_streamer, Service::LevelOneEquities, &["VTI", "VXUS"]).await;
+    assert_eq!(mock_streamer.logins(), 3);
+
+    mock_streamer.push(Service::LevelOneEquities, json!({"key": "VXUS", "3": 61.2}));
+    next_event(
+        &mut events,
+        |e| matches!(e, StreamEvent::Quote(q) if q.symbol == "VXUS" && q.last_price == Some(61.2)),
+    )
+    .await;
+}